@@ -0,0 +1,131 @@
+use crate::input::{InputType, WindowEvent};
+
+/// Presses and releases the joystick 1 button every few frames while it is engaged. Engaging and
+/// disengaging is toggled by the `Joystick1AutoFire` input.
+///
+/// Timing is based on the system clock rather than wall time, so the generated events land on the
+/// same clock cycles every run and recordings replay the same way.
+pub struct AutoFire {
+    frames_per_toggle: usize,
+    // The length of a frame in the program's TV standard
+    clocks_per_frame: usize,
+    engaged: bool,
+    // Whether the auto-fire key is down. Key repeat sends more presses while it's held, which
+    // shouldn't toggle again.
+    held: bool,
+    pressed: bool,
+    next_toggle_frame: usize,
+}
+
+impl AutoFire {
+    pub fn new(frames_per_toggle: usize, clocks_per_frame: usize) -> Self {
+        Self {
+            frames_per_toggle: frames_per_toggle.max(1),
+            clocks_per_frame,
+            engaged: false,
+            held: false,
+            pressed: false,
+            next_toggle_frame: 0,
+        }
+    }
+
+    /// Toggles auto-fire when the auto-fire input is pressed, once per press.
+    pub fn input_event(&mut self, event: &WindowEvent, clocks: usize) {
+        match event {
+            WindowEvent::InputStart(InputType::Joystick1AutoFire) if !self.held => {
+                self.held = true;
+                self.engaged = !self.engaged;
+                self.next_toggle_frame = clocks / self.clocks_per_frame;
+            }
+            WindowEvent::InputEnd(InputType::Joystick1AutoFire) => self.held = false,
+            _ => (),
+        }
+    }
+
+    /// Returns the button event that is due at `clocks`, if there is one.
+    pub fn update(&mut self, clocks: usize) -> WindowEvent {
+        if !self.engaged {
+            if self.pressed {
+                self.pressed = false;
+                return WindowEvent::InputEnd(InputType::Joystick1Button);
+            }
+            return WindowEvent::None;
+        }
+
+        let frame = clocks / self.clocks_per_frame;
+        if frame < self.next_toggle_frame {
+            return WindowEvent::None;
+        }
+        self.next_toggle_frame = frame + self.frames_per_toggle;
+        self.pressed = !self.pressed;
+        if self.pressed {
+            WindowEvent::InputStart(InputType::Joystick1Button)
+        } else {
+            WindowEvent::InputEnd(InputType::Joystick1Button)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::system::tia::CLOCKS_PER_FRAME;
+
+    #[test]
+    fn test_auto_fire_pattern() {
+        let mut auto_fire = AutoFire::new(2, CLOCKS_PER_FRAME);
+        assert_eq!(auto_fire.update(0), WindowEvent::None);
+
+        auto_fire.input_event(&WindowEvent::InputStart(InputType::Joystick1AutoFire), 0);
+        let events: Vec<WindowEvent> = (0..8)
+            .map(|frame| auto_fire.update(frame * CLOCKS_PER_FRAME))
+            .collect();
+        assert_eq!(
+            events,
+            vec![
+                WindowEvent::InputStart(InputType::Joystick1Button),
+                WindowEvent::None,
+                WindowEvent::InputEnd(InputType::Joystick1Button),
+                WindowEvent::None,
+                WindowEvent::InputStart(InputType::Joystick1Button),
+                WindowEvent::None,
+                WindowEvent::InputEnd(InputType::Joystick1Button),
+                WindowEvent::None,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_auto_fire_releases_when_disengaged() {
+        let mut auto_fire = AutoFire::new(2, CLOCKS_PER_FRAME);
+        let toggle = WindowEvent::InputStart(InputType::Joystick1AutoFire);
+        let release = WindowEvent::InputEnd(InputType::Joystick1AutoFire);
+        auto_fire.input_event(&toggle, 0);
+        assert_eq!(
+            auto_fire.update(0),
+            WindowEvent::InputStart(InputType::Joystick1Button)
+        );
+
+        auto_fire.input_event(&release, 0);
+        auto_fire.input_event(&toggle, CLOCKS_PER_FRAME);
+        assert_eq!(
+            auto_fire.update(CLOCKS_PER_FRAME),
+            WindowEvent::InputEnd(InputType::Joystick1Button)
+        );
+        assert_eq!(auto_fire.update(CLOCKS_PER_FRAME * 4), WindowEvent::None);
+    }
+
+    #[test]
+    fn test_key_repeat_toggles_once() {
+        let mut auto_fire = AutoFire::new(2, CLOCKS_PER_FRAME);
+        let toggle = WindowEvent::InputStart(InputType::Joystick1AutoFire);
+        auto_fire.input_event(&toggle, 0);
+        auto_fire.input_event(&toggle, CLOCKS_PER_FRAME);
+        assert!(auto_fire.engaged);
+
+        // Pressing again after letting go disengages it
+        auto_fire.input_event(&WindowEvent::InputEnd(InputType::Joystick1AutoFire), 0);
+        auto_fire.input_event(&toggle, CLOCKS_PER_FRAME * 2);
+        assert!(!auto_fire.engaged);
+    }
+}
@@ -0,0 +1,177 @@
+use std::fs;
+use std::str::FromStr;
+
+use crate::system::cartridge::Cartridge;
+use crate::system::System;
+use crate::Result;
+
+/// A list of patches read from a cheat file. Each line of the file is `ADDR=VALUE` in hex.
+///
+/// A plain line is a ROM patch, applied once to the program when it is loaded. It patches every
+/// bank that can be switched in at the address, unless it starts with `BANK:` to patch just that
+/// bank. A line starting with `!` is a poke, written to the bus after every frame so the program
+/// can't change it. Blank lines and lines starting with `#` are ignored.
+///
+/// ```text
+/// # Infinite lives
+/// !0085=03
+/// # Skip the title screen
+/// F123=EA
+/// # Only in the second bank
+/// 1:F456=EA
+/// ```
+#[derive(Debug, Default, PartialEq)]
+pub struct Cheats {
+    // The bank, if one was given, the address, and the value
+    patches: Vec<(Option<usize>, u16, u8)>,
+    pokes: Vec<(u16, u8)>,
+}
+
+impl Cheats {
+    pub fn load(file_name: &str) -> Result<Self> {
+        let file = fs::read_to_string(file_name).map_err(|e| e.to_string())?;
+        Ok(file.parse()?)
+    }
+
+    /// Applies the ROM patches to the cartridge, in the banks they're for
+    pub fn patch_cartridge(&self, cartridge: &mut Cartridge) -> Result<()> {
+        let bank_size = cartridge.bank_size();
+        for &(bank, addr, value) in &self.patches {
+            let offsets = match bank {
+                Some(bank) => vec![bank * bank_size + (addr & 0x0FFF) as usize % bank_size],
+                None => cartridge.rom_offsets(addr),
+            };
+            for offset in offsets {
+                let byte = cartridge
+                    .rom_mut()
+                    .get_mut(offset)
+                    .ok_or_else(|| format!("Cheat for ${addr:04X} is past the end of the ROM"))?;
+                *byte = value;
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies the pokes to the system. Called once per frame.
+    pub fn poke(&self, system: &mut System) {
+        for &(addr, value) in &self.pokes {
+            system.write(addr, value);
+        }
+    }
+}
+
+impl FromStr for Cheats {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let mut cheats = Cheats::default();
+        for line in s.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (is_poke, line) = match line.strip_prefix('!') {
+                Some(line) => (true, line),
+                None => (false, line),
+            };
+            let (bank, line) = match line.split_once(':') {
+                // Pokes go through the bus, to whatever bank is switched in
+                Some(_) if is_poke => return Err(format!("Pokes can't have a bank: {line}")),
+                Some((bank, line)) => {
+                    let bank = usize::from_str_radix(bank.trim(), 16)
+                        .map_err(|_| format!("Invalid cheat bank: {bank}"))?;
+                    (Some(bank), line)
+                }
+                None => (None, line),
+            };
+            let (addr, value) = line
+                .split_once('=')
+                .ok_or_else(|| format!("Invalid cheat: {line}"))?;
+            let addr = u16::from_str_radix(addr.trim(), 16)
+                .map_err(|_| format!("Invalid cheat address: {addr}"))?;
+            let value = u8::from_str_radix(value.trim(), 16)
+                .map_err(|_| format!("Invalid cheat value: {value}"))?;
+            if is_poke {
+                cheats.pokes.push((addr, value));
+            } else {
+                cheats.patches.push((bank, addr, value));
+            }
+        }
+        Ok(cheats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::system::cartridge::BankSwitching;
+    use crate::system::instructions::Instruction;
+
+    #[test]
+    fn test_parse_cheats() {
+        let cheats: Cheats = "# comment\n\nF123=EA\n!0085 = 3\n1:F456=60\n"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            cheats,
+            Cheats {
+                patches: vec![(None, 0xF123, 0xEA), (Some(1), 0xF456, 0x60)],
+                pokes: vec![(0x0085, 0x03)],
+            }
+        );
+        assert!("0085".parse::<Cheats>().is_err());
+        assert!("0085=FFF".parse::<Cheats>().is_err());
+        assert!("X:F123=EA".parse::<Cheats>().is_err());
+        assert!("!1:0085=03".parse::<Cheats>().is_err());
+    }
+
+    #[test]
+    fn test_patch_cartridge() {
+        let cheats: Cheats = "F001=42".parse().unwrap();
+        let mut cartridge = Cartridge::new(vec![0; 4096], BankSwitching::None).unwrap();
+        cheats.patch_cartridge(&mut cartridge).unwrap();
+        assert_eq!(cartridge.rom()[1], 0x42);
+    }
+
+    #[test]
+    fn test_patch_bank_switched_cartridge() {
+        let cheats: Cheats = "F001=42\n1:F002=43\n".parse().unwrap();
+        let mut cartridge = Cartridge::new(vec![0; 8192], BankSwitching::F8).unwrap();
+        cheats.patch_cartridge(&mut cartridge).unwrap();
+        // Without a bank, every bank that maps the address is patched
+        assert_eq!(cartridge.rom()[0x0001], 0x42);
+        assert_eq!(cartridge.rom()[0x1001], 0x42);
+        // With one, only that bank
+        assert_eq!(cartridge.rom()[0x0002], 0);
+        assert_eq!(cartridge.rom()[0x1002], 0x43);
+        // F8 carts power on in the last bank, which has both patches
+        assert_eq!(cartridge.program()[1..3], [0x42, 0x43]);
+
+        // E0 banks are 1K slices
+        let cheats: Cheats = "5:F402=44".parse().unwrap();
+        let mut cartridge = Cartridge::new(vec![0; 8192], BankSwitching::E0).unwrap();
+        cheats.patch_cartridge(&mut cartridge).unwrap();
+        assert_eq!(cartridge.rom()[5 * 0x400 + 2], 0x44);
+
+        let cheats: Cheats = "2:F000=EA".parse().unwrap();
+        let mut cartridge = Cartridge::new(vec![0; 8192], BankSwitching::F8).unwrap();
+        assert!(cheats.patch_cartridge(&mut cartridge).is_err());
+    }
+
+    #[test]
+    fn test_poke_overrides_program_writes() {
+        let cheats: Cheats = "!0080=07".parse().unwrap();
+        let mut program = [0; 4096];
+        // LDA #$01; STA $80
+        program[0..4].copy_from_slice(&[0xA9, 0x01, 0x85, 0x80]);
+        let mut system = System::new(program);
+
+        for _ in 0..2 {
+            let instruction: Instruction = system.next_byte().try_into().unwrap();
+            system.execute(instruction).unwrap();
+        }
+        assert_eq!(system.peek(0x0080), 0x01);
+
+        cheats.poke(&mut system);
+        assert_eq!(system.peek(0x0080), 0x07);
+    }
+}
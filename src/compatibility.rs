@@ -0,0 +1,128 @@
+use std::any::Any;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::panic;
+use std::path::Path;
+
+use crate::error;
+use crate::system::cartridge::{BankSwitching, Cartridge};
+use crate::system::tia::CLOCKS_PER_FRAME;
+use crate::system::System;
+use crate::Result;
+
+/// How many frames each ROM runs for when checking compatibility
+pub const COMPATIBILITY_FRAMES: usize = 60;
+
+/// The first thing a ROM hit that stopped it from running
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Compatibility {
+    /// Ran every frame without stopping
+    Clean,
+    /// Stopped with an emulation error, like an unknown opcode or an unmapped address
+    Error(String),
+    /// Reached a part of the emulator that panics with `todo!` or `unimplemented!`
+    Unimplemented(String),
+}
+
+/// Runs a program for a number of frames, catching the panics from the parts of the emulator that
+/// aren't written yet instead of letting them take down the caller. The system is deterministic,
+/// so the same program always gives the same result.
+pub fn check_program(program: [u8; 4096], frames: usize) -> Compatibility {
+    let run = panic::catch_unwind(move || -> error::Result<()> {
+        let mut system = System::new(program);
+        // The same result whatever the build
+        system.check_pc = true;
+        while system.clocks < frames * CLOCKS_PER_FRAME {
+            system.step()?;
+        }
+        Ok(())
+    });
+    match run {
+        Ok(Ok(())) => Compatibility::Clean,
+        Ok(Err(e)) => Compatibility::Error(e.to_string()),
+        Err(payload) => Compatibility::Unimplemented(panic_message(payload)),
+    }
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_owned()
+    }
+}
+
+/// The results of checking every ROM in a directory, sorted by file name
+#[derive(Debug, Default)]
+pub struct CompatibilityReport {
+    pub results: Vec<(String, Compatibility)>,
+}
+
+impl CompatibilityReport {
+    pub fn check_directory(dir: impl AsRef<Path>, frames: usize) -> Result<Self> {
+        let mut results = vec![];
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if !path.is_file() {
+                continue;
+            }
+            let name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let result = match Cartridge::new(fs::read(&path)?, BankSwitching::None) {
+                Ok(cartridge) => check_program(cartridge.program(), frames),
+                Err(e) => Compatibility::Error(e.to_string()),
+            };
+            results.push((name, result));
+        }
+        results.sort();
+        Ok(Self { results })
+    }
+
+    /// Every distinct failure and the ROMs that hit it, the most common first. These are the
+    /// features worth working on next.
+    pub fn failures(&self) -> Vec<(&Compatibility, Vec<&str>)> {
+        let mut failures: BTreeMap<&Compatibility, Vec<&str>> = BTreeMap::new();
+        for (name, result) in &self.results {
+            if *result != Compatibility::Clean {
+                failures.entry(result).or_default().push(name);
+            }
+        }
+        let mut failures: Vec<_> = failures.into_iter().collect();
+        failures.sort_by_key(|(_, names)| std::cmp::Reverse(names.len()));
+        failures
+    }
+}
+
+impl fmt::Display for Compatibility {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Clean => write!(f, "clean"),
+            Self::Error(message) => write!(f, "error: {message}"),
+            Self::Unimplemented(message) => write!(f, "unimplemented: {message}"),
+        }
+    }
+}
+
+impl fmt::Display for CompatibilityReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, result) in &self.results {
+            writeln!(f, "{name}: {result}")?;
+        }
+        let clean = self
+            .results
+            .iter()
+            .filter(|(_, result)| *result == Compatibility::Clean)
+            .count();
+        writeln!(f)?;
+        writeln!(f, "{clean} of {} ROMs ran clean", self.results.len())?;
+        for (failure, names) in self.failures() {
+            writeln!(f, "{:>4}  {failure}", names.len())?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,88 @@
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+use crate::system::System;
+use crate::Result;
+
+pub const CRASH_REPORT_FILE: &str = "stanley-crash.txt";
+
+/// Writes everything we know about the state of the system when emulation failed, to be attached
+/// to bug reports.
+pub fn write_crash_report(system: &System, error: &str, out: &mut impl Write) -> Result<()> {
+    let chip = &system.chip;
+    writeln!(out, "Stanley crash report")?;
+    writeln!(out, "Error: {error}")?;
+    writeln!(out, "Clocks: {}", system.clocks)?;
+    writeln!(out)?;
+
+    writeln!(out, "CPU")?;
+    writeln!(
+        out,
+        "A: {:02X}  X: {:02X}  Y: {:02X}  SP: {:02X}  PC: {:04X}",
+        chip.a, chip.x, chip.y, chip.sp, chip.pc
+    )?;
+    writeln!(out, "Status: {:08b} (NV-BDIZC)", system.status())?;
+    writeln!(out)?;
+
+    writeln!(out, "RAM")?;
+    for (row, bytes) in system.memory.chunks(16).enumerate() {
+        let bytes: Vec<String> = bytes.iter().map(|byte| format!("{byte:02X}")).collect();
+        writeln!(out, "{:04X}: {}", 0x80 + row * 16, bytes.join(" "))?;
+    }
+
+    // The chip displays are written for the raw mode terminal in the debugger
+    let riot = system.riot.to_string().replace('\r', "");
+    let tia = system.tia.to_string().replace('\r', "");
+    writeln!(out, "{}", riot.trim_end())?;
+    writeln!(out, "{}", tia.trim_end())?;
+    writeln!(out)?;
+
+    writeln!(out, "Recent PCs (oldest first)")?;
+    let pcs: Vec<String> = system
+        .recent_pcs
+        .iter()
+        .map(|pc| format!("{pc:04X}"))
+        .collect();
+    writeln!(out, "{}", pcs.join(" "))?;
+    Ok(())
+}
+
+/// Writes the crash report to `path`.
+pub fn save_crash_report(system: &System, error: &str, path: impl AsRef<Path>) -> Result<()> {
+    let mut file = fs::File::create(path)?;
+    write_crash_report(system, error, &mut file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crash_report() {
+        let mut program = [0; 4096];
+        // LDA #$42; STA $81; KIL
+        program[0..5].copy_from_slice(&[0xA9, 0x42, 0x85, 0x81, 0x02]);
+        let mut system = System::new(program);
+        system.chip.sp = 0xFF;
+        let error = loop {
+            if let Err(e) = system.step() {
+                break e.to_string();
+            }
+        };
+
+        let mut out = Vec::new();
+        write_crash_report(&system, &error, &mut out).unwrap();
+        let report = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = report.lines().collect();
+
+        assert_eq!(lines[0], "Stanley crash report");
+        assert_eq!(lines[1], "Error: CPU jammed at $1004");
+        assert!(lines.contains(&"A: 42  X: 00  Y: 00  SP: FF  PC: 1005"));
+        assert!(lines.contains(&"0080: 00 42 00 00 00 00 00 00 00 00 00 00 00 00 00 00"));
+        assert!(lines.contains(&"00F0: 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00"));
+        assert!(report.contains("RIOT"));
+        assert!(report.contains("TIA"));
+        assert_eq!(lines[lines.len() - 1], "1000 1002 1004");
+    }
+}
@@ -0,0 +1,153 @@
+use std::str::FromStr;
+
+use crate::system::System;
+
+/// A register or flag a condition checks
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Operand {
+    A,
+    X,
+    Y,
+    Sp,
+    Pc,
+    N,
+    Z,
+    C,
+    V,
+    D,
+    I,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Comparison {
+    Equal,
+    NotEqual,
+}
+
+/// A check on a register or flag that a breakpoint only stops for when it holds, like `A==0x00`,
+/// `X!=$10`, or `C=1`. Values are in hex, with or without a `$` or `0x` in front, and flags are
+/// 0 or 1. `=` and `==` both check for equality.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Condition {
+    operand: Operand,
+    comparison: Comparison,
+    value: u16,
+}
+
+impl Condition {
+    pub fn eval(&self, system: &System) -> bool {
+        let chip = &system.chip;
+        let actual = match self.operand {
+            Operand::A => chip.a as u16,
+            Operand::X => chip.x as u16,
+            Operand::Y => chip.y as u16,
+            Operand::Sp => chip.sp as u16,
+            Operand::Pc => chip.pc,
+            Operand::N => chip.n as u16,
+            Operand::Z => chip.z as u16,
+            Operand::C => chip.c as u16,
+            Operand::V => chip.v as u16,
+            Operand::D => chip.d as u16,
+            Operand::I => chip.i as u16,
+        };
+        match self.comparison {
+            Comparison::Equal => actual == self.value,
+            Comparison::NotEqual => actual != self.value,
+        }
+    }
+}
+
+impl FromStr for Condition {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (operand, comparison, value) = if let Some((lhs, rhs)) = s.split_once("!=") {
+            (lhs, Comparison::NotEqual, rhs)
+        } else if let Some((lhs, rhs)) = s.split_once("==") {
+            (lhs, Comparison::Equal, rhs)
+        } else if let Some((lhs, rhs)) = s.split_once('=') {
+            (lhs, Comparison::Equal, rhs)
+        } else {
+            return Err(format!("Condition {s} needs a ==, =, or !="));
+        };
+
+        let operand = match operand.trim().to_uppercase().as_str() {
+            "A" => Operand::A,
+            "X" => Operand::X,
+            "Y" => Operand::Y,
+            "SP" => Operand::Sp,
+            "PC" => Operand::Pc,
+            "N" => Operand::N,
+            "Z" => Operand::Z,
+            "C" => Operand::C,
+            "V" => Operand::V,
+            "D" => Operand::D,
+            "I" => Operand::I,
+            operand => return Err(format!("Unknown register or flag {operand} in condition")),
+        };
+        let value = value.trim();
+        let digits = value
+            .strip_prefix('$')
+            .or_else(|| value.strip_prefix("0x"))
+            .unwrap_or(value);
+        let value = u16::from_str_radix(digits, 16)
+            .map_err(|_| format!("Invalid value {value} in condition"))?;
+
+        Ok(Self {
+            operand,
+            comparison,
+            value,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_condition() {
+        assert_eq!(
+            "A==0x00".parse(),
+            Ok(Condition {
+                operand: Operand::A,
+                comparison: Comparison::Equal,
+                value: 0,
+            })
+        );
+        assert_eq!(
+            "sp != $FD".parse(),
+            Ok(Condition {
+                operand: Operand::Sp,
+                comparison: Comparison::NotEqual,
+                value: 0xFD,
+            })
+        );
+        assert_eq!(
+            "C=1".parse(),
+            Ok(Condition {
+                operand: Operand::C,
+                comparison: Comparison::Equal,
+                value: 1,
+            })
+        );
+        assert!("A".parse::<Condition>().is_err());
+        assert!("Q==1".parse::<Condition>().is_err());
+        assert!("A==GG".parse::<Condition>().is_err());
+    }
+
+    #[test]
+    fn test_eval_condition() {
+        let mut system = System::new([0; 4096]);
+        system.chip.a = 0xFF;
+        system.chip.c = true;
+        let eval = |condition: &str| condition.parse::<Condition>().unwrap().eval(&system);
+
+        assert!(eval("A=FF"));
+        assert!(!eval("A!=FF"));
+        assert!(eval("X==0"));
+        assert!(eval("C==1"));
+        assert!(!eval("Z==1"));
+        assert!(eval("PC==1000"));
+    }
+}
@@ -0,0 +1,216 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::system::instructions::{AddressMode, Instruction};
+
+/// Where the 6502 reads its reset and break vectors, as indexes into the program
+pub(super) const RESET_VECTOR: usize = 0xFFC;
+pub(super) const BREAK_VECTOR: usize = 0xFFE;
+
+/// Disassembles a program mapped at $1000, returning the text of each byte keyed by its address.
+/// Instructions that can run are written out with their operands, using the symbols for the
+/// addresses that have one, and the address's symbol is put on the line before as a label. Every
+/// other byte is data, and is written as a number.
+pub fn disassemble(program: &[u8], symbols: &HashMap<u16, String>) -> BTreeMap<u16, String> {
+    let mut disassembly = BTreeMap::new();
+    let code = find_code(program);
+    let mut program_iter = program.iter().enumerate().peekable();
+
+    while let Some((index, byte)) = program_iter.next() {
+        let key = (index + 0x1000) as u16;
+        let key_str = symbols
+            .get(&key)
+            .map(|val| format!("{val}:\r\n  "))
+            .unwrap_or_else(|| "  ".to_owned());
+
+        let value = match Instruction::try_from(*byte) {
+            Ok(inst) if code.contains(&index) => format!(
+                "{}{} {}",
+                key_str,
+                inst,
+                inst.format_arguments(&mut program_iter, symbols, key)
+            ),
+            _ => format!("{}{}", key_str, byte),
+        };
+        disassembly.insert(key, value);
+    }
+    disassembly
+}
+
+/// Lays a disassembly out like an assembler listing. Each line starts with the address and bytes,
+/// like `F000: A9 00     LDA #$00`, and ends with the cycles the instruction takes. A `+` on the
+/// cycles means crossing a page can take longer. Addresses are shown in the $F000 mirror, where
+/// programs are usually assembled, and labels get a line of their own.
+pub fn listing(program: &[u8], disassembly: &BTreeMap<u16, String>) -> Vec<String> {
+    let code = find_code(program);
+    let mut lines = Vec::new();
+    let mut entries = disassembly.iter().peekable();
+    while let Some((&address, text)) = entries.next() {
+        let index = (address & 0x0FFF) as usize;
+        let end = entries
+            .peek()
+            .map_or(program.len(), |(&next, _)| (next & 0x0FFF) as usize);
+        let bytes = program[index..end.max(index)]
+            .iter()
+            .map(|byte| format!("{byte:02X}"))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let (label, text) = match text.split_once(":\r\n") {
+            Some((label, text)) => (Some(label), text),
+            None => (None, text.as_str()),
+        };
+        if let Some(label) = label {
+            lines.push(format!("{label}:"));
+        }
+        let cycles = match Instruction::try_from(program[index]) {
+            Ok(inst) if code.contains(&index) => match inst.base_cycles() {
+                (cycles, true) => format!("{cycles}+"),
+                (cycles, false) => cycles.to_string(),
+            },
+            _ => String::new(),
+        };
+        let address = address | 0xF000;
+        let line = format!("{address:04X}: {bytes:<8}  {:<16}  {cycles}", text.trim());
+        lines.push(line.trim_end().to_owned());
+    }
+    lines
+}
+
+/// Finds the index of every instruction in the program that can run, by following branches, jumps,
+/// and subroutine calls from the reset vector. Every other byte is data.
+pub(super) fn find_code(program: &[u8]) -> BTreeSet<usize> {
+    let byte = |index: usize| program.get(index).copied().unwrap_or(0) as usize;
+    let vector = |index: usize| ((byte(index + 1) << 8) | byte(index)) & 0x0FFF;
+    let mut code = BTreeSet::new();
+    let mut pending = vec![vector(RESET_VECTOR)];
+
+    while let Some(mut index) = pending.pop() {
+        while index < program.len() && !code.contains(&index) {
+            let inst: Instruction = match program[index].try_into() {
+                Ok(inst) => inst,
+                Err(_) => break,
+            };
+            let next = index + 1 + inst.address_mode().operand_bytes();
+            // The operands run off the end of the program
+            if next > program.len() {
+                break;
+            }
+            code.insert(index);
+
+            if let Some(target) = jump_target(&inst, program, index) {
+                if target & 0x1000 != 0 {
+                    pending.push((target & 0x0FFF) as usize);
+                }
+            }
+            // Execution doesn't carry on past these. Where an indirect jump goes depends on
+            // memory, so it isn't followed.
+            match inst {
+                Instruction::Brk(_) => {
+                    pending.push(vector(BREAK_VECTOR));
+                    break;
+                }
+                Instruction::Jmp(_)
+                | Instruction::Rts(_)
+                | Instruction::Rti(_)
+                | Instruction::Kil(_) => break,
+                _ => index = next,
+            }
+        }
+    }
+    code
+}
+
+/// The address a branch, JMP, or JSR at an index of the program goes to
+pub(super) fn jump_target(inst: &Instruction, program: &[u8], index: usize) -> Option<u16> {
+    let pc = (index + 0x1000) as u16;
+    let operand = |offset: usize| program.get(index + offset).copied().unwrap_or(0);
+    match (inst, inst.address_mode()) {
+        (_, AddressMode::Relative) => Some((pc + 2).wrapping_add(operand(1) as i8 as u16)),
+        (Instruction::Jmp(_) | Instruction::Jsr(_), AddressMode::Absolute) => {
+            Some(((operand(2) as u16) << 8) + operand(1) as u16)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A program of NOPs that starts at $F000
+    fn program() -> [u8; 4096] {
+        let mut program = [0xEA; 4096];
+        program[RESET_VECTOR..RESET_VECTOR + 2].copy_from_slice(&[0x00, 0xF0]);
+        program
+    }
+
+    #[test]
+    fn test_disassemble() {
+        let mut program = program();
+        // LDA #$0E; STA COLUBK; JMP start
+        program[0..7].copy_from_slice(&[0xA9, 0x0E, 0x85, 0x09, 0x4C, 0x00, 0xF0]);
+        let symbols = HashMap::from([(0x1000, "start".to_owned())]);
+        let disassembly = disassemble(&program, &symbols);
+
+        assert_eq!(disassembly[&0x1000], "start:\r\n  LDA #$0E");
+        assert_eq!(disassembly[&0x1002], "  STA $09");
+        assert_eq!(disassembly[&0x1004], "  JMP start");
+        // Nothing runs after the jump
+        assert_eq!(disassembly[&0x1007], "  234");
+    }
+
+    #[test]
+    fn test_listing() {
+        let mut program = program();
+        // start: LDA #$00; STA COLUBK; LDA $F100, X; JMP start
+        program[0..10]
+            .copy_from_slice(&[0xA9, 0x00, 0x85, 0x09, 0xBD, 0x00, 0xF1, 0x4C, 0x00, 0xF0]);
+        let symbols = HashMap::from([(0x1000, "start".to_owned())]);
+        let listing = listing(&program, &disassemble(&program, &symbols));
+
+        assert_eq!(
+            listing[..6],
+            [
+                "start:",
+                "F000: A9 00     LDA #$00          2",
+                "F002: 85 09     STA $09           3",
+                "F004: BD 00 F1  LDA $F100, X      4+",
+                "F007: 4C 00 F0  JMP start         3",
+                "F00A: EA        234",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_disassemble_short_program() {
+        // Without a reset vector the program runs from its start
+        let disassembly = disassemble(&[0xEA, 0x60], &HashMap::new());
+        assert_eq!(disassembly.len(), 2);
+        assert_eq!(disassembly[&0x1000], "  NOP ");
+        assert_eq!(disassembly[&0x1001], "  RTS ");
+    }
+
+    #[test]
+    fn test_disassemble_follows_flow() {
+        let mut program = program();
+        program[0..16].copy_from_slice(&[
+            // JSR sub; JMP $F000
+            0x20, 0x0A, 0xF0, 0x4C, 0x00, 0xF0,
+            // A table of bytes that would decode as LDA #$02; ORA ($03, X)
+            0xA9, 0x02, 0x01, 0x03, // sub: LDA table, X; BEQ done; RTS; done: RTS
+            0xBD, 0x06, 0xF0, 0xF0, 0x01, 0x60,
+        ]);
+        program[16] = 0x60;
+        let disassembly = disassemble(&program, &HashMap::new());
+        assert_eq!(disassembly[&0x1000], "  JSR $F00A");
+        assert_eq!(disassembly[&0x1003], "  JMP $F000");
+        assert_eq!(disassembly[&0x1006], "  169");
+        assert_eq!(disassembly[&0x1007], "  2");
+        assert_eq!(disassembly[&0x1008], "  1");
+        assert_eq!(disassembly[&0x100A], "  LDA $F006, X");
+        assert_eq!(disassembly[&0x100D], "  BEQ $1010");
+        assert_eq!(disassembly[&0x100F], "  RTS ");
+        assert_eq!(disassembly[&0x1010], "  RTS ");
+        // Nothing reaches the NOPs after the subroutine
+        assert_eq!(disassembly[&0x1011], "  234");
+    }
+}
@@ -0,0 +1,923 @@
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    fs,
+    io::{stdout, BufWriter, Write},
+    str::FromStr,
+    time::Duration,
+};
+
+use crossterm::style::Color;
+use crossterm::{
+    cursor,
+    event::{poll, read, Event as CTEvent, KeyCode, KeyEvent, KeyModifiers},
+    execute, queue,
+    style::{self, Print},
+    terminal::{self, ClearType},
+};
+
+pub mod condition;
+pub mod disasm;
+
+use self::condition::Condition;
+use self::disasm::{find_code, jump_target};
+use super::Result;
+use crate::system::{instructions::Instruction, System};
+use crate::watch::Watch;
+
+/// What the main loop should do after the debugger runs
+#[derive(Debug, PartialEq)]
+pub enum DebugAction {
+    /// Execute the next instruction
+    Execute,
+    /// Go around the loop again without executing anything
+    Wait,
+    /// Render the screen now, then go around the loop again without executing anything
+    Render,
+    /// The system was put back to an earlier state, so its clocks went back. Go around the loop
+    /// again without executing anything.
+    SteppedBack,
+    /// Stop running
+    Quit,
+}
+
+pub enum BreakPointType {
+    Number(u16),
+    Symbol(String),
+}
+
+pub fn try_parse_breakpoint(s: &str) -> std::result::Result<BreakPointType, String> {
+    Ok(match u16::from_str_radix(s, 16) {
+        Ok(int) => BreakPointType::Number(int),
+        Err(_) => BreakPointType::Symbol(s.to_owned()),
+    })
+}
+
+/// A place to pause, given as `LOCATION` or `LOCATION:CONDITION`. A breakpoint with a condition
+/// only pauses when the condition holds, see `Condition`.
+pub struct BreakPoint {
+    location: BreakPointType,
+    condition: Option<Condition>,
+}
+
+impl FromStr for BreakPoint {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let (location, condition) = match s.split_once(':') {
+            Some((location, condition)) => (location, Some(condition.parse()?)),
+            None => (s, None),
+        };
+        Ok(Self {
+            location: try_parse_breakpoint(location)?,
+            condition,
+        })
+    }
+}
+
+pub trait Debugger {
+    fn setup(
+        &mut self,
+        _program: [u8; 4096],
+        _breakpoints: Vec<BreakPoint>,
+        _symbol_file: Option<String>,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    fn debug_loop(&mut self, _system: &mut System) -> Result<DebugAction> {
+        Ok(DebugAction::Execute)
+    }
+
+    fn teardown(&self) -> super::Result<()> {
+        Ok(())
+    }
+
+    fn add_watch(&mut self, _watch: Watch) {}
+
+    /// Remembers the state before each of the last `steps` instructions, so they can be stepped
+    /// back through while paused
+    fn keep_history(&mut self, _steps: usize) {}
+
+    fn dump_disassembly(&mut self, _program: [u8; 4096], _labels: bool) {}
+}
+
+pub fn get_debugger(is_debug: bool) -> Box<dyn Debugger> {
+    if is_debug {
+        Box::new(ActiveDebugger::default())
+    } else {
+        Box::new(NullDebugger)
+    }
+}
+
+/// Reads the symbols out of a DASM symbol file. Each line has a symbol and its address in hex,
+/// either as `name address` like DASM's `.sym` files, or as `name = address` like its listings.
+/// Addresses can start with `$`, and anything after them, like DASM's flags, is ignored. Headers,
+/// comments, and lines that don't parse are skipped.
+fn parse_symbols(file: &str) -> HashMap<u16, String> {
+    file.lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            if line.starts_with("---") || line.starts_with(';') {
+                return None;
+            }
+            let (name, rest) = match line.split_once('=') {
+                Some((name, rest)) => (name.trim(), rest),
+                None => line.split_once(char::is_whitespace)?,
+            };
+            if name.is_empty() || name.contains(char::is_whitespace) {
+                return None;
+            }
+            let address = rest.split_whitespace().next()?;
+            let address = address.strip_prefix('$').unwrap_or(address);
+            let address = u16::from_str_radix(address, 16).ok()? & 0x1FFF;
+            Some((address, name.to_owned()))
+        })
+        .collect()
+}
+
+/// Formats memory as a hex dump starting at an address, 16 bytes to a row, with the printable
+/// bytes shown as ASCII at the end of each row
+fn format_memory(memory: &[u8], start: u16) -> String {
+    memory
+        .chunks(16)
+        .enumerate()
+        .map(|(row, bytes)| {
+            let hex = bytes
+                .iter()
+                .map(|byte| format!("{byte:02X}"))
+                .collect::<Vec<_>>()
+                .join(" ");
+            let ascii: String = bytes
+                .iter()
+                .map(|&byte| match byte {
+                    0x20..=0x7E => byte as char,
+                    _ => '.',
+                })
+                .collect();
+            let address = start as usize + row * 16;
+            format!("{address:04X}  {hex:<47}  {ascii}")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Where execution stops when stepping over the instruction at PC, if it's a subroutine call
+fn step_over_target(system: &System) -> Option<u16> {
+    match Instruction::try_from(system.peek(system.chip.pc)) {
+        Ok(inst @ Instruction::Jsr(_)) => Some(
+            system
+                .chip
+                .pc
+                .wrapping_add(inst.address_mode().byte_length())
+                & 0x1FFF,
+        ),
+        _ => None,
+    }
+}
+
+/// Edits a register or flag while paused. `a`, `x`, `y`, `p` and `s` prompt for a hex value to put
+/// in A, X, Y, PC or SP, and `N`, `Z`, `C`, `V`, `D` and `I` toggle a flag. Returns false when
+/// the key isn't an edit command.
+fn edit_registers(system: &mut System, code: KeyCode, keys: impl Iterator<Item = KeyCode>) -> bool {
+    let chip = &mut system.chip;
+    let flag = match code {
+        KeyCode::Char('N') => Some(&mut chip.n),
+        KeyCode::Char('Z') => Some(&mut chip.z),
+        KeyCode::Char('C') => Some(&mut chip.c),
+        KeyCode::Char('V') => Some(&mut chip.v),
+        KeyCode::Char('D') => Some(&mut chip.d),
+        KeyCode::Char('I') => Some(&mut chip.i),
+        _ => None,
+    };
+    if let Some(flag) = flag {
+        *flag = !*flag;
+        return true;
+    }
+
+    let name = match code {
+        KeyCode::Char('a') => "A",
+        KeyCode::Char('x') => "X",
+        KeyCode::Char('y') => "Y",
+        KeyCode::Char('p') => "PC",
+        KeyCode::Char('s') => "SP",
+        _ => return false,
+    };
+    let _ = execute!(
+        stdout(),
+        cursor::MoveToNextLine(1),
+        Print(format!("{name} = $"))
+    );
+    // Cancelling leaves the register as it was
+    if let Some(value) = read_hex(keys) {
+        match code {
+            KeyCode::Char('a') => chip.a = value as u8,
+            KeyCode::Char('x') => chip.x = value as u8,
+            KeyCode::Char('y') => chip.y = value as u8,
+            KeyCode::Char('p') => chip.pc = value,
+            _ => chip.sp = value as u8,
+        }
+    }
+    true
+}
+
+/// Reads a hex number up to Enter. Escape cancels.
+fn read_hex(keys: impl Iterator<Item = KeyCode>) -> Option<u16> {
+    read_line(keys).and_then(|line| u16::from_str_radix(&line, 16).ok())
+}
+
+/// Reads the characters typed up to Enter. Escape cancels.
+fn read_line(keys: impl Iterator<Item = KeyCode>) -> Option<String> {
+    let mut line = String::new();
+    for key in keys {
+        match key {
+            KeyCode::Enter => return Some(line),
+            KeyCode::Esc => return None,
+            KeyCode::Backspace => {
+                line.pop();
+            }
+            KeyCode::Char(c) => line.push(c),
+            _ => (),
+        }
+    }
+    None
+}
+
+/// The keys typed at a prompt, echoing each one
+fn prompt_keys() -> impl Iterator<Item = KeyCode> {
+    std::iter::from_fn(|| loop {
+        match read() {
+            Ok(CTEvent::Key(KeyEvent { code, .. })) => {
+                if let KeyCode::Char(c) = code {
+                    let _ = execute!(stdout(), Print(c));
+                }
+                return Some(code);
+            }
+            Ok(_) => continue,
+            Err(_) => return None,
+        }
+    })
+}
+
+pub struct NullDebugger;
+impl Debugger for NullDebugger {}
+
+/// Writes a line to a file for every instruction executed, for diffing against the logs of other
+/// 6502 emulators. Each line has the address, the instruction's bytes, the disassembly, the
+/// registers, and the clocks run so far.
+pub struct Tracer {
+    file: BufWriter<fs::File>,
+}
+
+impl Tracer {
+    pub fn create(file_name: &str) -> Result<Self> {
+        Ok(Self {
+            file: BufWriter::new(fs::File::create(file_name)?),
+        })
+    }
+
+    /// Traces the instruction the system is about to execute
+    pub fn trace(&mut self, system: &System) -> Result<()> {
+        writeln!(self.file, "{}", trace_line(system))?;
+        Ok(())
+    }
+}
+
+fn trace_line(system: &System) -> String {
+    let chip = &system.chip;
+    let pc = chip.pc;
+    let opcode = system.peek(pc);
+    let registers = format!(
+        "A:{:02X} X:{:02X} Y:{:02X} P:{:02X} SP:{:02X} CYC:{}",
+        chip.a,
+        chip.x,
+        chip.y,
+        system.status(),
+        chip.sp,
+        system.clocks
+    );
+    let (bytes, disassembly) = match Instruction::try_from(opcode) {
+        Ok(inst) => {
+            let bytes: Vec<u8> = (0..inst.address_mode().byte_length())
+                .map(|offset| system.peek(pc.wrapping_add(offset)))
+                .collect();
+            let arguments =
+                inst.format_arguments(&mut bytes[1..].iter().enumerate(), &HashMap::new(), pc);
+            (bytes, format!("{inst} {arguments}"))
+        }
+        Err(_) => (vec![opcode], "???".to_owned()),
+    };
+    let bytes = bytes
+        .iter()
+        .map(|byte| format!("{byte:02X}"))
+        .collect::<Vec<_>>()
+        .join(" ");
+    format!("{pc:04X}  {bytes:<8}  {disassembly:<16}  {registers}")
+}
+
+#[derive(Default)]
+pub struct ActiveDebugger {
+    disassembly: Option<BTreeMap<u16, String>>,
+    // The conditions of the breakpoints at each address, None for the ones that always pause
+    breakpoints: BTreeMap<u16, Vec<Option<Condition>>>,
+    symbol_map: HashMap<u16, String>,
+    in_breakpoint: bool,
+    // Where to stop after stepping over a subroutine call
+    step_over: Option<u16>,
+    // The address the disassembly is scrolled to, it follows PC when not set
+    view: Option<u16>,
+    watches: Vec<Watch>,
+    // Saved states from before the most recent instructions, newest last
+    history: VecDeque<Vec<u8>>,
+    history_steps: usize,
+    // Show the RAM under the registers
+    show_memory: bool,
+}
+
+impl ActiveDebugger {
+    fn disassemble(&mut self, program: [u8; 4096]) {
+        self.disassembly
+            .replace(disasm::disassemble(&program, &self.symbol_map));
+    }
+
+    /// Adds an `LXXXX` label to the symbol map for every branch, JMP, and JSR target that doesn't
+    /// already have a symbol, so jumps can be read without a symbol file.
+    fn generate_labels(&mut self, program: [u8; 4096]) {
+        for index in find_code(&program) {
+            let target = match program[index].try_into() {
+                Ok(inst) => jump_target(&inst, &program, index),
+                Err(_) => None,
+            };
+            if let Some(target) = target.filter(|target| target & 0x1000 != 0) {
+                let target = target & 0x1FFF;
+                self.symbol_map
+                    .entry(target)
+                    .or_insert_with(|| format!("L{target:04X}"));
+            }
+        }
+    }
+
+    /// Adds breakpoints, looking up the address of the ones given as a symbol. Symbols that aren't
+    /// in the symbol file are skipped.
+    fn add_breakpoints(&mut self, breakpoints: Vec<BreakPoint>) {
+        for breakpoint in breakpoints {
+            if let Some(address) = self.resolve(breakpoint.location) {
+                self.breakpoints
+                    .entry(address & 0x1FFF)
+                    .or_default()
+                    .push(breakpoint.condition);
+            }
+        }
+    }
+
+    /// The address of a location given as a number or a symbol
+    fn resolve(&self, location: BreakPointType) -> Option<u16> {
+        match location {
+            BreakPointType::Number(val) => Some(val),
+            BreakPointType::Symbol(sym) => {
+                self.symbol_map
+                    .iter()
+                    .find_map(|(&key, value)| if *value == sym { Some(key) } else { None })
+            }
+        }
+    }
+
+    /// Saves the state before the system executes an instruction, dropping the oldest state once
+    /// the history is full
+    fn record(&mut self, system: &System) {
+        if self.history_steps == 0 {
+            return;
+        }
+        if self.history.len() == self.history_steps {
+            self.history.pop_front();
+        }
+        self.history.push_back(system.save_state());
+    }
+
+    /// Puts the system back how it was before the last instruction it executed. Returns false
+    /// when there's no history left to go back through.
+    fn step_back(&mut self, system: &mut System) -> Result<bool> {
+        match self.history.pop_back() {
+            Some(state) => {
+                system.load_state(&state)?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// The disassembly lines shown around an address
+    fn view_lines(&self, center: u16) -> impl Iterator<Item = (&u16, &String)> {
+        self.disassembly
+            .as_ref()
+            .unwrap()
+            .range(center.saturating_sub(5)..center.saturating_add(5))
+    }
+
+    /// Moves the disassembly view one line up or down from an address
+    fn scroll(&mut self, from: u16, up: bool) {
+        let disassembly = self.disassembly.as_ref().unwrap();
+        let next = if up {
+            disassembly.range(..from).next_back()
+        } else {
+            disassembly.range(from.saturating_add(1)..).next()
+        };
+        if let Some((&key, _)) = next {
+            self.view = Some(key);
+        }
+    }
+
+    fn at_breakpoint(&self, system: &System) -> bool {
+        self.breakpoints
+            .get(&(system.chip.pc & 0x1FFF))
+            .is_some_and(|conditions| {
+                conditions
+                    .iter()
+                    .any(|condition| condition.is_none_or(|condition| condition.eval(system)))
+            })
+    }
+
+    fn parse_symbol_file(&mut self, symbol_file: Option<String>) -> Result<()> {
+        let Some(symbol_file) = symbol_file else {
+            return Ok(());
+        };
+        let file = fs::read_to_string(&symbol_file)
+            .map_err(|e| format!("Couldn't read symbol file {symbol_file}: {e}"))?;
+        self.symbol_map = parse_symbols(&file);
+        Ok(())
+    }
+}
+
+impl Debugger for ActiveDebugger {
+    fn setup(
+        &mut self,
+        program: [u8; 4096],
+        breakpoints: Vec<BreakPoint>,
+        symbol_file: Option<String>,
+    ) -> super::Result<()> {
+        let mut stdout = stdout();
+        self.parse_symbol_file(symbol_file)?;
+        self.disassemble(program);
+        self.add_breakpoints(breakpoints);
+        execute!(stdout, terminal::EnterAlternateScreen)?;
+        terminal::enable_raw_mode()?;
+        Ok(())
+    }
+
+    fn debug_loop(&mut self, system: &mut System) -> super::Result<DebugAction> {
+        let mut stdout = stdout();
+        queue!(
+            stdout,
+            style::ResetColor,
+            terminal::Clear(ClearType::All),
+            cursor::Hide,
+            cursor::MoveTo(0, 0),
+        )?;
+
+        queue!(
+            stdout,
+            style::SetForegroundColor(Color::White),
+            Print(format!("{}", system.chip)),
+            cursor::MoveToNextLine(1),
+            Print(format!("{}", system)),
+        )?;
+        queue!(stdout, cursor::MoveToNextLine(1),)?;
+        let riot = &system.riot;
+        queue!(stdout, Print(format!("{} ", riot)))?;
+        queue!(
+            stdout,
+            cursor::MoveToNextLine(1),
+            Print(format!("{} ", system.tia)),
+        )?;
+
+        if !self.watches.is_empty() {
+            queue!(stdout, cursor::MoveToNextLine(1), Print("Watches"))?;
+            for watch in &self.watches {
+                let value = watch.eval(system);
+                queue!(
+                    stdout,
+                    cursor::MoveToNextLine(1),
+                    Print(format!("{watch} = ${value:04X} ({value})")),
+                )?;
+            }
+            queue!(stdout, cursor::MoveToNextLine(1))?;
+        }
+
+        // The whole of RAM fits on the screen. The stack lives in it too, $0100-$01FF is a mirror.
+        if self.show_memory {
+            queue!(stdout, cursor::MoveToNextLine(1), Print("Memory"))?;
+            for line in format_memory(&system.memory, 0x80).lines() {
+                queue!(stdout, cursor::MoveToNextLine(1), Print(line))?;
+            }
+            queue!(stdout, cursor::MoveToNextLine(1))?;
+        }
+
+        queue!(stdout, cursor::MoveToNextLine(1), Print("Program"),)?;
+
+        let current_line = system.chip.pc & 0x1FFF;
+        for (&key, line) in self.view_lines(self.view.unwrap_or(current_line)) {
+            if current_line == key {
+                queue!(
+                    stdout,
+                    style::SetForegroundColor(Color::Black),
+                    style::SetBackgroundColor(Color::White)
+                )?;
+            }
+            queue!(
+                stdout,
+                cursor::MoveToNextLine(1),
+                Print(format!("{} ", line)),
+            )?;
+            if current_line == key {
+                queue!(
+                    stdout,
+                    style::SetForegroundColor(Color::White),
+                    style::SetBackgroundColor(Color::Black)
+                )?;
+            }
+        }
+        stdout.flush()?;
+
+        if self.step_over == Some(system.chip.pc & 0x1FFF) {
+            self.step_over = None;
+            self.in_breakpoint = true;
+        }
+        if self.at_breakpoint(system) || self.in_breakpoint {
+            if let Ok(CTEvent::Key(KeyEvent { code, modifiers })) = read() {
+                // Anything but moving the view goes back to following PC
+                let view = self.view.take().unwrap_or(current_line);
+                match code {
+                    KeyCode::Esc => return Err("User cancelled execution".into()),
+                    KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => {
+                        return Err("User cancelled execution".into());
+                    }
+                    KeyCode::Char('q') => return Ok(DebugAction::Quit),
+                    KeyCode::Up | KeyCode::Down => {
+                        self.in_breakpoint = true;
+                        self.scroll(view, code == KeyCode::Up);
+                        return Ok(DebugAction::Wait);
+                    }
+                    // Go to an address or symbol, or back to PC when nothing is entered
+                    KeyCode::Char('g') => {
+                        self.in_breakpoint = true;
+                        queue!(stdout, cursor::MoveToNextLine(1), Print("Go to: "))?;
+                        stdout.flush()?;
+                        if let Some(location) = read_line(prompt_keys()).filter(|s| !s.is_empty()) {
+                            let location = try_parse_breakpoint(&location)?;
+                            self.view = self.resolve(location).map(|addr| addr & 0x1FFF);
+                        }
+                        return Ok(DebugAction::Wait);
+                    }
+                    code if edit_registers(system, code, prompt_keys()) => {
+                        self.in_breakpoint = true;
+                        return Ok(DebugAction::Wait);
+                    }
+                    KeyCode::Char('n') => self.in_breakpoint = true,
+                    KeyCode::Char('c') => self.in_breakpoint = false,
+                    // Run a subroutine call to where it returns, anything else is a step
+                    KeyCode::Char('o') => match step_over_target(system) {
+                        Some(target) => {
+                            self.step_over = Some(target);
+                            self.in_breakpoint = false;
+                        }
+                        None => self.in_breakpoint = true,
+                    },
+                    // Move the beam by one cpu cycle without finishing the instruction
+                    KeyCode::Char('b') => {
+                        self.in_breakpoint = true;
+                        system.tick_color_clocks(3);
+                        return Ok(DebugAction::Wait);
+                    }
+                    KeyCode::Char('m') => {
+                        self.in_breakpoint = true;
+                        self.show_memory = !self.show_memory;
+                        return Ok(DebugAction::Wait);
+                    }
+                    // Undo the last instruction
+                    KeyCode::Char('u') => {
+                        self.in_breakpoint = true;
+                        return Ok(if self.step_back(system)? {
+                            DebugAction::SteppedBack
+                        } else {
+                            DebugAction::Wait
+                        });
+                    }
+                    // Run to the start of the next frame and show it
+                    KeyCode::Char('f') => {
+                        self.in_breakpoint = true;
+                        system.step_frame()?;
+                        return Ok(DebugAction::Render);
+                    }
+                    _ => {}
+                }
+                // Only the steps taken by hand are kept, saving the state of every instruction
+                // while running freely would slow everything down
+                self.record(system);
+                return Ok(DebugAction::Execute);
+            }
+        }
+
+        if let Ok(true) = poll(Duration::from_millis(1)) {
+            if let Ok(CTEvent::Key(KeyEvent { code, modifiers })) = read() {
+                if code == KeyCode::Esc
+                    || (code == KeyCode::Char('c') && modifiers.contains(KeyModifiers::CONTROL))
+                {
+                    return Err("User cancelled execution".into());
+                }
+            }
+        }
+        Ok(DebugAction::Execute)
+    }
+
+    fn teardown(&self) -> super::Result<()> {
+        let mut stdout = stdout();
+        execute!(
+            stdout,
+            style::ResetColor,
+            cursor::Show,
+            terminal::LeaveAlternateScreen
+        )?;
+        Ok(())
+    }
+
+    fn add_watch(&mut self, watch: Watch) {
+        self.watches.push(watch);
+    }
+
+    fn keep_history(&mut self, steps: usize) {
+        self.history_steps = steps;
+    }
+
+    fn dump_disassembly(&mut self, program: [u8; 4096], labels: bool) {
+        if labels {
+            self.generate_labels(program);
+        }
+        self.disassemble(program);
+        for line in disasm::listing(&program, self.disassembly.as_ref().unwrap()) {
+            println!("{line}")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A program of NOPs that starts at $F000
+    fn program() -> [u8; 4096] {
+        let mut program = [0xEA; 4096];
+        program[disasm::RESET_VECTOR..disasm::RESET_VECTOR + 2].copy_from_slice(&[0x00, 0xF0]);
+        program
+    }
+
+    #[test]
+    fn test_trace_line() {
+        let mut program = program();
+        program[0..2].copy_from_slice(&[0xA9, 0x0E]);
+        let mut system = System::new(program);
+        assert_eq!(
+            trace_line(&system),
+            "1000  A9 0E     LDA #$0E          A:00 X:00 Y:00 P:20 SP:00 CYC:0"
+        );
+        system.step().unwrap();
+        assert_eq!(
+            trace_line(&system),
+            "1002  EA        NOP               A:0E X:00 Y:00 P:20 SP:00 CYC:2"
+        );
+    }
+
+    #[test]
+    fn test_generate_labels() {
+        let mut program = program();
+        // LDX #$05; loop: DEX; BNE loop; JMP $F000
+        program[0..8].copy_from_slice(&[0xA2, 0x05, 0xCA, 0xD0, 0xFD, 0x4C, 0x00, 0xF0]);
+        let mut debugger = ActiveDebugger::default();
+        debugger.generate_labels(program);
+        debugger.disassemble(program);
+
+        let disassembly = debugger.disassembly.unwrap();
+        assert_eq!(disassembly[&0x1000], "L1000:\r\n  LDX #$05");
+        assert_eq!(disassembly[&0x1002], "L1002:\r\n  DEX ");
+        assert_eq!(disassembly[&0x1003], "  BNE L1002");
+        assert_eq!(disassembly[&0x1005], "  JMP L1000");
+    }
+
+    #[test]
+    fn test_generate_labels_keeps_symbols() {
+        let mut program = program();
+        program[0..2].copy_from_slice(&[0xD0, 0xFE]);
+        let mut debugger = ActiveDebugger::default();
+        debugger.symbol_map.insert(0x1000, "Start".to_owned());
+        debugger.generate_labels(program);
+        debugger.disassemble(program);
+
+        let disassembly = debugger.disassembly.unwrap();
+        assert_eq!(disassembly[&0x1000], "Start:\r\n  BNE Start");
+    }
+
+    #[test]
+    fn test_parse_symbols() {
+        let file = "\
+--- Symbol List (sorted by symbol)
+; Registers
+COLUBK                   0009
+Start                    f000              (R )
+Loop = $F00A
+Counter=$80
+Bad                      nothex
+NoAddress
+--- End of Symbol List.
+";
+        let symbols = parse_symbols(file);
+        assert_eq!(
+            symbols,
+            HashMap::from([
+                (0x0009, "COLUBK".to_owned()),
+                (0x1000, "Start".to_owned()),
+                (0x100A, "Loop".to_owned()),
+                (0x0080, "Counter".to_owned()),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_symbol_file_with_missing_address() {
+        let file = std::env::temp_dir().join("stanley_missing_address.sym");
+        fs::write(&file, "Start f000\nNoAddress\n\n").unwrap();
+        let mut debugger = ActiveDebugger::default();
+        debugger
+            .parse_symbol_file(Some(file.to_string_lossy().into_owned()))
+            .unwrap();
+        fs::remove_file(&file).unwrap();
+        assert_eq!(
+            debugger.symbol_map,
+            HashMap::from([(0x1000, "Start".to_owned())])
+        );
+
+        assert!(debugger
+            .parse_symbol_file(Some("no/such/file.sym".to_owned()))
+            .is_err());
+    }
+
+    #[test]
+    fn test_step_back() {
+        let mut program = program();
+        // LDA #$01; LDX #$02; LDY #$03
+        program[0..6].copy_from_slice(&[0xA9, 0x01, 0xA2, 0x02, 0xA0, 0x03]);
+        let mut system = System::new(program);
+        let mut debugger = ActiveDebugger::default();
+        debugger.keep_history(2);
+        for _ in 0..3 {
+            debugger.record(&system);
+            system.step().unwrap();
+        }
+        assert_eq!((system.chip.a, system.chip.x, system.chip.y), (1, 2, 3));
+
+        assert!(debugger.step_back(&mut system).unwrap());
+        assert_eq!((system.chip.a, system.chip.x, system.chip.y), (1, 2, 0));
+        assert_eq!(system.chip.pc, 0x1004);
+        assert!(debugger.step_back(&mut system).unwrap());
+        assert_eq!((system.chip.a, system.chip.x, system.chip.y), (1, 0, 0));
+        assert_eq!(system.clocks, 2);
+        // Only the last two steps were kept
+        assert!(!debugger.step_back(&mut system).unwrap());
+        assert_eq!(system.chip.pc, 0x1002);
+    }
+
+    #[test]
+    fn test_format_memory() {
+        let mut memory = [0; 128];
+        memory[..5].copy_from_slice(b"Hello");
+        memory[127] = 0xFF;
+        let dump = format_memory(&memory, 0x80);
+        let lines: Vec<_> = dump.lines().collect();
+        assert_eq!(lines.len(), 8);
+        assert_eq!(
+            lines[0],
+            "0080  48 65 6C 6C 6F 00 00 00 00 00 00 00 00 00 00 00  Hello..........."
+        );
+        assert_eq!(
+            lines[7],
+            "00F0  00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 FF  ................"
+        );
+    }
+
+    #[test]
+    fn test_step_over_target() {
+        let mut program = program();
+        // JSR $F010; NOP
+        program[0..4].copy_from_slice(&[0x20, 0x10, 0xF0, 0xEA]);
+        let mut system = System::new(program);
+        system.chip.pc = 0xF000;
+        assert_eq!(step_over_target(&system), Some(0x1003));
+        system.chip.pc = 0xF003;
+        assert_eq!(step_over_target(&system), None);
+    }
+
+    #[test]
+    fn test_edit_registers() {
+        let mut system = System::new(program());
+        let keys = |keys: &str| {
+            keys.chars()
+                .map(|c| {
+                    if c == '\n' {
+                        KeyCode::Enter
+                    } else {
+                        KeyCode::Char(c)
+                    }
+                })
+                .collect::<Vec<_>>()
+                .into_iter()
+        };
+
+        assert!(edit_registers(
+            &mut system,
+            KeyCode::Char('a'),
+            keys("1f\n")
+        ));
+        assert_eq!(system.chip.a, 0x1F);
+        assert!(edit_registers(
+            &mut system,
+            KeyCode::Char('p'),
+            keys("F123\n")
+        ));
+        assert_eq!(system.chip.pc, 0xF123);
+        assert!(edit_registers(&mut system, KeyCode::Char('C'), keys("")));
+        assert!(system.chip.c);
+
+        // Escape cancels the edit
+        let keys = [KeyCode::Char('7'), KeyCode::Esc].into_iter();
+        assert!(edit_registers(&mut system, KeyCode::Char('x'), keys));
+        assert_eq!(system.chip.x, 0);
+
+        assert!(!edit_registers(
+            &mut system,
+            KeyCode::Char('n'),
+            [].into_iter()
+        ));
+    }
+
+    #[test]
+    fn test_view_at_start_of_program() {
+        let mut debugger = ActiveDebugger::default();
+        debugger.disassemble(program());
+
+        let lines: Vec<u16> = debugger.view_lines(0x1000).map(|(&key, _)| key).collect();
+        assert_eq!(lines, (0x1000..0x1005).collect::<Vec<_>>());
+        assert_eq!(debugger.view_lines(0).count(), 0);
+
+        debugger.scroll(0x1000, true);
+        assert_eq!(debugger.view, None);
+        debugger.scroll(0x1000, false);
+        assert_eq!(debugger.view, Some(0x1001));
+    }
+
+    #[test]
+    fn test_multiple_breakpoints() {
+        let mut debugger = ActiveDebugger::default();
+        debugger.symbol_map.insert(0x1234, "Kernel".to_owned());
+        debugger.add_breakpoints(
+            ["F010", "Kernel", "Missing"]
+                .iter()
+                .map(|s| s.parse().unwrap())
+                .collect(),
+        );
+
+        let mut system = System::new([0; 4096]);
+        for (pc, paused) in [
+            (0xF010, true),
+            (0x1010, true),
+            (0xF234, true),
+            (0xF011, false),
+        ] {
+            system.chip.pc = pc;
+            assert_eq!(debugger.at_breakpoint(&system), paused);
+        }
+        assert_eq!(debugger.breakpoints.len(), 2);
+    }
+
+    #[test]
+    fn test_conditional_breakpoints() {
+        let mut debugger = ActiveDebugger::default();
+        debugger.symbol_map.insert(0x1234, "Kernel".to_owned());
+        debugger.add_breakpoints(
+            ["Kernel:A==0x00", "Kernel:C=1", "F010"]
+                .iter()
+                .map(|s| s.parse().unwrap())
+                .collect(),
+        );
+        assert!("Kernel:Q=1".parse::<BreakPoint>().is_err());
+
+        let mut system = System::new([0; 4096]);
+        system.chip.pc = 0x1234;
+        system.chip.a = 0x10;
+        assert!(!debugger.at_breakpoint(&system));
+        // Either condition pauses
+        system.chip.c = true;
+        assert!(debugger.at_breakpoint(&system));
+        system.chip.c = false;
+        system.chip.a = 0;
+        assert!(debugger.at_breakpoint(&system));
+        system.chip.pc = 0x1010;
+        assert!(debugger.at_breakpoint(&system));
+    }
+}
@@ -0,0 +1,60 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+pub type Result<T> = std::result::Result<T, StanleyError>;
+
+/// Errors from running the emulator
+#[derive(Debug)]
+pub enum StanleyError {
+    /// An opcode that couldn't be decoded, and the address it was read from
+    UnknownOpcode(u8, u16),
+    /// An address nothing is mapped to, and the address of the instruction that accessed it
+    UnmappedAddress(u16, u16),
+    /// The address of a KIL instruction, which halts the processor
+    CpuJammed(u16),
+    /// An address outside the cartridge and RAM that the program counter ran into
+    PcOutOfBounds(u16),
+    /// The size of a ROM that isn't a size we can load, and the size that was expected
+    RomSize(usize, usize),
+    /// Why a save state couldn't be loaded
+    SaveState(String),
+    Io(io::Error),
+}
+
+impl fmt::Display for StanleyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownOpcode(opcode, pc) => {
+                write!(f, "Unknown instruction: {opcode:02X} at ${pc:04X}")
+            }
+            Self::UnmappedAddress(addr, pc) => {
+                write!(f, "Nothing is mapped to ${addr:04X}, accessed at ${pc:04X}")
+            }
+            Self::CpuJammed(pc) => write!(f, "CPU jammed at ${pc:04X}"),
+            Self::PcOutOfBounds(pc) => {
+                write!(f, "Program counter left the cartridge, reached ${pc:04X}")
+            }
+            Self::RomSize(size, expected) => {
+                write!(f, "Program expected to be {expected} bytes, was {size}")
+            }
+            Self::SaveState(message) => write!(f, "Couldn't load save state: {message}"),
+            Self::Io(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl Error for StanleyError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for StanleyError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
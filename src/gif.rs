@@ -0,0 +1,172 @@
+use std::collections::VecDeque;
+
+/// Colors in the GIF's color table. The 2600 only makes 128 colors, so they all fit.
+const PALETTE_SIZE: usize = 256;
+/// Each frame's pixels are color table indexes, 8 bits each
+const MIN_CODE_SIZE: u8 = 8;
+const CLEAR_CODE: u16 = 1 << MIN_CODE_SIZE;
+const END_CODE: u16 = CLEAR_CODE + 1;
+/// The decoder grows its code table with every code it reads. Clearing the table before it
+/// reaches 512 entries keeps every code 9 bits long, so pixels can be written without compressing
+/// them.
+const CODES_BETWEEN_CLEARS: usize = 250;
+
+/// Collects frames of an animation and writes them out as a looping GIF. Only the most recent
+/// frames are kept, so a long session doesn't grow without bound.
+pub struct GifWriter {
+    width: u16,
+    height: u16,
+    max_frames: usize,
+    palette: Vec<[u8; 3]>,
+    frames: VecDeque<Vec<u8>>,
+}
+
+impl GifWriter {
+    pub fn new(width: u16, height: u16, max_frames: usize) -> Self {
+        Self {
+            width,
+            height,
+            max_frames,
+            palette: Vec::new(),
+            frames: VecDeque::new(),
+        }
+    }
+
+    /// Adds a frame of `width * height` RGB pixels, dropping the oldest frame when full
+    pub fn add_frame(&mut self, pixels: &[[u8; 3]]) {
+        let frame = pixels
+            .iter()
+            .map(|&color| self.color_index(color))
+            .collect();
+        if self.frames.len() == self.max_frames {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(frame);
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Writes the GIF, showing each frame for `delay` hundredths of a second
+    pub fn finish(&self, delay: u16) -> Vec<u8> {
+        let mut gif = b"GIF89a".to_vec();
+        gif.extend_from_slice(&self.width.to_le_bytes());
+        gif.extend_from_slice(&self.height.to_le_bytes());
+        // A global color table of 256 colors, background color 0, square pixels
+        gif.extend_from_slice(&[0xF7, 0, 0]);
+        for index in 0..PALETTE_SIZE {
+            gif.extend_from_slice(&self.palette.get(index).copied().unwrap_or_default());
+        }
+        // Loop forever
+        gif.extend_from_slice(&[0x21, 0xFF, 0x0B]);
+        gif.extend_from_slice(b"NETSCAPE2.0");
+        gif.extend_from_slice(&[0x03, 0x01, 0x00, 0x00, 0x00]);
+
+        for frame in &self.frames {
+            gif.extend_from_slice(&[0x21, 0xF9, 0x04, 0x00]);
+            gif.extend_from_slice(&delay.to_le_bytes());
+            gif.extend_from_slice(&[0x00, 0x00]);
+
+            gif.extend_from_slice(&[0x2C, 0, 0, 0, 0]);
+            gif.extend_from_slice(&self.width.to_le_bytes());
+            gif.extend_from_slice(&self.height.to_le_bytes());
+            gif.push(0x00);
+            gif.push(MIN_CODE_SIZE);
+            for block in lzw_uncompressed(frame).chunks(255) {
+                gif.push(block.len() as u8);
+                gif.extend_from_slice(block);
+            }
+            gif.push(0x00);
+        }
+        gif.push(0x3B);
+        gif
+    }
+
+    /// Finds a color in the color table, adding it if there's room and otherwise using the
+    /// closest color there
+    fn color_index(&mut self, color: [u8; 3]) -> u8 {
+        if let Some(index) = self.palette.iter().position(|&entry| entry == color) {
+            return index as u8;
+        }
+        if self.palette.len() < PALETTE_SIZE {
+            self.palette.push(color);
+            return (self.palette.len() - 1) as u8;
+        }
+        let distance = |entry: &[u8; 3]| -> i32 {
+            (0..3)
+                .map(|i| (entry[i] as i32 - color[i] as i32).pow(2))
+                .sum()
+        };
+        (0..PALETTE_SIZE)
+            .min_by_key(|&index| distance(&self.palette[index]))
+            .unwrap_or_default() as u8
+    }
+}
+
+/// Encodes each pixel as its own 9 bit code
+fn lzw_uncompressed(pixels: &[u8]) -> Vec<u8> {
+    let mut bits = BitWriter::default();
+    for chunk in pixels.chunks(CODES_BETWEEN_CLEARS) {
+        bits.write(CLEAR_CODE);
+        for &pixel in chunk {
+            bits.write(pixel as u16);
+        }
+    }
+    bits.write(END_CODE);
+    bits.finish()
+}
+
+/// Packs 9 bit codes, least significant bit first
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    count: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16) {
+        self.buffer |= (code as u32) << self.count;
+        self.count += MIN_CODE_SIZE as u32 + 1;
+        while self.count >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gif_writer() {
+        let mut writer = GifWriter::new(4, 2, 3);
+        for shade in 0..5 {
+            writer.add_frame(&[[shade * 10, 0, 0]; 8]);
+        }
+        // Only the last three frames are kept
+        assert_eq!(writer.frame_count(), 3);
+
+        let gif = writer.finish(2);
+        assert_eq!(&gif[..6], b"GIF89a");
+        assert_eq!(gif[6..8], 4u16.to_le_bytes());
+        assert_eq!(gif[8..10], 2u16.to_le_bytes());
+        assert_eq!(gif.last(), Some(&0x3B));
+    }
+
+    #[test]
+    fn test_lzw_uncompressed() {
+        // Clear, 1, 2, end as 9 bit codes
+        assert_eq!(lzw_uncompressed(&[1, 2]), [0x00, 0x03, 0x08, 0x08, 0x08]);
+    }
+}
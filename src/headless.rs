@@ -0,0 +1,43 @@
+use crate::screenshot::save_png;
+use crate::system::System;
+use crate::Result;
+
+/// Runs a program for a number of frames without opening a window, then saves the picture as a
+/// PNG. Used for scripts and CI, where there's no display to draw to.
+pub fn run(system: &mut System, frames: usize, file_name: &str) -> Result<()> {
+    run_frames(system, frames)?;
+    save_png(&system.tia.buffer, file_name)
+}
+
+/// Steps the system until the beam has started `frames` new frames
+pub fn run_frames(system: &mut System, frames: usize) -> Result<()> {
+    for _ in 0..frames {
+        system.step_frame()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::system::tia::{CLOCKS_PER_FRAME, STRIDE, WIDTH};
+    use crate::system::tv::TvStandard;
+
+    #[test]
+    fn test_run_frames() {
+        // LDA #$0E, STA COLUBK, JMP $1002
+        let mut program = [0; 4096];
+        program[..7].copy_from_slice(&[0xA9, 0x0E, 0x85, 0x09, 0x4C, 0x02, 0x10]);
+        let mut system = System::new(program);
+
+        run_frames(&mut system, 1).unwrap();
+        assert!(system.clocks >= CLOCKS_PER_FRAME);
+        assert!(system.clocks < CLOCKS_PER_FRAME * 2);
+        // The whole picture was drawn in the background color
+        let middle = (96 * WIDTH as usize + 80) * STRIDE;
+        assert_eq!(
+            system.tia.buffer.0[middle..middle + STRIDE],
+            TvStandard::Ntsc.color(0x0E).as_slice()
+        );
+    }
+}
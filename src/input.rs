@@ -0,0 +1,125 @@
+use std::str::FromStr;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum InputType {
+    Joystick1Button,
+    Joystick1ButtonB,
+    Joystick1AutoFire,
+    Joystick1Up,
+    Joystick1Down,
+    Joystick1Left,
+    Joystick1Right,
+    Joystick2Button,
+    Joystick2Up,
+    Joystick2Down,
+    Joystick2Left,
+    Joystick2Right,
+    /// Turns the driving controller in player 1's port a step
+    Driving1Clockwise,
+    Driving1CounterClockwise,
+    Select,
+    Reset,
+}
+
+impl FromStr for InputType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "Joystick1Button" => InputType::Joystick1Button,
+            "Joystick1ButtonB" => InputType::Joystick1ButtonB,
+            "Joystick1AutoFire" => InputType::Joystick1AutoFire,
+            "Joystick1Up" => InputType::Joystick1Up,
+            "Joystick1Down" => InputType::Joystick1Down,
+            "Joystick1Left" => InputType::Joystick1Left,
+            "Joystick1Right" => InputType::Joystick1Right,
+            "Joystick2Button" => InputType::Joystick2Button,
+            "Joystick2Up" => InputType::Joystick2Up,
+            "Joystick2Down" => InputType::Joystick2Down,
+            "Joystick2Left" => InputType::Joystick2Left,
+            "Joystick2Right" => InputType::Joystick2Right,
+            "Driving1Clockwise" => InputType::Driving1Clockwise,
+            "Driving1CounterClockwise" => InputType::Driving1CounterClockwise,
+            "Select" => InputType::Select,
+            "Reset" => InputType::Reset,
+            _ => return Err("Invalid input type".to_owned()),
+        })
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum WindowEvent {
+    None,
+    Quit,
+    InputStart(InputType),
+    InputEnd(InputType),
+    SaveState,
+    LoadState,
+    /// The turbo key was pressed or released
+    Turbo(bool),
+    Pause,
+    /// The mouse moved paddle 0 to a position from 0 to 255
+    PaddleMoved(u8),
+}
+
+impl FromStr for WindowEvent {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "Quit" => WindowEvent::Quit,
+            "SaveState" => WindowEvent::SaveState,
+            "LoadState" => WindowEvent::LoadState,
+            "Turbo(true)" => WindowEvent::Turbo(true),
+            "Turbo(false)" => WindowEvent::Turbo(false),
+            "Pause" => WindowEvent::Pause,
+            input if s.starts_with("PaddleMoved") => {
+                let position = input
+                    .get(12..s.len() - 1)
+                    .and_then(|position| position.parse().ok())
+                    .ok_or_else(|| "Invalid paddle position".to_owned())?;
+                WindowEvent::PaddleMoved(position)
+            }
+            input if s.starts_with("InputStart") => {
+                let input_type = input
+                    .get(11..s.len() - 1)
+                    .ok_or_else(|| "Invalid input_start".to_owned())?;
+                WindowEvent::InputStart(input_type.parse()?)
+            }
+            input if s.starts_with("InputEnd") => {
+                let input_type = input
+                    .get(9..s.len() - 1)
+                    .ok_or_else(|| "Invalid input_start".to_owned())?;
+                WindowEvent::InputEnd(input_type.parse()?)
+            }
+            _ => return Err("Invalid window event".to_owned()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_joystick2_events_round_trip() {
+        for input in [
+            InputType::Joystick2Button,
+            InputType::Joystick2Up,
+            InputType::Joystick2Down,
+            InputType::Joystick2Left,
+            InputType::Joystick2Right,
+        ] {
+            for event in [WindowEvent::InputStart(input), WindowEvent::InputEnd(input)] {
+                assert_eq!(format!("{event:?}").parse::<WindowEvent>(), Ok(event));
+            }
+        }
+    }
+
+    #[test]
+    fn test_paddle_event_round_trip() {
+        let event = WindowEvent::PaddleMoved(128);
+        assert_eq!(format!("{event:?}").parse::<WindowEvent>(), Ok(event));
+        assert!("PaddleMoved(300)".parse::<WindowEvent>().is_err());
+    }
+}
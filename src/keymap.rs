@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+use std::fs;
+use std::str::FromStr;
+
+use sdl2::keyboard::Keycode;
+
+use crate::input::InputType;
+use crate::Result;
+
+/// The keys that drive the console's inputs. A keymap file has a `KEY=INPUT` binding on each
+/// line, where KEY is an SDL key name and INPUT is an `InputType`. A keymap file replaces every
+/// default binding, so it should list all the inputs that need a key. Blank lines and lines
+/// starting with `#` are ignored.
+///
+/// ```text
+/// # Player 1 on the numpad side of the keyboard
+/// I=Joystick1Up
+/// K=Joystick1Down
+/// J=Joystick1Left
+/// L=Joystick1Right
+/// Space=Joystick1Button
+/// ```
+#[derive(Debug, PartialEq)]
+pub struct KeyMap(HashMap<Keycode, InputType>);
+
+impl KeyMap {
+    pub fn load(file_name: &str) -> Result<Self> {
+        let file = fs::read_to_string(file_name).map_err(|e| e.to_string())?;
+        Ok(file.parse()?)
+    }
+
+    pub fn get(&self, keycode: Keycode) -> Option<InputType> {
+        self.0.get(&keycode).copied()
+    }
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        Self(HashMap::from([
+            (Keycode::F, InputType::Joystick1Button),
+            // Genesis controller second button
+            (Keycode::R, InputType::Joystick1ButtonB),
+            (Keycode::G, InputType::Joystick1AutoFire),
+            (Keycode::W, InputType::Joystick1Up),
+            (Keycode::S, InputType::Joystick1Down),
+            (Keycode::A, InputType::Joystick1Left),
+            (Keycode::D, InputType::Joystick1Right),
+            // Driving controller, turned a step each time a key repeats
+            (Keycode::Q, InputType::Driving1CounterClockwise),
+            (Keycode::E, InputType::Driving1Clockwise),
+            // Player 2 on the arrow keys and right shift
+            (Keycode::RShift, InputType::Joystick2Button),
+            (Keycode::Up, InputType::Joystick2Up),
+            (Keycode::Down, InputType::Joystick2Down),
+            (Keycode::Left, InputType::Joystick2Left),
+            (Keycode::Right, InputType::Joystick2Right),
+            // Console switches
+            (Keycode::F1, InputType::Select),
+            (Keycode::F2, InputType::Reset),
+        ]))
+    }
+}
+
+impl FromStr for KeyMap {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let mut bindings = HashMap::new();
+        for line in s.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, input) = line
+                .split_once('=')
+                .ok_or_else(|| format!("Invalid key binding: {line}"))?;
+            let keycode =
+                Keycode::from_name(key.trim()).ok_or_else(|| format!("Invalid key name: {key}"))?;
+            let input = input
+                .trim()
+                .parse()
+                .map_err(|_| format!("Invalid input: {input}"))?;
+            bindings.insert(keycode, input);
+        }
+        Ok(Self(bindings))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_keymap() {
+        let keymap: KeyMap = "# comment\n\nI=Joystick1Up\nRight Shift = Joystick1Button\n"
+            .parse()
+            .unwrap();
+        assert_eq!(keymap.get(Keycode::I), Some(InputType::Joystick1Up));
+        assert_eq!(
+            keymap.get(Keycode::RShift),
+            Some(InputType::Joystick1Button)
+        );
+        // The file replaces the defaults
+        assert_eq!(keymap.get(Keycode::W), None);
+        assert_eq!(
+            KeyMap::default().get(Keycode::W),
+            Some(InputType::Joystick1Up)
+        );
+    }
+
+    #[test]
+    fn test_parse_keymap_errors() {
+        assert!("I Joystick1Up".parse::<KeyMap>().is_err());
+        assert!("NotAKey=Joystick1Up".parse::<KeyMap>().is_err());
+        assert!("I=Joystick3Up".parse::<KeyMap>().is_err());
+    }
+}
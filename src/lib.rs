@@ -1,9 +1,24 @@
 use std::error::Error;
 
+pub mod autofire;
+pub mod cheats;
+pub mod compatibility;
+pub mod crash_report;
 pub mod debugger;
+pub mod error;
+pub mod gif;
+pub mod headless;
+pub mod input;
+#[cfg(feature = "sdl")]
+pub mod keymap;
+pub mod pause;
+pub mod profiler;
 pub mod recorder;
+#[cfg(feature = "sdl")]
 pub mod renderer;
+pub mod screenshot;
 pub mod system;
 pub mod timer;
+pub mod watch;
 
 pub type Result<T> = std::result::Result<T, Box<dyn Error>>;
@@ -1,5 +1,5 @@
 use std::{
-    fs,
+    fs, io, panic, thread,
     time::{Duration, Instant},
 };
 
@@ -7,30 +7,128 @@ use clap::Parser;
 
 pub use stanley::*;
 
-use debugger::{get_debugger, try_parse_breakpoint, BreakPointType};
+use autofire::AutoFire;
+use cheats::Cheats;
+use compatibility::{CompatibilityReport, COMPATIBILITY_FRAMES};
+use crash_report::{save_crash_report, CRASH_REPORT_FILE};
+use debugger::{get_debugger, BreakPoint, DebugAction, Tracer};
+use error::StanleyError;
+use keymap::KeyMap;
+use pause::Pause;
+use profiler::Profiler;
 use recorder::Recorder;
 use renderer::{Renderer, WindowEvent};
-use system::instructions::Instruction;
-use system::System;
-use timer::Timer;
+use screenshot::save_png;
+use system::cartridge::{BankSwitching, Cartridge};
+use system::ram::RamInit;
+use system::tv::{detect_tv_standard, TvStandard};
+use system::{System, SystemConfig};
+use timer::{RenderMode, Timer};
+use watch::Watch;
+
+/// How long to wait between checking for events while paused
+const PAUSED_SLEEP: Duration = Duration::from_millis(5);
 
 #[derive(Parser)]
 #[clap(author, version, about, long_about = None)]
 struct Args {
     #[clap(short, long)]
     debug: bool,
+    /// With --debug, print the program as a listing of addresses, bytes, and instructions, then
+    /// exit
     #[clap(long)]
     disassemble: bool,
+    /// Generate labels for branch and jump targets in the disassembly
+    #[clap(long)]
+    labels: bool,
     /// Record your session, taking a screenshot when you exit. The screenshot and recording of
     /// your session are placed in the tests/snapshots/<SNAPSHOT_NAME>/ directory. These will be
     /// picked up by the automated test system.
     #[clap(long, value_name = "SNAPSHOT_NAME")]
     record: Option<String>,
+    /// Also record an animated GIF of your session into the snapshot directory, capturing a frame
+    /// every FRAMES frames
+    #[clap(long, value_name = "FRAMES", requires = "record")]
+    gif: Option<usize>,
     // TODO: take hex argument
-    #[clap(short, long, parse(try_from_str=try_parse_breakpoint))]
-    breakpoint: Option<BreakPointType>,
+    /// An address or symbol to pause at. Can be given more than once. Add a condition after a
+    /// colon to only pause when it holds, like `Kernel:A==00` or `F010:C=1`.
+    #[clap(short, long)]
+    breakpoint: Vec<BreakPoint>,
     #[clap(short, long)]
     symbol_file: Option<String>,
+    /// An expression to show in the debugger, like `[$80] + [$81] * 256`. Can be given more than
+    /// once.
+    #[clap(short, long)]
+    watch: Vec<Watch>,
+    /// Keep the state before each of the last STEPS instructions stepped through in the debugger,
+    /// so it can step back through them with `u`. Each step keeps a few KB.
+    #[clap(long, value_name = "STEPS", default_value_t = 0, requires = "debug")]
+    history: usize,
+    /// Number of frames between each press and release of the button while auto-fire is engaged
+    #[clap(long, value_name = "FRAMES", default_value_t = 4)]
+    autofire_rate: usize,
+    /// A file of `ADDR=VALUE` patches to apply to the program, in every bank or only the one
+    /// given as `BANK:ADDR=VALUE`. Lines starting with `!` are written to memory every frame
+    /// instead.
+    #[clap(long, value_name = "FILE")]
+    cheats: Option<String>,
+    /// Stop when the program counter leaves the cartridge and RAM. Always on in debug builds.
+    #[clap(long)]
+    check_pc: bool,
+    /// Write a line to FILE for every instruction executed, with the registers and clocks
+    #[clap(long, value_name = "FILE")]
+    trace: Option<String>,
+    /// Count the executions and cycles of every instruction, and print the most expensive ones on
+    /// exit, along with the totals for each opcode
+    #[clap(long)]
+    profile: bool,
+    /// The TV standard the program was written for: NTSC, PAL, or SECAM. Detected from the program
+    /// when not given, though SECAM programs are detected as PAL.
+    #[clap(long)]
+    tv: Option<TvStandard>,
+    /// The bank switching scheme of the cartridge: none for 2K and 4K ROMs, F8 for Atari 8K, E0 for
+    /// Parker Brothers 8K, or FE for Activision 8K
+    #[clap(long, value_name = "SCHEME", default_value = "none")]
+    bank_switching: BankSwitching,
+    /// What RAM holds at power on: zero, ones, random, or seeded:N for the same random bytes every
+    /// run with seed N
+    #[clap(long, value_name = "MODE", default_value = "zero")]
+    ram_init: RamInit,
+    /// How many times bigger than the console's picture to make the window
+    #[clap(long, value_name = "N", default_value_t = 3)]
+    scale: u32,
+    /// Run without a window for a number of frames, then save the picture as a PNG to the
+    /// screenshot path, or FILE_NAME.png
+    #[clap(long)]
+    headless: bool,
+    /// How many frames to run in headless mode
+    #[clap(long, value_name = "N", default_value_t = 1)]
+    frames: usize,
+    /// Save the picture as a PNG when you exit
+    #[clap(long, value_name = "PATH")]
+    screenshot: Option<String>,
+    /// Mix each frame shown with the one before it, so sprites that flicker on alternate frames
+    /// look solid
+    #[clap(long)]
+    blend: bool,
+    /// Draw the playfield, players, missiles, and ball each in a fixed color, to see where a
+    /// kernel puts them
+    #[clap(long)]
+    debug_colors: bool,
+    /// Show the picture every 16 milliseconds wherever the beam is, instead of once the console
+    /// finishes each frame. Changes made mid-frame can tear.
+    #[clap(long)]
+    no_vsync: bool,
+    /// A file of `KEY=INPUT` bindings to use instead of the default keys
+    #[clap(long, value_name = "FILE")]
+    keymap: Option<String>,
+    /// Treat FILE_NAME as a directory of ROMs. Run each one for a few seconds, and report the
+    /// first thing each hits that isn't emulated yet.
+    #[clap(long)]
+    compatibility: bool,
+    /// The ROM to run, or - to read it from standard input. Screenshots and save states of a ROM
+    /// read from standard input default to stdin.png and stdin.state.
     file_name: String,
 }
 
@@ -38,55 +136,169 @@ fn main() -> Result<()> {
     let Args {
         debug,
         disassemble,
+        labels,
         record,
+        gif,
         breakpoint,
         symbol_file,
+        watch,
+        history,
+        autofire_rate,
+        cheats,
+        check_pc,
+        trace,
+        profile,
+        tv,
+        bank_switching,
+        scale,
+        headless,
+        frames,
+        screenshot,
+        keymap,
+        blend,
+        no_vsync,
+        debug_colors,
+        ram_init,
+        compatibility,
         file_name,
     } = Args::parse();
 
-    let byte_vec = fs::read(&file_name).map_err(|e| e.to_string())?;
-    let program = byte_vec
-        .try_into()
-        .expect("Program expected to be 4096 bytes was not");
-    let mut debugger = get_debugger(debug);
+    if compatibility {
+        // The panics are collected into the report, don't print them as they happen
+        panic::set_hook(Box::new(|_| {}));
+        let report = CompatibilityReport::check_directory(&file_name, COMPATIBILITY_FRAMES)?;
+        print!("{report}");
+        return Ok(());
+    }
+
+    // Reading from stdin lets an assembler's output be piped straight in
+    let mut cartridge = if file_name == "-" {
+        Cartridge::read(io::stdin().lock(), bank_switching)?
+    } else {
+        let file = fs::File::open(&file_name).map_err(|e| e.to_string())?;
+        Cartridge::read(file, bank_switching)?
+    };
+    // The default screenshot and save state go next to the ROM
+    let base_name = if file_name == "-" {
+        "stdin"
+    } else {
+        &file_name
+    };
+    // The recording keeps the ROM as it was before any cheats
     let mut recorder_option = record
-        .map(|snapshot_name| Recorder::new(&snapshot_name, &file_name))
+        .map(|snapshot_name| Recorder::new(&snapshot_name, cartridge.rom()))
         .transpose()?;
+    let cheats = cheats.map(|file| Cheats::load(&file)).transpose()?;
+    if let Some(cheats) = &cheats {
+        cheats.patch_cartridge(&mut cartridge)?;
+    }
+    let program = cartridge.program();
+    let mut debugger = get_debugger(debug);
 
     if debug && disassemble {
-        debugger.dump_disassembly(program);
+        debugger.dump_disassembly(program, labels);
+        return Ok(());
+    }
+
+    let defaults = SystemConfig::default();
+    let config = SystemConfig {
+        tv_standard: tv.unwrap_or_else(|| detect_tv_standard(&program)),
+        ram_init,
+        bank_switching,
+        check_pc: defaults.check_pc || check_pc,
+        debug_colors,
+    };
+    let mut system = System::with_config(cartridge.rom(), config)?;
+    // PAL and SECAM frames are longer than NTSC ones
+    let clocks_per_frame = system.tia.clocks_per_frame();
+    if let (Some(recorder), Some(frames)) = (recorder_option.as_mut(), gif) {
+        recorder.record_gif(frames * clocks_per_frame);
+    }
+    if profile {
+        system.profiler = Some(Profiler::default());
+    }
+    if headless {
+        let output = screenshot.unwrap_or_else(|| format!("{base_name}.png"));
+        headless::run(&mut system, frames, &output)?;
+        if let Some(profiler) = &system.profiler {
+            print!("{profiler}");
+        }
         return Ok(());
     }
 
     debugger.setup(program, breakpoint, symbol_file)?;
+    for watch in watch {
+        debugger.add_watch(watch);
+    }
+    debugger.keep_history(history);
 
-    let mut system = System::new(program);
     let total_time = Instant::now();
-    let mut renderer = Renderer::setup()?;
+    let keymap = keymap
+        .map(|file| KeyMap::load(&file))
+        .transpose()?
+        .unwrap_or_default();
+    let mut renderer = Renderer::setup(scale, keymap, blend)?;
+    let mut auto_fire = AutoFire::new(autofire_rate, clocks_per_frame);
+    let state_file = format!("{base_name}.state");
+    let mut pause = Pause::default();
+    let mut tracer = trace.map(|file| Tracer::create(&file)).transpose()?;
 
     // Timing stuff
-    let mut previous_clocks = 0;
-    let mut timer = Timer::start();
+    let mut previous_frame = 0;
+    let mut timer = Timer::start(clocks_per_frame);
+    let render_mode = if no_vsync {
+        RenderMode::WallClock
+    } else {
+        RenderMode::default()
+    };
 
     loop {
-        let clocks_run = system.clocks - previous_clocks;
-        if timer.should_render() && (debug || !system.tia.is_drawing()) {
+        let frame_ready = system.tia.frame_ready();
+        if timer.should_render_frame(render_mode, frame_ready, debug) {
             renderer.render(&system.tia.buffer)?;
+            renderer.queue_audio(&system.tia.take_audio_samples())?;
             timer.did_render();
         }
+        // Wait until the real console would have finished the frame too
+        if frame_ready {
+            system.tia.clear_frame_ready();
+            timer.end_frame();
+        }
 
-        if clocks_run > 10 {
-            let clock_time = Duration::from_nanos((clocks_run * 837) as u64);
-            timer.pause_for(clock_time);
-            previous_clocks = system.clocks;
+        let frame = system.clocks / clocks_per_frame;
+        if frame != previous_frame {
+            if let Some(cheats) = &cheats {
+                cheats.poke(&mut system);
+            }
+            previous_frame = frame;
         }
 
-        if let Err(e) = debugger.debug_loop(&system) {
-            eprintln!("{}", e);
-            break;
+        match debugger.debug_loop(&mut system) {
+            Ok(DebugAction::Execute) => (),
+            Ok(DebugAction::Wait) => continue,
+            Ok(DebugAction::SteppedBack) => {
+                previous_frame = system.clocks / clocks_per_frame;
+                continue;
+            }
+            Ok(DebugAction::Quit) => break,
+            Ok(DebugAction::Render) => {
+                renderer.render(&system.tia.buffer)?;
+                timer.did_render();
+                continue;
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                break;
+            }
         }
 
-        let event = renderer.handle_events();
+        let event = match renderer.handle_events() {
+            WindowEvent::None => auto_fire.update(system.clocks),
+            event => {
+                auto_fire.input_event(&event, system.clocks);
+                event
+            }
+        };
         if let Some(recorder) = recorder_option.as_mut() {
             recorder.update(&event, &system)?;
         }
@@ -94,18 +306,58 @@ fn main() -> Result<()> {
         match event {
             WindowEvent::Quit => break,
             WindowEvent::None => (),
+            WindowEvent::Turbo(turbo) => timer.set_turbo(turbo),
+            WindowEvent::Pause => {
+                pause.input_event(&event);
+                if !pause.is_paused() {
+                    timer.resume();
+                }
+            }
+            WindowEvent::SaveState => match fs::write(&state_file, system.save_state()) {
+                Ok(()) => eprintln!("State saved to {state_file}"),
+                Err(e) => eprintln!("Couldn't save state: {e}"),
+            },
+            WindowEvent::LoadState => {
+                let loaded = fs::read(&state_file)
+                    .map_err(StanleyError::from)
+                    .and_then(|state| system.load_state(&state));
+                match loaded {
+                    // The clocks may have gone back, count frames again from where they are now
+                    Ok(()) => previous_frame = system.clocks / clocks_per_frame,
+                    Err(e) => eprintln!("{e}"),
+                }
+            }
             event => system.input_event(&event),
         };
 
-        let instruction: Instruction = system.next_byte().try_into()?;
-
-        if let Err(e) = system.execute(instruction) {
+        if pause.is_paused() {
+            // Keep showing the frame and handling events without spinning the cpu
+            if timer.should_render() {
+                renderer.render(&system.tia.buffer)?;
+                timer.did_render();
+            }
+            thread::sleep(PAUSED_SLEEP);
+        }
+        if let Some(tracer) = tracer.as_mut().filter(|_| !pause.is_paused()) {
+            tracer.trace(&system)?;
+        }
+        if let Err(e) = pause.step(&mut system) {
             eprintln!("Time: {}", total_time.elapsed().as_nanos());
             eprintln!("Clocks: {}", system.clocks);
             eprintln!("{}", e);
+            match save_crash_report(&system, &e.to_string(), CRASH_REPORT_FILE) {
+                Ok(()) => eprintln!("Crash report written to {CRASH_REPORT_FILE}"),
+                Err(report_error) => eprintln!("Couldn't write crash report: {report_error}"),
+            }
             break;
         }
     }
     debugger.teardown()?;
+    if let Some(screenshot) = &screenshot {
+        save_png(&system.tia.buffer, screenshot)?;
+    }
+    if let Some(profiler) = &system.profiler {
+        print!("{profiler}");
+    }
     Ok(())
 }
@@ -0,0 +1,61 @@
+use crate::error;
+use crate::input::WindowEvent;
+use crate::system::System;
+
+/// Freezes the system while the window keeps rendering and handling events. Pausing is toggled by
+/// the `Pause` event.
+#[derive(Debug, Default)]
+pub struct Pause {
+    paused: bool,
+}
+
+impl Pause {
+    /// Toggles pausing when the pause key is pressed.
+    pub fn input_event(&mut self, event: &WindowEvent) {
+        if *event == WindowEvent::Pause {
+            self.paused = !self.paused;
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Steps the system unless it's paused, returning the clocks run.
+    pub fn step(&self, system: &mut System) -> error::Result<usize> {
+        if self.paused {
+            return Ok(0);
+        }
+        let start_clocks = system.clocks;
+        system.step()?;
+        Ok(system.clocks - start_clocks)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nothing_runs_while_paused() {
+        // NOP; NOP; ...
+        let mut system = System::new([0xEA; 4096]);
+        let mut pause = Pause::default();
+        assert_eq!(pause.step(&mut system).unwrap(), 2);
+
+        pause.input_event(&WindowEvent::Pause);
+        assert!(pause.is_paused());
+        for _ in 0..10 {
+            assert_eq!(pause.step(&mut system).unwrap(), 0);
+        }
+        assert_eq!(system.chip.pc, 0x1001);
+        assert_eq!(system.clocks, 2);
+
+        // Other events don't resume
+        pause.input_event(&WindowEvent::Quit);
+        assert!(pause.is_paused());
+        pause.input_event(&WindowEvent::Pause);
+        assert_eq!(pause.step(&mut system).unwrap(), 2);
+        assert_eq!(system.chip.pc, 0x1002);
+    }
+}
@@ -0,0 +1,162 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::system::instructions::Instruction;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ProfileEntry {
+    pub opcode: u8,
+    pub count: usize,
+    pub cycles: usize,
+}
+
+/// Tracks how many times the instruction at each address ran, and how many cycles it took in
+/// total. Cycles spent waiting on WSYNC are attributed to the instruction that strobed it.
+#[derive(Debug, Default)]
+pub struct Profiler {
+    entries: HashMap<u16, ProfileEntry>,
+}
+
+impl Profiler {
+    pub fn record(&mut self, pc: u16, opcode: u8, cycles: usize) {
+        let entry = self.entries.entry(pc & 0x1FFF).or_default();
+        entry.opcode = opcode;
+        entry.count += 1;
+        entry.cycles += cycles;
+    }
+
+    pub fn get(&self, pc: u16) -> Option<&ProfileEntry> {
+        self.entries.get(&(pc & 0x1FFF))
+    }
+
+    /// Entries sorted by total cycles, most expensive first.
+    pub fn report(&self) -> Vec<(u16, ProfileEntry)> {
+        let mut report: Vec<(u16, ProfileEntry)> = self
+            .entries
+            .iter()
+            .map(|(&pc, &entry)| (pc, entry))
+            .collect();
+        report.sort_by(|a, b| b.1.cycles.cmp(&a.1.cycles).then(a.0.cmp(&b.0)));
+        report
+    }
+
+    /// Totals for each opcode over every address it ran at, sorted by total cycles, most expensive
+    /// first
+    pub fn opcode_report(&self) -> Vec<ProfileEntry> {
+        let mut opcodes: HashMap<u8, ProfileEntry> = HashMap::new();
+        for entry in self.entries.values() {
+            let total = opcodes.entry(entry.opcode).or_insert(ProfileEntry {
+                opcode: entry.opcode,
+                ..Default::default()
+            });
+            total.count += entry.count;
+            total.cycles += entry.cycles;
+        }
+        let mut report: Vec<ProfileEntry> = opcodes.into_values().collect();
+        report.sort_by(|a, b| b.cycles.cmp(&a.cycles).then(a.opcode.cmp(&b.opcode)));
+        report
+    }
+}
+
+fn instruction_name(opcode: u8) -> String {
+    Instruction::try_from(opcode)
+        .map(|inst| inst.to_string())
+        .unwrap_or_else(|_| "???".to_owned())
+}
+
+impl fmt::Display for Profiler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let total: usize = self.entries.values().map(|entry| entry.cycles).sum();
+        writeln!(f, "ADDR  INST       COUNT      CYCLES       %")?;
+        for (pc, entry) in self.report() {
+            let name = instruction_name(entry.opcode);
+            writeln!(
+                f,
+                "{pc:04X}  {name:<4} {:>10}  {:>10}  {:>5.1}",
+                entry.count,
+                entry.cycles,
+                entry.cycles as f64 * 100.0 / total as f64
+            )?;
+        }
+
+        writeln!(f)?;
+        writeln!(f, "OP  INST       COUNT      CYCLES       %")?;
+        for entry in self.opcode_report() {
+            writeln!(
+                f,
+                "{:02X}  {:<4} {:>10}  {:>10}  {:>5.1}",
+                entry.opcode,
+                instruction_name(entry.opcode),
+                entry.count,
+                entry.cycles,
+                entry.cycles as f64 * 100.0 / total as f64
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::system::System;
+
+    #[test]
+    fn test_profile_loop() {
+        let mut program = [0; 4096];
+        // LDX #$05; loop: DEX; BNE loop; NOP
+        program[0..6].copy_from_slice(&[0xA2, 0x05, 0xCA, 0xD0, 0xFD, 0xEA]);
+        let mut system = System::new(program);
+        system.profiler = Some(Profiler::default());
+
+        while system.chip.pc != 0x1005 {
+            system.step().unwrap();
+        }
+
+        let profiler = system.profiler.as_ref().unwrap();
+        let dex = profiler.get(0x1002).unwrap();
+        assert_eq!((dex.count, dex.cycles), (5, 10));
+        // Taken four times at 3 cycles, and falls through once at 2 cycles
+        let bne = profiler.get(0x1003).unwrap();
+        assert_eq!((bne.count, bne.cycles), (5, 14));
+
+        let report = profiler.report();
+        assert_eq!(report[0].0, 0x1003);
+        assert_eq!(report[1].0, 0x1002);
+        assert_eq!(report[2].0, 0x1000);
+    }
+
+    #[test]
+    fn test_opcode_report() {
+        let mut program = [0; 4096];
+        // NOP; LDA #$01; NOP; LDA #$02; NOP
+        program[0..7].copy_from_slice(&[0xEA, 0xA9, 0x01, 0xEA, 0xA9, 0x02, 0xEA]);
+        let mut system = System::new(program);
+        system.profiler = Some(Profiler::default());
+        for _ in 0..5 {
+            system.step().unwrap();
+        }
+
+        let report = system.profiler.as_ref().unwrap().opcode_report();
+        assert_eq!(
+            report,
+            [
+                ProfileEntry {
+                    opcode: 0xEA,
+                    count: 3,
+                    cycles: 6
+                },
+                ProfileEntry {
+                    opcode: 0xA9,
+                    count: 2,
+                    cycles: 4
+                },
+            ]
+        );
+        assert!(system
+            .profiler
+            .unwrap()
+            .to_string()
+            .contains("EA  NOP           3           6   60.0"));
+    }
+}
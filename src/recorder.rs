@@ -2,37 +2,79 @@ use std::fs;
 use std::io::Write;
 use std::path::{Path, PathBuf};
 
-use crate::renderer::WindowEvent;
-use crate::system::tia::{HEIGHT, STRIDE, WIDTH};
+use crate::gif::GifWriter;
+use crate::input::WindowEvent;
+use crate::system::tia::{Buffer, CLOCK_NANOS, HEIGHT, WIDTH};
 use crate::system::System;
 use crate::Result;
 
+/// The most frames kept for the GIF, the last 20 seconds at the default rate
+const MAX_GIF_FRAMES: usize = 600;
+
 pub struct Recorder {
     recording: fs::File,
     path: PathBuf,
+    gif: Option<GifRecording>,
+}
+
+struct GifRecording {
+    writer: GifWriter,
+    interval: usize,
+    next_frame: usize,
 }
 
 impl Recorder {
-    pub fn new(snapshot_name: &str, binary_file_path: &str) -> Result<Self> {
+    pub fn new(snapshot_name: &str, rom: &[u8]) -> Result<Self> {
         let path = {
             let tmp_path = Path::new("./tests/snapshots");
             tmp_path.join(snapshot_name)
         };
         fs::create_dir_all(&path)?;
         let recording = fs::File::create(&path.join("recording.txt"))?;
-        let output_binary = path.join("binary.bin");
-        fs::copy(binary_file_path, output_binary)?;
-        Ok(Self { recording, path })
+        fs::write(path.join("binary.bin"), rom)?;
+        Ok(Self {
+            recording,
+            path,
+            gif: None,
+        })
+    }
+
+    /// Also records an animated GIF of the session, capturing a frame every `interval` clocks.
+    /// It's written to session.gif in the snapshot directory when you exit.
+    pub fn record_gif(&mut self, interval: usize) {
+        self.gif = Some(GifRecording {
+            // Every other line is kept, which keeps the shape of the 2600's wide pixels
+            writer: GifWriter::new(WIDTH as u16, HEIGHT as u16 / 2, MAX_GIF_FRAMES),
+            interval: interval.max(1),
+            next_frame: 0,
+        });
     }
 
     pub fn update(&mut self, event: &WindowEvent, system: &System) -> Result<()> {
+        if let Some(gif) = self.gif.as_mut() {
+            if system.clocks >= gif.next_frame {
+                gif.writer.add_frame(&downsample(&system.tia.buffer));
+                gif.next_frame = system.clocks + gif.interval;
+            }
+        }
         match event {
             WindowEvent::Quit => {
                 writeln!(self.recording, "{} {event:?}", system.clocks)?;
                 fs::write(self.path.join("screen.bin"), system.tia.buffer.0)?;
-                self.create_ppm(&system.tia.buffer.0)?;
+                self.create_ppm(&system.tia.buffer)?;
+                if let Some(gif) = &self.gif {
+                    let delay = (gif.interval * CLOCK_NANOS / 10_000_000).max(2);
+                    let data = gif.writer.finish(delay.min(u16::MAX as usize) as u16);
+                    fs::write(self.path.join("session.gif"), data)?;
+                }
             }
-            WindowEvent::None => {}
+            // Replaying a recording starts from power on, it can't jump to a saved state. Replays
+            // run as fast as they can and without stopping anyway.
+            WindowEvent::None
+            | WindowEvent::SaveState
+            | WindowEvent::LoadState
+            | WindowEvent::Turbo(_)
+            | WindowEvent::Pause => {}
             _ => {
                 writeln!(self.recording, "{} {event:?}", system.clocks)?;
             }
@@ -40,16 +82,15 @@ impl Recorder {
         Ok(())
     }
 
-    fn create_ppm(&self, buffer: &[u8]) -> Result<()> {
+    fn create_ppm(&self, buffer: &Buffer) -> Result<()> {
         let mut ppm_image = fs::File::create(&self.path.join("screen.ppm"))?;
         writeln!(ppm_image, "P6")?;
         writeln!(ppm_image, "{} {}", WIDTH * 3, HEIGHT * 2)?;
         writeln!(ppm_image, "255")?;
 
-        for line in buffer.chunks(WIDTH as usize * STRIDE) {
+        for line in buffer.as_rgb().chunks(WIDTH as usize * 3) {
             for _ in 0..=1 {
-                for pixel in line.chunks(STRIDE) {
-                    let pixel = [pixel[2], pixel[1], pixel[0]];
+                for pixel in line.chunks(3) {
                     ppm_image.write_all(&[pixel, pixel, pixel].concat())?;
                 }
             }
@@ -57,3 +98,14 @@ impl Recorder {
         Ok(())
     }
 }
+
+/// Takes every other line of the picture, as RGB
+fn downsample(buffer: &Buffer) -> Vec<[u8; 3]> {
+    buffer
+        .as_rgb()
+        .chunks(WIDTH as usize * 3)
+        .step_by(2)
+        .flat_map(|line| line.chunks(3))
+        .map(|pixel| [pixel[0], pixel[1], pixel[2]])
+        .collect()
+}
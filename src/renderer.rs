@@ -1,106 +1,109 @@
 use sdl2::{
+    audio::{AudioQueue, AudioSpecDesired},
     event::Event,
     keyboard::Keycode,
     render::{Texture, TextureCreator, WindowCanvas},
     surface::Surface,
     EventPump,
 };
-use std::str::FromStr;
-
-use crate::system::tia::{HEIGHT, WIDTH};
-#[derive(Debug, Copy, Clone)]
-pub enum InputType {
-    Joystick1Button,
-    Joystick1Up,
-    Joystick1Down,
-    Joystick1Left,
-    Joystick1Right,
-}
-
-impl FromStr for InputType {
-    type Err = String;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(match s {
-            "Joystick1Button" => InputType::Joystick1Button,
-            "Joystick1Up" => InputType::Joystick1Up,
-            "Joystick1Down" => InputType::Joystick1Down,
-            "Joystick1Left" => InputType::Joystick1Left,
-            "Joystick1Right" => InputType::Joystick1Right,
-            _ => return Err("Invalid input type".to_owned()),
-        })
-    }
-}
 
-#[derive(Debug, Copy, Clone)]
-pub enum WindowEvent {
-    None,
-    Quit,
-    InputStart(InputType),
-    InputEnd(InputType),
-}
+pub use crate::input::{InputType, WindowEvent};
+use crate::keymap::KeyMap;
+use crate::system::audio::SAMPLE_RATE;
+use crate::system::tia::{Buffer, BUFF_SIZE, HEIGHT, WIDTH};
 
-impl FromStr for WindowEvent {
-    type Err = String;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(match s {
-            "Quit" => WindowEvent::Quit,
-            input if s.starts_with("InputStart") => {
-                let input_type = input
-                    .get(11..s.len() - 1)
-                    .ok_or_else(|| "Invalid input_start".to_owned())?;
-                WindowEvent::InputStart(input_type.parse()?)
-            }
-            input if s.starts_with("InputEnd") => {
-                let input_type = input
-                    .get(9..s.len() - 1)
-                    .ok_or_else(|| "Invalid input_start".to_owned())?;
-                WindowEvent::InputEnd(input_type.parse()?)
-            }
-            _ => return Err("Invalid window event".to_owned()),
-        })
-    }
-}
+/// Stop queuing audio once this many bytes are waiting to play, a tenth of a second. Past that the
+/// sound falls behind the picture.
+const MAX_QUEUED_AUDIO: u32 = SAMPLE_RATE as u32 * 2 / 10;
 
 pub struct Renderer<'a> {
     event_pump: EventPump,
     canvas: WindowCanvas,
     texture: Texture<'a>,
+    audio: Option<AudioQueue<i16>>,
+    keymap: KeyMap,
+    // The last frame rendered, kept when blending frames
+    previous: Option<Box<Buffer>>,
 }
 
 impl<'a> Renderer<'a> {
-    pub fn setup() -> super::Result<Renderer<'a>> {
+    /// Opens the window. With `blend`, each frame shown is mixed with the one before it, so
+    /// sprites a game flickers on alternate frames look solid.
+    pub fn setup(scale: u32, keymap: KeyMap, blend: bool) -> super::Result<Renderer<'a>> {
         let sdl_context = sdl2::init()?;
         let video_subsystem = sdl_context.video()?;
+        let (width, height) = window_size(scale);
         let window = video_subsystem
-            .window("Stanley: Atari 2600 Emulator", 800, 600)
+            .window("Stanley: Atari 2600 Emulator", width, height)
             .position_centered()
             .build()
             .map_err(|e| e.to_string())?;
-        let canvas = window.into_canvas().index(find_sdl_gl_driver()?).build()?;
+        let mut canvas = window.into_canvas().index(find_sdl_gl_driver()?).build()?;
+        canvas.set_logical_size(width, height)?;
         let texture_creator: &'static TextureCreator<_> =
             Box::leak(Box::new(canvas.texture_creator()));
         // TODO: Should I use a supported opengl pixel formats?
         let surface = Surface::new(WIDTH, HEIGHT, sdl2::pixels::PixelFormatEnum::RGBA8888)?;
         let texture = surface.as_texture(texture_creator)?;
 
+        // Missing sound shouldn't stop a program from running
+        let audio = match open_audio_queue(&sdl_context) {
+            Ok(queue) => Some(queue),
+            Err(e) => {
+                eprintln!("Couldn't open audio device, running without sound: {e}");
+                None
+            }
+        };
+
         let event_pump = sdl_context.event_pump()?;
         Ok(Self {
             texture,
             canvas,
             event_pump,
+            audio,
+            keymap,
+            previous: blend.then(|| Box::new(Buffer([0; BUFF_SIZE]))),
         })
     }
 
-    pub fn render(&mut self, buffer: &crate::system::tia::Buffer) -> super::Result<()> {
-        self.texture
-            .update(None, &buffer.0, (4 * crate::system::tia::WIDTH) as usize)?;
+    pub fn render(&mut self, buffer: &Buffer) -> super::Result<()> {
+        let blended;
+        let pixels = match self.previous.as_mut() {
+            Some(previous) => {
+                blended = blend(buffer, previous);
+                previous.0.copy_from_slice(&buffer.0);
+                &blended.0
+            }
+            None => &buffer.0,
+        };
+        self.texture.update(None, pixels, (4 * WIDTH) as usize)?;
         self.canvas.copy(&self.texture, None, None)?;
         self.canvas.present();
         Ok(())
     }
 
+    /// Queues samples from the TIA to play. When the queue runs dry the device plays silence
+    /// until more arrive, and when it's too far ahead the samples are dropped.
+    pub fn queue_audio(&mut self, samples: &[i16]) -> super::Result<()> {
+        if let Some(queue) = &self.audio {
+            if queue.size() < MAX_QUEUED_AUDIO {
+                queue.queue_audio(samples)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The mouse drives paddle 0 across the window. Turning a paddle clockwise lowers its
+    /// resistance, so the right of the window is position 0.
+    fn paddle_position(&self, x: i32) -> WindowEvent {
+        let width = match self.canvas.output_size() {
+            Ok((width, _)) if width > 0 => width as i32,
+            _ => return WindowEvent::None,
+        };
+        let turned = x.clamp(0, width - 1) * u8::MAX as i32 / (width - 1).max(1);
+        WindowEvent::PaddleMoved(u8::MAX - turned as u8)
+    }
+
     pub fn handle_events(&mut self) -> WindowEvent {
         let mut events = self.event_pump.poll_iter();
         let event = events.next();
@@ -113,55 +116,83 @@ impl<'a> Renderer<'a> {
                     ..
                 },
             ) => WindowEvent::Quit,
+            // Turbo while held
             Some(Event::KeyDown {
-                keycode: Some(Keycode::F),
+                keycode: Some(Keycode::Tab),
                 ..
-            }) => WindowEvent::InputStart(InputType::Joystick1Button),
+            }) => WindowEvent::Turbo(true),
             Some(Event::KeyUp {
-                keycode: Some(Keycode::F),
+                keycode: Some(Keycode::Tab),
                 ..
-            }) => WindowEvent::InputEnd(InputType::Joystick1Button),
-            // Up
+            }) => WindowEvent::Turbo(false),
             Some(Event::KeyDown {
-                keycode: Some(Keycode::W),
+                keycode: Some(Keycode::P),
                 ..
-            }) => WindowEvent::InputStart(InputType::Joystick1Up),
-            Some(Event::KeyUp {
-                keycode: Some(Keycode::W),
-                ..
-            }) => WindowEvent::InputEnd(InputType::Joystick1Up),
-            // Down
+            }) => WindowEvent::Pause,
+            Some(Event::MouseMotion { x, .. }) => self.paddle_position(x),
+            // The mouse wheel turns the driving controller
+            Some(Event::MouseWheel { y, .. }) if y > 0 => {
+                WindowEvent::InputStart(InputType::Driving1Clockwise)
+            }
+            Some(Event::MouseWheel { y, .. }) if y < 0 => {
+                WindowEvent::InputStart(InputType::Driving1CounterClockwise)
+            }
+            // Save states
             Some(Event::KeyDown {
-                keycode: Some(Keycode::S),
+                keycode: Some(Keycode::F5),
                 ..
-            }) => WindowEvent::InputStart(InputType::Joystick1Down),
-            Some(Event::KeyUp {
-                keycode: Some(Keycode::S),
-                ..
-            }) => WindowEvent::InputEnd(InputType::Joystick1Down),
-            // Left
+            }) => WindowEvent::SaveState,
             Some(Event::KeyDown {
-                keycode: Some(Keycode::A),
+                keycode: Some(Keycode::F9),
                 ..
-            }) => WindowEvent::InputStart(InputType::Joystick1Left),
-            Some(Event::KeyUp {
-                keycode: Some(Keycode::A),
-                ..
-            }) => WindowEvent::InputEnd(InputType::Joystick1Left),
-            // Right
+            }) => WindowEvent::LoadState,
+            // Everything else is looked up in the keymap
             Some(Event::KeyDown {
-                keycode: Some(Keycode::D),
+                keycode: Some(keycode),
                 ..
-            }) => WindowEvent::InputStart(InputType::Joystick1Right),
+            }) => match self.keymap.get(keycode) {
+                Some(input) => WindowEvent::InputStart(input),
+                None => WindowEvent::None,
+            },
             Some(Event::KeyUp {
-                keycode: Some(Keycode::D),
+                keycode: Some(keycode),
                 ..
-            }) => WindowEvent::InputEnd(InputType::Joystick1Right),
+            }) => match self.keymap.get(keycode) {
+                Some(input) => WindowEvent::InputEnd(input),
+                None => WindowEvent::None,
+            },
             _ => WindowEvent::None,
         }
     }
 }
 
+/// The size of the window at a scale. The 2600's pixels are about twice as wide as they are tall.
+pub fn window_size(scale: u32) -> (u32, u32) {
+    let scale = scale.max(1);
+    (WIDTH * 2 * scale, HEIGHT * scale)
+}
+
+/// Mixes two frames half and half. Objects drawn in only one of them come out at half strength
+/// instead of disappearing.
+pub fn blend(current: &Buffer, previous: &Buffer) -> Buffer {
+    let mut blended = Buffer([0; BUFF_SIZE]);
+    for ((out, &a), &b) in blended.0.iter_mut().zip(&current.0).zip(&previous.0) {
+        *out = ((a as u16 + b as u16) / 2) as u8;
+    }
+    blended
+}
+
+fn open_audio_queue(sdl_context: &sdl2::Sdl) -> super::Result<AudioQueue<i16>> {
+    let desired = AudioSpecDesired {
+        freq: Some(SAMPLE_RATE),
+        channels: Some(1),
+        samples: None,
+    };
+    let queue = sdl_context.audio()?.open_queue(None, &desired)?;
+    queue.resume();
+    Ok(queue)
+}
+
 fn find_sdl_gl_driver() -> super::Result<u32> {
     for (index, item) in sdl2::render::drivers().enumerate() {
         if item.name == "opengl" {
@@ -170,3 +201,33 @@ fn find_sdl_gl_driver() -> super::Result<u32> {
     }
     Err("Couldn't find gl driver".into())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_size() {
+        assert_eq!(window_size(1), (320, 192));
+        assert_eq!(window_size(3), (960, 576));
+        assert_eq!(window_size(5), (1600, 960));
+        // There's always a window to look at
+        assert_eq!(window_size(0), (320, 192));
+    }
+
+    #[test]
+    fn test_blend() {
+        let mut current = Buffer([0; BUFF_SIZE]);
+        let mut previous = Buffer([0; BUFF_SIZE]);
+        // A sprite only drawn in the current frame, and background that's the same in both
+        current.0[..4].copy_from_slice(&[0xFF, 0x80, 0x00, 0xFF]);
+        current.0[4..8].copy_from_slice(&[0x10, 0x20, 0x30, 0xFF]);
+        previous.0[4..8].copy_from_slice(&[0x10, 0x20, 0x30, 0xFF]);
+
+        let blended = blend(&current, &previous);
+        assert_eq!(blended.0[..4], [0x7F, 0x40, 0x00, 0x7F]);
+        assert_eq!(blended.0[4..8], [0x10, 0x20, 0x30, 0xFF]);
+        // The frames themselves are left alone
+        assert_eq!(current.0[0], 0xFF);
+    }
+}
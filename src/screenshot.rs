@@ -0,0 +1,111 @@
+use std::fs;
+
+use crate::system::tia::{Buffer, HEIGHT, WIDTH};
+use crate::Result;
+
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+/// The most bytes an uncompressed deflate block can hold
+const MAX_STORED_BLOCK: usize = 0xFFFF;
+
+/// Writes the picture to a PNG file
+pub fn save_png(buffer: &Buffer, file_name: &str) -> Result<()> {
+    fs::write(file_name, encode_png(buffer))?;
+    Ok(())
+}
+
+/// Encodes the picture as a PNG. The 2600's pixels are about twice as wide as they are tall, so
+/// each one is doubled across to keep the picture's shape.
+///
+/// The image data isn't compressed, which keeps the encoder small. A screenshot comes out around
+/// 180KB.
+pub fn encode_png(buffer: &Buffer) -> Vec<u8> {
+    let width = WIDTH * 2;
+    let mut header = Vec::new();
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&HEIGHT.to_be_bytes());
+    // 8 bit RGB, default compression and filtering, not interlaced
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+    let mut image = Vec::with_capacity(HEIGHT as usize * (1 + width as usize * 3));
+    for line in buffer.as_rgb().chunks(WIDTH as usize * 3) {
+        // Each line starts with the filter type, 0 for none
+        image.push(0);
+        for pixel in line.chunks(3) {
+            image.extend_from_slice(&[pixel, pixel].concat());
+        }
+    }
+
+    let mut png = PNG_SIGNATURE.to_vec();
+    write_chunk(&mut png, b"IHDR", &header);
+    write_chunk(&mut png, b"IDAT", &zlib_stored(&image));
+    write_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// Wraps the data in a zlib stream of uncompressed deflate blocks
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut stream = vec![0x78, 0x01];
+    let mut blocks = data.chunks(MAX_STORED_BLOCK).peekable();
+    while let Some(block) = blocks.next() {
+        let is_final = blocks.peek().is_none();
+        stream.push(is_final as u8);
+        let len = block.len() as u16;
+        stream.extend_from_slice(&len.to_le_bytes());
+        stream.extend_from_slice(&(!len).to_le_bytes());
+        stream.extend_from_slice(block);
+    }
+    stream.extend_from_slice(&adler32(data).to_be_bytes());
+    stream
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in data {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::system::tia::BUFF_SIZE;
+
+    #[test]
+    fn test_encode_png() {
+        let png = encode_png(&Buffer([0x80; BUFF_SIZE]));
+        assert_eq!(png[..8], PNG_SIGNATURE);
+        assert_eq!(&png[12..16], b"IHDR");
+        assert_eq!(png[16..20], 320u32.to_be_bytes());
+        assert_eq!(png[20..24], 192u32.to_be_bytes());
+        assert_eq!(&png[png.len() - 8..png.len() - 4], b"IEND");
+    }
+
+    #[test]
+    fn test_checksums() {
+        assert_eq!(crc32(b"IEND"), 0xAE42_6082);
+        assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+    }
+}
@@ -0,0 +1,229 @@
+use super::state::{StateReader, StateWriter};
+use crate::error::Result;
+
+/// The TIA clocks its audio circuits twice a line
+pub const COLOR_CLOCKS_PER_SAMPLE: usize = 114;
+/// The rate samples come out of the TIA, the NTSC color clock of 3.58MHz divided by the clocks
+/// per sample
+pub const SAMPLE_RATE: i32 = 31_400;
+/// Each channel's volume goes up to 15, scale the two of them to fill most of an i16
+const VOLUME_SCALE: i16 = 1024;
+
+/// One of the two TIA audio channels. AUDF divides the audio clock down, and on each divided clock
+/// AUDC picks how the output bit changes: held high, toggled, or taken from one of the
+/// polynomial counters. AUDV sets the volume the output bit plays at.
+#[derive(Debug, Clone)]
+pub struct AudioChannel {
+    audc: u8,
+    audf: u8,
+    audv: u8,
+
+    divider: u8,
+    // Counts divided clocks for the modes that divide further
+    counter: u8,
+    poly4: u8,
+    poly5: u8,
+    poly9: u16,
+    output: bool,
+}
+
+impl Default for AudioChannel {
+    fn default() -> Self {
+        Self {
+            audc: 0,
+            audf: 0,
+            audv: 0,
+            divider: 0,
+            counter: 0,
+            // The polynomial counters lock up at zero
+            poly4: 0x0F,
+            poly5: 0x1F,
+            poly9: 0x1FF,
+            output: false,
+        }
+    }
+}
+
+impl AudioChannel {
+    pub fn set_audc(&mut self, value: u8) {
+        self.audc = value & 0x0F;
+    }
+
+    pub fn set_audf(&mut self, value: u8) {
+        self.audf = value & 0x1F;
+    }
+
+    pub fn set_audv(&mut self, value: u8) {
+        self.audv = value & 0x0F;
+    }
+
+    /// Runs one audio clock, returning the volume the channel plays at afterwards
+    pub fn clock(&mut self) -> u8 {
+        if self.divider < self.audf {
+            self.divider += 1;
+        } else {
+            self.divider = 0;
+            self.step();
+        }
+        self.volume()
+    }
+
+    pub fn volume(&self) -> u8 {
+        if self.output {
+            self.audv
+        } else {
+            0
+        }
+    }
+
+    pub fn save_state(&self, state: &mut StateWriter) {
+        for value in [
+            self.audc,
+            self.audf,
+            self.audv,
+            self.divider,
+            self.counter,
+            self.poly4,
+            self.poly5,
+        ] {
+            state.u8(value);
+        }
+        state.u16(self.poly9);
+        state.bool(self.output);
+    }
+
+    pub fn load_state(&mut self, state: &mut StateReader) -> Result<()> {
+        for value in [
+            &mut self.audc,
+            &mut self.audf,
+            &mut self.audv,
+            &mut self.divider,
+            &mut self.counter,
+            &mut self.poly4,
+            &mut self.poly5,
+        ] {
+            *value = state.u8()?;
+        }
+        self.poly9 = state.u16()?;
+        self.output = state.bool()?;
+        Ok(())
+    }
+
+    fn step(&mut self) {
+        match self.audc {
+            // Volume only, for playing samples by writing AUDV
+            0x0 | 0xB => self.output = true,
+            0x1 => self.output = self.step_poly4(),
+            // Div 15 -> 4 bit poly
+            0x2 => {
+                if self.count(15) {
+                    self.output = self.step_poly4();
+                }
+            }
+            // 5 bit poly -> 4 bit poly
+            0x3 => {
+                if self.step_poly5() {
+                    self.output = self.step_poly4();
+                }
+            }
+            // Pure tone, half the divided clock
+            0x4 | 0x5 => self.output = !self.output,
+            // Div 31 pure tone, high 18 clocks out of 31
+            0x6 | 0xA => {
+                self.count(31);
+                self.output = self.counter < 18;
+            }
+            // 5 bit poly -> div 2
+            0x7 => {
+                if self.step_poly5() {
+                    self.output = !self.output;
+                }
+            }
+            0x8 => self.output = self.step_poly9(),
+            0x9 => self.output = self.step_poly5(),
+            // Div 6 pure tone
+            0xC | 0xD => {
+                if self.count(3) {
+                    self.output = !self.output;
+                }
+            }
+            // Div 93 pure tone
+            0xE => {
+                self.count(93);
+                self.output = self.counter < 47;
+            }
+            // 5 bit poly -> div 6
+            _ => {
+                if self.step_poly5() && self.count(3) {
+                    self.output = !self.output;
+                }
+            }
+        }
+    }
+
+    /// Counts a divided clock, returning true each time the count wraps
+    fn count(&mut self, period: u8) -> bool {
+        self.counter = (self.counter + 1) % period;
+        self.counter == 0
+    }
+
+    fn step_poly4(&mut self) -> bool {
+        let bit = (self.poly4 ^ (self.poly4 >> 1)) & 0x01;
+        self.poly4 = (self.poly4 >> 1) | (bit << 3);
+        self.poly4 & 0x01 != 0
+    }
+
+    fn step_poly5(&mut self) -> bool {
+        let bit = (self.poly5 ^ (self.poly5 >> 2)) & 0x01;
+        self.poly5 = (self.poly5 >> 1) | (bit << 4);
+        self.poly5 & 0x01 != 0
+    }
+
+    fn step_poly9(&mut self) -> bool {
+        let bit = (self.poly9 ^ (self.poly9 >> 4)) & 0x01;
+        self.poly9 = (self.poly9 >> 1) | (bit << 8);
+        self.poly9 & 0x01 != 0
+    }
+}
+
+/// Mixes the volumes of the two channels into one sample
+pub fn mix(volume0: u8, volume1: u8) -> i16 {
+    (volume0 as i16 + volume1 as i16) * VOLUME_SCALE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn volumes(channel: &mut AudioChannel, clocks: usize) -> Vec<u8> {
+        (0..clocks).map(|_| channel.clock()).collect()
+    }
+
+    #[test]
+    fn test_pure_tone() {
+        let mut channel = AudioChannel::default();
+        channel.set_audc(0x4);
+        channel.set_audv(0x0F);
+        channel.set_audf(1);
+        // AUDF 1 divides by two, and the output toggles on each divided clock
+        assert_eq!(volumes(&mut channel, 8), [0, 15, 15, 0, 0, 15, 15, 0]);
+    }
+
+    #[test]
+    fn test_poly4_repeats_every_15_clocks() {
+        let mut channel = AudioChannel::default();
+        channel.set_audc(0x1);
+        channel.set_audv(0x08);
+        let first = volumes(&mut channel, 15);
+        assert!(first.contains(&0) && first.contains(&8));
+        assert_eq!(volumes(&mut channel, 15), first);
+    }
+
+    #[test]
+    fn test_silent_at_zero_volume() {
+        let mut channel = AudioChannel::default();
+        channel.set_audc(0x4);
+        assert!(volumes(&mut channel, 10).iter().all(|&volume| volume == 0));
+        assert_eq!(mix(15, 15), 30 * VOLUME_SCALE);
+    }
+}
@@ -0,0 +1,332 @@
+use std::io::Read;
+use std::str::FromStr;
+
+use super::state::{StateReader, StateWriter};
+use super::PROGRAM_SIZE;
+use crate::error::{Result, StanleyError};
+
+/// E0 carts are split into 1K slices
+const E0_SLICE_SIZE: usize = 0x400;
+/// Reading or writing $1FE0-$1FF7 switches one of the three lower slices of program memory. The
+/// top slice always holds the last bank.
+const E0_HOTSPOTS: std::ops::RangeInclusive<u16> = 0x1FE0..=0x1FF7;
+/// Accessing $1FF8 switches in the first 4K bank of an F8 cart, and $1FF9 the second
+const F8_HOTSPOTS: std::ops::RangeInclusive<u16> = 0x1FF8..=0x1FF9;
+/// FE carts watch for the stack access at $01FE made by JSR and RTS. The next byte on the bus is
+/// the high byte of the address being jumped to, which selects the bank.
+const FE_STACK_ADDRESS: u16 = 0x01FE;
+
+/// The ways a cartridge can map a ROM bigger than program memory into it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BankSwitching {
+    /// A 2K or 4K ROM that fills program memory
+    #[default]
+    None,
+    /// Parker Brothers 8K, four independently switched 1K slices
+    E0,
+    /// Activision 8K, two 4K banks switched by JSR and RTS
+    Fe,
+    /// Atari 8K, two 4K banks switched by accessing the hotspots at the top of program memory
+    F8,
+}
+
+impl BankSwitching {
+    fn rom_size(&self) -> usize {
+        match self {
+            Self::None => PROGRAM_SIZE,
+            Self::E0 | Self::Fe | Self::F8 => PROGRAM_SIZE * 2,
+        }
+    }
+}
+
+impl FromStr for BankSwitching {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        Ok(match s.to_lowercase().as_str() {
+            "none" | "2k" | "4k" => BankSwitching::None,
+            "e0" => BankSwitching::E0,
+            "fe" => BankSwitching::Fe,
+            "f8" => BankSwitching::F8,
+            _ => return Err(format!("Unknown bank switching scheme: {s}")),
+        })
+    }
+}
+
+/// A cartridge ROM and the bank switching hardware in front of it. The banks that are switched in
+/// are copied into the system's program memory, so reading the program stays a plain array index.
+#[derive(Debug, Default)]
+pub struct Cartridge {
+    rom: Vec<u8>,
+    bank_switching: BankSwitching,
+    // The last access was to the FE stack address, the next one picks the bank
+    fe_armed: bool,
+}
+
+impl Cartridge {
+    pub fn new(mut rom: Vec<u8>, bank_switching: BankSwitching) -> Result<Self> {
+        // A 2K ROM is mirrored into both halves of program memory
+        if bank_switching == BankSwitching::None && rom.len() == PROGRAM_SIZE / 2 {
+            rom = rom.repeat(2);
+        }
+        if rom.len() != bank_switching.rom_size() {
+            return Err(StanleyError::RomSize(rom.len(), bank_switching.rom_size()));
+        }
+        Ok(Self {
+            rom,
+            bank_switching,
+            fe_armed: false,
+        })
+    }
+
+    /// Reads a whole ROM image, from a file or a pipe, into a cartridge
+    pub fn read(mut reader: impl Read, bank_switching: BankSwitching) -> Result<Self> {
+        let mut rom = Vec::new();
+        reader.read_to_end(&mut rom)?;
+        Self::new(rom, bank_switching)
+    }
+
+    pub fn rom(&self) -> &[u8] {
+        &self.rom
+    }
+
+    pub fn rom_mut(&mut self) -> &mut [u8] {
+        &mut self.rom
+    }
+
+    /// The size of the banks the scheme switches, 1K slices for E0 and 4K for the rest
+    pub fn bank_size(&self) -> usize {
+        match self.bank_switching {
+            BankSwitching::E0 => E0_SLICE_SIZE,
+            BankSwitching::None | BankSwitching::Fe | BankSwitching::F8 => PROGRAM_SIZE,
+        }
+    }
+
+    /// Where in the ROM each bank that can be switched in at a program address keeps that byte
+    pub fn rom_offsets(&self, addr: u16) -> Vec<usize> {
+        let offset = (addr & 0x0FFF) as usize;
+        match self.bank_switching {
+            BankSwitching::None => vec![offset],
+            BankSwitching::Fe | BankSwitching::F8 => vec![offset, PROGRAM_SIZE + offset],
+            BankSwitching::E0 => {
+                let slice_offset = offset % E0_SLICE_SIZE;
+                // The top slice always holds the last bank
+                if offset / E0_SLICE_SIZE == 3 {
+                    vec![7 * E0_SLICE_SIZE + slice_offset]
+                } else {
+                    (0..8)
+                        .map(|bank| bank * E0_SLICE_SIZE + slice_offset)
+                        .collect()
+                }
+            }
+        }
+    }
+
+    /// Program memory as it is when the system powers on
+    pub fn program(&self) -> [u8; PROGRAM_SIZE] {
+        let mut program = [0; PROGRAM_SIZE];
+        match self.bank_switching {
+            BankSwitching::None | BankSwitching::Fe => {
+                program.copy_from_slice(&self.rom[..PROGRAM_SIZE]);
+            }
+            // F8 carts power on in the last bank, where the reset vector has to be
+            BankSwitching::F8 => self.switch_bank(&mut program, 1),
+            BankSwitching::E0 => {
+                for (slice, bank) in [4, 5, 6, 7].into_iter().enumerate() {
+                    self.switch_e0(&mut program, slice, bank);
+                }
+            }
+        }
+        program
+    }
+
+    /// Whether an access to a program address switches banks instead of touching the ROM
+    pub fn is_hotspot(&self, addr: u16) -> bool {
+        match self.bank_switching {
+            BankSwitching::E0 => E0_HOTSPOTS.contains(&(addr & 0x1FFF)),
+            BankSwitching::F8 => F8_HOTSPOTS.contains(&(addr & 0x1FFF)),
+            BankSwitching::None | BankSwitching::Fe => false,
+        }
+    }
+
+    /// Watches a read or write on the bus, switching banks into program memory when it hits a
+    /// hotspot.
+    pub fn access(&mut self, addr: u16, value: u8, program: &mut [u8; PROGRAM_SIZE]) {
+        match self.bank_switching {
+            BankSwitching::None => (),
+            BankSwitching::E0 => {
+                if self.is_hotspot(addr) {
+                    let hotspot = (addr & 0x1FFF) - E0_HOTSPOTS.start();
+                    self.switch_e0(program, (hotspot / 8) as usize, (hotspot % 8) as usize);
+                }
+            }
+            BankSwitching::Fe => {
+                if self.fe_armed {
+                    // $Fxxx is the first bank and $Dxxx is the second
+                    let bank = if value & 0x20 != 0 { 0 } else { 1 };
+                    self.switch_bank(program, bank);
+                }
+                self.fe_armed = addr & 0x1FFF == FE_STACK_ADDRESS;
+            }
+            BankSwitching::F8 => {
+                if self.is_hotspot(addr) {
+                    let bank = (addr & 0x1FFF) - F8_HOTSPOTS.start();
+                    self.switch_bank(program, bank as usize);
+                }
+            }
+        }
+    }
+
+    /// The banks that are switched in are saved with program memory, only the FE latch is left
+    pub fn save_state(&self, state: &mut StateWriter) {
+        state.bool(self.fe_armed);
+    }
+
+    pub fn load_state(&mut self, state: &mut StateReader) -> Result<()> {
+        self.fe_armed = state.bool()?;
+        Ok(())
+    }
+
+    fn switch_bank(&self, program: &mut [u8; PROGRAM_SIZE], bank: usize) {
+        program.copy_from_slice(&self.rom[bank * PROGRAM_SIZE..][..PROGRAM_SIZE]);
+    }
+
+    fn switch_e0(&self, program: &mut [u8; PROGRAM_SIZE], slice: usize, bank: usize) {
+        program[slice * E0_SLICE_SIZE..][..E0_SLICE_SIZE]
+            .copy_from_slice(&self.rom[bank * E0_SLICE_SIZE..][..E0_SLICE_SIZE]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A ROM where every byte holds the number of the 1K bank it is in
+    fn numbered_rom(size: usize) -> Vec<u8> {
+        (0..size).map(|i| (i / E0_SLICE_SIZE) as u8).collect()
+    }
+
+    #[test]
+    fn test_read() {
+        let mut rom = vec![0xEA; PROGRAM_SIZE / 2];
+        rom[0] = 0xA9;
+        let cartridge = Cartridge::read(rom.as_slice(), BankSwitching::None).unwrap();
+        // A 2K ROM shows up in both halves
+        assert_eq!(cartridge.program()[0], 0xA9);
+        assert_eq!(cartridge.program()[PROGRAM_SIZE / 2], 0xA9);
+
+        assert!(matches!(
+            Cartridge::read(&[0u8; 100][..], BankSwitching::None),
+            Err(StanleyError::RomSize(100, PROGRAM_SIZE))
+        ));
+    }
+
+    #[test]
+    fn test_rom_size() {
+        assert!(Cartridge::new(vec![0; 4096], BankSwitching::None).is_ok());
+        assert!(matches!(
+            Cartridge::new(vec![0; 3000], BankSwitching::None),
+            Err(StanleyError::RomSize(3000, 4096))
+        ));
+        assert!(matches!(
+            Cartridge::new(vec![0; 4096], BankSwitching::E0),
+            Err(StanleyError::RomSize(4096, 8192))
+        ));
+    }
+
+    #[test]
+    fn test_2k_rom_is_mirrored() {
+        let cartridge = Cartridge::new(numbered_rom(2048), BankSwitching::None).unwrap();
+        let program = cartridge.program();
+        assert_eq!(program[..2048], program[2048..]);
+        assert_eq!(program[0x400], 1);
+        assert_eq!(program[0xC00], 1);
+    }
+
+    #[test]
+    fn test_e0_hotspots() {
+        let mut cartridge = Cartridge::new(numbered_rom(8192), BankSwitching::E0).unwrap();
+        let mut program = cartridge.program();
+        assert_eq!(program[0x000], 4);
+        assert_eq!(program[0x400], 5);
+        assert_eq!(program[0x800], 6);
+        assert_eq!(program[0xC00], 7);
+
+        // Slice 0, bank 2
+        cartridge.access(0x1FE2, 0, &mut program);
+        // Slice 1, bank 0
+        cartridge.access(0xFFE8, 0, &mut program);
+        // Slice 2, bank 7
+        cartridge.access(0x1FF7, 0, &mut program);
+        assert_eq!(program[0x000], 2);
+        assert_eq!(program[0x3FF], 2);
+        assert_eq!(program[0x400], 0);
+        assert_eq!(program[0x800], 7);
+        assert_eq!(program[0xC00], 7);
+
+        // Outside the hotspots nothing changes
+        cartridge.access(0x1FF8, 0, &mut program);
+        cartridge.access(0x1FDF, 0, &mut program);
+        assert_eq!(program[0x000], 2);
+    }
+
+    #[test]
+    fn test_f8_hotspots() {
+        let mut cartridge = Cartridge::new(numbered_rom(8192), BankSwitching::F8).unwrap();
+        let mut program = cartridge.program();
+        assert_eq!(program[0], 4);
+
+        cartridge.access(0xFFF8, 0, &mut program);
+        assert_eq!(program[0], 0);
+        assert_eq!(program[0xFFF], 3);
+        cartridge.access(0x1FF9, 0, &mut program);
+        assert_eq!(program[0], 4);
+        cartridge.access(0x1FFA, 0, &mut program);
+        assert_eq!(program[0], 4);
+    }
+
+    #[test]
+    fn test_rom_offsets() {
+        let cartridge = Cartridge::new(numbered_rom(4096), BankSwitching::None).unwrap();
+        assert_eq!(cartridge.rom_offsets(0xF123), vec![0x123]);
+        assert_eq!(cartridge.bank_size(), 4096);
+
+        let cartridge = Cartridge::new(numbered_rom(8192), BankSwitching::F8).unwrap();
+        assert_eq!(cartridge.rom_offsets(0xF123), vec![0x123, 0x1123]);
+
+        let cartridge = Cartridge::new(numbered_rom(8192), BankSwitching::E0).unwrap();
+        assert_eq!(cartridge.bank_size(), 0x400);
+        assert_eq!(cartridge.rom_offsets(0xF523).len(), 8);
+        assert_eq!(cartridge.rom_offsets(0xF523)[2], 0x923);
+        assert_eq!(cartridge.rom_offsets(0xFD23), vec![0x1D23]);
+    }
+
+    #[test]
+    fn test_fe_switches_on_stack_access() {
+        let mut cartridge = Cartridge::new(numbered_rom(8192), BankSwitching::Fe).unwrap();
+        let mut program = cartridge.program();
+        assert_eq!(program[0], 0);
+
+        // JSR $D123: the return address goes to the stack, then the high byte of the target is
+        // read
+        cartridge.access(0x01FF, 0xF0, &mut program);
+        cartridge.access(0x01FE, 0x05, &mut program);
+        assert_eq!(program[0], 0);
+        cartridge.access(0xF003, 0xD1, &mut program);
+        assert_eq!(program[0], 4);
+
+        // Only the access right after the stack access counts
+        cartridge.access(0x1000, 0xF0, &mut program);
+        assert_eq!(program[0], 4);
+
+        // RTS: the low byte is pulled from $01FE, then the high byte from $01FF
+        cartridge.access(0x01FE, 0x05, &mut program);
+        cartridge.access(0x01FF, 0xF0, &mut program);
+        assert_eq!(program[0], 0);
+
+        // Zero page $FE doesn't count
+        cartridge.access(0x00FE, 0x00, &mut program);
+        cartridge.access(0x00FF, 0xD0, &mut program);
+        assert_eq!(program[0], 0);
+    }
+}
@@ -14,261 +14,399 @@ impl NtscColor {
     }
 }
 
-pub static COLOR_MAP: [NtscColor; 256] = [
+/// The colors an NTSC console makes. The TIA ignores D0 of its color registers, so a register's
+/// color is at `value >> 1`: the hue in the upper nibble and the luminance in the next 3 bits.
+pub const NTSC_PALETTE: [NtscColor; 128] = [
     NtscColor::new(0, 0, 0),
-    NtscColor::new(0, 0, 0),
-    NtscColor::new(64, 64, 64),
     NtscColor::new(64, 64, 64),
     NtscColor::new(108, 108, 108),
-    NtscColor::new(108, 108, 108),
     NtscColor::new(144, 144, 144),
-    NtscColor::new(144, 144, 144),
-    NtscColor::new(176, 176, 176),
     NtscColor::new(176, 176, 176),
     NtscColor::new(200, 200, 200),
-    NtscColor::new(200, 200, 200),
-    NtscColor::new(220, 220, 220),
     NtscColor::new(220, 220, 220),
     NtscColor::new(236, 236, 236),
-    NtscColor::new(236, 236, 236),
     NtscColor::new(68, 68, 0),
-    NtscColor::new(68, 68, 0),
-    NtscColor::new(100, 100, 16),
     NtscColor::new(100, 100, 16),
     NtscColor::new(132, 132, 36),
-    NtscColor::new(132, 132, 36),
-    NtscColor::new(160, 160, 52),
     NtscColor::new(160, 160, 52),
     NtscColor::new(184, 184, 64),
-    NtscColor::new(184, 184, 64),
     NtscColor::new(208, 208, 80),
-    NtscColor::new(208, 208, 80),
-    NtscColor::new(232, 232, 92),
     NtscColor::new(232, 232, 92),
     NtscColor::new(252, 252, 104),
-    NtscColor::new(252, 252, 104),
-    NtscColor::new(112, 40, 0),
     NtscColor::new(112, 40, 0),
     NtscColor::new(132, 68, 20),
-    NtscColor::new(132, 68, 20),
     NtscColor::new(152, 92, 40),
-    NtscColor::new(152, 92, 40),
-    NtscColor::new(172, 120, 60),
     NtscColor::new(172, 120, 60),
     NtscColor::new(188, 140, 76),
-    NtscColor::new(188, 140, 76),
-    NtscColor::new(204, 160, 92),
     NtscColor::new(204, 160, 92),
     NtscColor::new(220, 180, 104),
-    NtscColor::new(220, 180, 104),
     NtscColor::new(236, 200, 120),
-    NtscColor::new(236, 200, 120),
-    NtscColor::new(132, 24, 0),
     NtscColor::new(132, 24, 0),
     NtscColor::new(152, 52, 24),
-    NtscColor::new(152, 52, 24),
-    NtscColor::new(172, 80, 48),
     NtscColor::new(172, 80, 48),
     NtscColor::new(192, 104, 72),
-    NtscColor::new(192, 104, 72),
     NtscColor::new(208, 128, 92),
-    NtscColor::new(208, 128, 92),
-    NtscColor::new(224, 148, 112),
     NtscColor::new(224, 148, 112),
     NtscColor::new(236, 168, 128),
-    NtscColor::new(236, 168, 128),
-    NtscColor::new(252, 188, 148),
     NtscColor::new(252, 188, 148),
     NtscColor::new(136, 0, 0),
-    NtscColor::new(136, 0, 0),
     NtscColor::new(156, 32, 32),
-    NtscColor::new(156, 32, 32),
-    NtscColor::new(176, 60, 60),
     NtscColor::new(176, 60, 60),
     NtscColor::new(192, 88, 88),
-    NtscColor::new(192, 88, 88),
-    NtscColor::new(208, 112, 112),
     NtscColor::new(208, 112, 112),
     NtscColor::new(224, 136, 136),
-    NtscColor::new(224, 136, 136),
     NtscColor::new(236, 160, 160),
-    NtscColor::new(236, 160, 160),
-    NtscColor::new(252, 180, 180),
     NtscColor::new(252, 180, 180),
     NtscColor::new(120, 0, 92),
-    NtscColor::new(120, 0, 92),
-    NtscColor::new(140, 32, 116),
     NtscColor::new(140, 32, 116),
     NtscColor::new(160, 60, 136),
-    NtscColor::new(160, 60, 136),
     NtscColor::new(176, 88, 156),
-    NtscColor::new(176, 88, 156),
-    NtscColor::new(192, 112, 176),
     NtscColor::new(192, 112, 176),
     NtscColor::new(208, 132, 192),
-    NtscColor::new(208, 132, 192),
-    NtscColor::new(220, 156, 208),
     NtscColor::new(220, 156, 208),
     NtscColor::new(236, 176, 224),
-    NtscColor::new(236, 176, 224),
     NtscColor::new(72, 0, 120),
-    NtscColor::new(72, 0, 120),
-    NtscColor::new(96, 32, 144),
     NtscColor::new(96, 32, 144),
     NtscColor::new(120, 60, 164),
-    NtscColor::new(120, 60, 164),
-    NtscColor::new(140, 88, 184),
     NtscColor::new(140, 88, 184),
     NtscColor::new(160, 112, 204),
-    NtscColor::new(160, 112, 204),
     NtscColor::new(180, 132, 220),
-    NtscColor::new(180, 132, 220),
-    NtscColor::new(196, 156, 236),
     NtscColor::new(196, 156, 236),
     NtscColor::new(212, 176, 252),
-    NtscColor::new(212, 176, 252),
-    NtscColor::new(20, 0, 132),
     NtscColor::new(20, 0, 132),
     NtscColor::new(48, 32, 152),
-    NtscColor::new(48, 32, 152),
     NtscColor::new(76, 60, 172),
-    NtscColor::new(76, 60, 172),
-    NtscColor::new(104, 88, 192),
     NtscColor::new(104, 88, 192),
     NtscColor::new(124, 112, 208),
-    NtscColor::new(124, 112, 208),
-    NtscColor::new(148, 136, 224),
     NtscColor::new(148, 136, 224),
     NtscColor::new(168, 160, 236),
-    NtscColor::new(168, 160, 236),
     NtscColor::new(188, 180, 252),
-    NtscColor::new(188, 180, 252),
-    NtscColor::new(0, 0, 136),
     NtscColor::new(0, 0, 136),
     NtscColor::new(28, 32, 156),
-    NtscColor::new(28, 32, 156),
-    NtscColor::new(56, 64, 176),
     NtscColor::new(56, 64, 176),
     NtscColor::new(80, 92, 192),
-    NtscColor::new(80, 92, 192),
     NtscColor::new(104, 116, 208),
-    NtscColor::new(104, 116, 208),
-    NtscColor::new(124, 140, 224),
     NtscColor::new(124, 140, 224),
     NtscColor::new(144, 164, 236),
-    NtscColor::new(144, 164, 236),
-    NtscColor::new(164, 184, 252),
     NtscColor::new(164, 184, 252),
     NtscColor::new(0, 24, 124),
-    NtscColor::new(0, 24, 124),
     NtscColor::new(28, 56, 144),
-    NtscColor::new(28, 56, 144),
-    NtscColor::new(56, 84, 168),
     NtscColor::new(56, 84, 168),
     NtscColor::new(80, 112, 188),
-    NtscColor::new(80, 112, 188),
-    NtscColor::new(104, 136, 204),
     NtscColor::new(104, 136, 204),
     NtscColor::new(124, 156, 220),
-    NtscColor::new(124, 156, 220),
-    NtscColor::new(144, 180, 236),
     NtscColor::new(144, 180, 236),
     NtscColor::new(164, 200, 252),
-    NtscColor::new(164, 200, 252),
-    NtscColor::new(0, 44, 92),
     NtscColor::new(0, 44, 92),
     NtscColor::new(28, 76, 120),
-    NtscColor::new(28, 76, 120),
     NtscColor::new(56, 104, 144),
-    NtscColor::new(56, 104, 144),
-    NtscColor::new(80, 132, 172),
     NtscColor::new(80, 132, 172),
     NtscColor::new(104, 156, 192),
-    NtscColor::new(104, 156, 192),
-    NtscColor::new(124, 180, 212),
     NtscColor::new(124, 180, 212),
     NtscColor::new(144, 204, 232),
-    NtscColor::new(144, 204, 232),
     NtscColor::new(164, 224, 252),
-    NtscColor::new(164, 224, 252),
-    NtscColor::new(0, 60, 44),
     NtscColor::new(0, 60, 44),
     NtscColor::new(28, 92, 72),
-    NtscColor::new(28, 92, 72),
-    NtscColor::new(56, 124, 100),
     NtscColor::new(56, 124, 100),
     NtscColor::new(80, 156, 128),
-    NtscColor::new(80, 156, 128),
     NtscColor::new(104, 180, 148),
-    NtscColor::new(104, 180, 148),
-    NtscColor::new(124, 208, 172),
     NtscColor::new(124, 208, 172),
     NtscColor::new(144, 228, 192),
-    NtscColor::new(144, 228, 192),
-    NtscColor::new(164, 252, 212),
     NtscColor::new(164, 252, 212),
     NtscColor::new(0, 60, 0),
-    NtscColor::new(0, 60, 0),
     NtscColor::new(32, 92, 32),
-    NtscColor::new(32, 92, 32),
-    NtscColor::new(64, 124, 64),
     NtscColor::new(64, 124, 64),
     NtscColor::new(92, 156, 92),
-    NtscColor::new(92, 156, 92),
-    NtscColor::new(116, 180, 116),
     NtscColor::new(116, 180, 116),
     NtscColor::new(140, 208, 140),
-    NtscColor::new(140, 208, 140),
     NtscColor::new(164, 228, 164),
-    NtscColor::new(164, 228, 164),
-    NtscColor::new(184, 252, 184),
     NtscColor::new(184, 252, 184),
     NtscColor::new(20, 56, 0),
-    NtscColor::new(20, 56, 0),
-    NtscColor::new(52, 92, 28),
     NtscColor::new(52, 92, 28),
     NtscColor::new(80, 124, 56),
-    NtscColor::new(80, 124, 56),
     NtscColor::new(108, 152, 80),
-    NtscColor::new(108, 152, 80),
-    NtscColor::new(132, 180, 104),
     NtscColor::new(132, 180, 104),
     NtscColor::new(156, 204, 124),
-    NtscColor::new(156, 204, 124),
-    NtscColor::new(180, 228, 144),
     NtscColor::new(180, 228, 144),
     NtscColor::new(200, 252, 164),
-    NtscColor::new(200, 252, 164),
     NtscColor::new(44, 48, 0),
-    NtscColor::new(44, 48, 0),
-    NtscColor::new(76, 80, 28),
     NtscColor::new(76, 80, 28),
     NtscColor::new(104, 112, 52),
-    NtscColor::new(104, 112, 52),
-    NtscColor::new(132, 140, 76),
     NtscColor::new(132, 140, 76),
     NtscColor::new(156, 168, 100),
-    NtscColor::new(156, 168, 100),
-    NtscColor::new(180, 192, 120),
     NtscColor::new(180, 192, 120),
     NtscColor::new(204, 212, 136),
-    NtscColor::new(204, 212, 136),
-    NtscColor::new(224, 236, 156),
     NtscColor::new(224, 236, 156),
     NtscColor::new(68, 40, 0),
-    NtscColor::new(68, 40, 0),
     NtscColor::new(100, 72, 24),
-    NtscColor::new(100, 72, 24),
-    NtscColor::new(132, 104, 48),
     NtscColor::new(132, 104, 48),
     NtscColor::new(160, 132, 68),
-    NtscColor::new(160, 132, 68),
-    NtscColor::new(184, 156, 88),
     NtscColor::new(184, 156, 88),
     NtscColor::new(208, 180, 108),
-    NtscColor::new(208, 180, 108),
     NtscColor::new(232, 204, 124),
-    NtscColor::new(232, 204, 124),
-    NtscColor::new(252, 224, 140),
     NtscColor::new(252, 224, 140),
 ];
+
+/// PAL hues are laid out differently, and the first two and last two hues are grays
+pub const PAL_PALETTE: [NtscColor; 128] = [
+    NtscColor::new(0, 0, 0),
+    NtscColor::new(43, 43, 43),
+    NtscColor::new(82, 82, 82),
+    NtscColor::new(118, 118, 118),
+    NtscColor::new(151, 151, 151),
+    NtscColor::new(182, 182, 182),
+    NtscColor::new(210, 210, 210),
+    NtscColor::new(236, 236, 236),
+    NtscColor::new(0, 0, 0),
+    NtscColor::new(43, 43, 43),
+    NtscColor::new(82, 82, 82),
+    NtscColor::new(118, 118, 118),
+    NtscColor::new(151, 151, 151),
+    NtscColor::new(182, 182, 182),
+    NtscColor::new(210, 210, 210),
+    NtscColor::new(236, 236, 236),
+    NtscColor::new(128, 88, 0),
+    NtscColor::new(150, 113, 26),
+    NtscColor::new(171, 135, 50),
+    NtscColor::new(190, 156, 72),
+    NtscColor::new(207, 175, 92),
+    NtscColor::new(223, 192, 111),
+    NtscColor::new(238, 209, 128),
+    NtscColor::new(252, 224, 144),
+    NtscColor::new(68, 92, 0),
+    NtscColor::new(94, 121, 26),
+    NtscColor::new(118, 147, 50),
+    NtscColor::new(140, 172, 72),
+    NtscColor::new(160, 194, 92),
+    NtscColor::new(179, 215, 111),
+    NtscColor::new(196, 234, 128),
+    NtscColor::new(212, 252, 144),
+    NtscColor::new(112, 52, 0),
+    NtscColor::new(137, 81, 26),
+    NtscColor::new(160, 107, 50),
+    NtscColor::new(182, 132, 72),
+    NtscColor::new(201, 154, 92),
+    NtscColor::new(220, 175, 111),
+    NtscColor::new(236, 194, 128),
+    NtscColor::new(252, 212, 144),
+    NtscColor::new(0, 100, 20),
+    NtscColor::new(26, 128, 53),
+    NtscColor::new(50, 152, 82),
+    NtscColor::new(72, 176, 110),
+    NtscColor::new(92, 197, 135),
+    NtscColor::new(111, 217, 158),
+    NtscColor::new(128, 235, 180),
+    NtscColor::new(144, 252, 200),
+    NtscColor::new(112, 0, 20),
+    NtscColor::new(137, 26, 53),
+    NtscColor::new(160, 50, 82),
+    NtscColor::new(182, 72, 110),
+    NtscColor::new(201, 92, 135),
+    NtscColor::new(220, 111, 158),
+    NtscColor::new(236, 128, 180),
+    NtscColor::new(252, 144, 200),
+    NtscColor::new(0, 92, 92),
+    NtscColor::new(26, 118, 118),
+    NtscColor::new(50, 142, 142),
+    NtscColor::new(72, 164, 164),
+    NtscColor::new(92, 184, 184),
+    NtscColor::new(111, 203, 203),
+    NtscColor::new(128, 220, 220),
+    NtscColor::new(144, 236, 236),
+    NtscColor::new(112, 0, 88),
+    NtscColor::new(137, 26, 110),
+    NtscColor::new(160, 50, 130),
+    NtscColor::new(182, 72, 148),
+    NtscColor::new(201, 92, 165),
+    NtscColor::new(220, 111, 181),
+    NtscColor::new(236, 128, 196),
+    NtscColor::new(252, 144, 210),
+    NtscColor::new(0, 60, 112),
+    NtscColor::new(26, 88, 137),
+    NtscColor::new(50, 114, 160),
+    NtscColor::new(72, 138, 182),
+    NtscColor::new(92, 160, 201),
+    NtscColor::new(111, 181, 220),
+    NtscColor::new(128, 200, 236),
+    NtscColor::new(144, 218, 252),
+    NtscColor::new(88, 0, 112),
+    NtscColor::new(110, 26, 137),
+    NtscColor::new(130, 50, 160),
+    NtscColor::new(148, 72, 182),
+    NtscColor::new(165, 92, 201),
+    NtscColor::new(181, 111, 220),
+    NtscColor::new(196, 128, 236),
+    NtscColor::new(210, 144, 252),
+    NtscColor::new(0, 32, 112),
+    NtscColor::new(26, 60, 137),
+    NtscColor::new(50, 88, 160),
+    NtscColor::new(72, 112, 182),
+    NtscColor::new(92, 136, 201),
+    NtscColor::new(111, 158, 220),
+    NtscColor::new(128, 178, 236),
+    NtscColor::new(144, 198, 252),
+    NtscColor::new(60, 0, 128),
+    NtscColor::new(84, 26, 150),
+    NtscColor::new(108, 50, 171),
+    NtscColor::new(130, 72, 190),
+    NtscColor::new(150, 92, 207),
+    NtscColor::new(170, 111, 223),
+    NtscColor::new(188, 128, 238),
+    NtscColor::new(206, 144, 252),
+    NtscColor::new(0, 0, 136),
+    NtscColor::new(26, 26, 156),
+    NtscColor::new(50, 50, 174),
+    NtscColor::new(72, 72, 191),
+    NtscColor::new(92, 92, 206),
+    NtscColor::new(111, 111, 220),
+    NtscColor::new(128, 128, 233),
+    NtscColor::new(144, 144, 245),
+    NtscColor::new(0, 0, 0),
+    NtscColor::new(43, 43, 43),
+    NtscColor::new(82, 82, 82),
+    NtscColor::new(118, 118, 118),
+    NtscColor::new(151, 151, 151),
+    NtscColor::new(182, 182, 182),
+    NtscColor::new(210, 210, 210),
+    NtscColor::new(236, 236, 236),
+    NtscColor::new(0, 0, 0),
+    NtscColor::new(43, 43, 43),
+    NtscColor::new(82, 82, 82),
+    NtscColor::new(118, 118, 118),
+    NtscColor::new(151, 151, 151),
+    NtscColor::new(182, 182, 182),
+    NtscColor::new(210, 210, 210),
+    NtscColor::new(236, 236, 236),
+];
+
+/// SECAM consoles ignore the hue, and the luminance picks one of eight colors
+pub const SECAM_PALETTE: [NtscColor; 128] = [
+    NtscColor::new(0, 0, 0),
+    NtscColor::new(33, 33, 255),
+    NtscColor::new(240, 60, 121),
+    NtscColor::new(255, 80, 255),
+    NtscColor::new(127, 255, 0),
+    NtscColor::new(127, 255, 255),
+    NtscColor::new(255, 255, 63),
+    NtscColor::new(255, 255, 255),
+    NtscColor::new(0, 0, 0),
+    NtscColor::new(33, 33, 255),
+    NtscColor::new(240, 60, 121),
+    NtscColor::new(255, 80, 255),
+    NtscColor::new(127, 255, 0),
+    NtscColor::new(127, 255, 255),
+    NtscColor::new(255, 255, 63),
+    NtscColor::new(255, 255, 255),
+    NtscColor::new(0, 0, 0),
+    NtscColor::new(33, 33, 255),
+    NtscColor::new(240, 60, 121),
+    NtscColor::new(255, 80, 255),
+    NtscColor::new(127, 255, 0),
+    NtscColor::new(127, 255, 255),
+    NtscColor::new(255, 255, 63),
+    NtscColor::new(255, 255, 255),
+    NtscColor::new(0, 0, 0),
+    NtscColor::new(33, 33, 255),
+    NtscColor::new(240, 60, 121),
+    NtscColor::new(255, 80, 255),
+    NtscColor::new(127, 255, 0),
+    NtscColor::new(127, 255, 255),
+    NtscColor::new(255, 255, 63),
+    NtscColor::new(255, 255, 255),
+    NtscColor::new(0, 0, 0),
+    NtscColor::new(33, 33, 255),
+    NtscColor::new(240, 60, 121),
+    NtscColor::new(255, 80, 255),
+    NtscColor::new(127, 255, 0),
+    NtscColor::new(127, 255, 255),
+    NtscColor::new(255, 255, 63),
+    NtscColor::new(255, 255, 255),
+    NtscColor::new(0, 0, 0),
+    NtscColor::new(33, 33, 255),
+    NtscColor::new(240, 60, 121),
+    NtscColor::new(255, 80, 255),
+    NtscColor::new(127, 255, 0),
+    NtscColor::new(127, 255, 255),
+    NtscColor::new(255, 255, 63),
+    NtscColor::new(255, 255, 255),
+    NtscColor::new(0, 0, 0),
+    NtscColor::new(33, 33, 255),
+    NtscColor::new(240, 60, 121),
+    NtscColor::new(255, 80, 255),
+    NtscColor::new(127, 255, 0),
+    NtscColor::new(127, 255, 255),
+    NtscColor::new(255, 255, 63),
+    NtscColor::new(255, 255, 255),
+    NtscColor::new(0, 0, 0),
+    NtscColor::new(33, 33, 255),
+    NtscColor::new(240, 60, 121),
+    NtscColor::new(255, 80, 255),
+    NtscColor::new(127, 255, 0),
+    NtscColor::new(127, 255, 255),
+    NtscColor::new(255, 255, 63),
+    NtscColor::new(255, 255, 255),
+    NtscColor::new(0, 0, 0),
+    NtscColor::new(33, 33, 255),
+    NtscColor::new(240, 60, 121),
+    NtscColor::new(255, 80, 255),
+    NtscColor::new(127, 255, 0),
+    NtscColor::new(127, 255, 255),
+    NtscColor::new(255, 255, 63),
+    NtscColor::new(255, 255, 255),
+    NtscColor::new(0, 0, 0),
+    NtscColor::new(33, 33, 255),
+    NtscColor::new(240, 60, 121),
+    NtscColor::new(255, 80, 255),
+    NtscColor::new(127, 255, 0),
+    NtscColor::new(127, 255, 255),
+    NtscColor::new(255, 255, 63),
+    NtscColor::new(255, 255, 255),
+    NtscColor::new(0, 0, 0),
+    NtscColor::new(33, 33, 255),
+    NtscColor::new(240, 60, 121),
+    NtscColor::new(255, 80, 255),
+    NtscColor::new(127, 255, 0),
+    NtscColor::new(127, 255, 255),
+    NtscColor::new(255, 255, 63),
+    NtscColor::new(255, 255, 255),
+    NtscColor::new(0, 0, 0),
+    NtscColor::new(33, 33, 255),
+    NtscColor::new(240, 60, 121),
+    NtscColor::new(255, 80, 255),
+    NtscColor::new(127, 255, 0),
+    NtscColor::new(127, 255, 255),
+    NtscColor::new(255, 255, 63),
+    NtscColor::new(255, 255, 255),
+    NtscColor::new(0, 0, 0),
+    NtscColor::new(33, 33, 255),
+    NtscColor::new(240, 60, 121),
+    NtscColor::new(255, 80, 255),
+    NtscColor::new(127, 255, 0),
+    NtscColor::new(127, 255, 255),
+    NtscColor::new(255, 255, 63),
+    NtscColor::new(255, 255, 255),
+    NtscColor::new(0, 0, 0),
+    NtscColor::new(33, 33, 255),
+    NtscColor::new(240, 60, 121),
+    NtscColor::new(255, 80, 255),
+    NtscColor::new(127, 255, 0),
+    NtscColor::new(127, 255, 255),
+    NtscColor::new(255, 255, 63),
+    NtscColor::new(255, 255, 255),
+    NtscColor::new(0, 0, 0),
+    NtscColor::new(33, 33, 255),
+    NtscColor::new(240, 60, 121),
+    NtscColor::new(255, 80, 255),
+    NtscColor::new(127, 255, 0),
+    NtscColor::new(127, 255, 255),
+    NtscColor::new(255, 255, 63),
+    NtscColor::new(255, 255, 255),
+    NtscColor::new(0, 0, 0),
+    NtscColor::new(33, 33, 255),
+    NtscColor::new(240, 60, 121),
+    NtscColor::new(255, 80, 255),
+    NtscColor::new(127, 255, 0),
+    NtscColor::new(127, 255, 255),
+    NtscColor::new(255, 255, 63),
+    NtscColor::new(255, 255, 255),
+];
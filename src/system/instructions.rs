@@ -2,8 +2,9 @@ use std::collections::HashMap;
 use std::fmt;
 
 use super::System;
+use crate::error::{Result, StanleyError};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Instruction {
     Adc(AddressMode),
     And(AddressMode),
@@ -63,17 +64,18 @@ pub enum Instruction {
     Sty(AddressMode),
     // Illegal opcodes
     Dop(AddressMode),
+    Top(AddressMode),
+    Kil(AddressMode),
 }
 
 impl Instruction {
-    pub fn execute(&self, system: &mut System) -> crate::Result<usize> {
-        let mut clocks = 0;
+    pub fn execute(&self, system: &mut System) -> Result<usize> {
+        // Page crossings and taken branches add to this as they happen
+        let (mut clocks, _) = self.base_cycles();
 
         match self {
-            // TODO: Decimal mode
             Self::Adc(mode) => {
-                let address_value = mode.execute(system, &mut clocks);
-                clocks += 1;
+                let address_value = mode.execute(system);
                 let value = match address_value {
                     AddressValue::Value(val) => val,
                     AddressValue::Address {
@@ -90,6 +92,26 @@ impl Instruction {
                 let v = value as u16;
                 let c = system.chip.c as u16;
                 let result = a + v + c;
+                if system.chip.d {
+                    // Add each digit, carrying into the next one past 9.
+                    // See: http://www.6502.org/tutorials/decimal_mode.html
+                    let mut low = (a & 0x0F) + (v & 0x0F) + c;
+                    if low >= 0x0A {
+                        low = ((low + 0x06) & 0x0F) + 0x10;
+                    }
+                    let mut decimal = (a & 0xF0) + (v & 0xF0) + low;
+                    // The NMOS 6502 sets N and V before adjusting the high digit, and Z from the
+                    // binary result
+                    system.chip.v = (a ^ decimal) & (v ^ decimal) & 0x80 != 0;
+                    system.chip.n = decimal & 0x80 != 0;
+                    if decimal >= 0xA0 {
+                        decimal += 0x60;
+                    }
+                    system.chip.c = decimal > 0xFF;
+                    system.chip.z = result as u8 == 0;
+                    system.chip.a = decimal as u8;
+                    return Ok(clocks);
+                }
                 system.chip.c = result > 0xFF;
                 // Overflow is only set if the result is a different sign from both of the operands
                 // http://www.righto.com/2012/12/the-6502-overflow-flag-explained.html
@@ -100,10 +122,8 @@ impl Instruction {
                 system.chip.z = result == 0;
                 system.chip.a = result;
             }
-            // TODO: Decimal mode
             Self::Sbc(mode) => {
-                let address_value = mode.execute(system, &mut clocks);
-                clocks += 1;
+                let address_value = mode.execute(system);
                 let value = match address_value {
                     AddressValue::Value(val) => val,
                     AddressValue::Address {
@@ -120,6 +140,26 @@ impl Instruction {
                 let v = value;
                 let c = system.chip.c as u8;
                 let result = a.wrapping_add(!v).wrapping_add(c);
+                if system.chip.d {
+                    // Subtract each digit, borrowing from the next one below 0. The NMOS 6502 sets
+                    // the flags from the binary result.
+                    // See: http://www.6502.org/tutorials/decimal_mode.html
+                    let (a, v, c) = (a as i16, v as i16, c as i16);
+                    let mut low = (a & 0x0F) - (v & 0x0F) + c - 1;
+                    if low < 0 {
+                        low = ((low - 0x06) & 0x0F) - 0x10;
+                    }
+                    let mut decimal = (a & 0xF0) - (v & 0xF0) + low;
+                    if decimal < 0 {
+                        decimal -= 0x60;
+                    }
+                    system.chip.c = a - v + c > 0;
+                    system.chip.v = (a as u8 ^ result) & (!(v as u8) ^ result) & 0x80 != 0;
+                    system.chip.n = result & 0x80 != 0;
+                    system.chip.z = result == 0;
+                    system.chip.a = decimal as u8;
+                    return Ok(clocks);
+                }
                 system.chip.c = result & 0x80 == 0;
                 system.chip.v = (a ^ result) & ((!v) ^ result) & 0x80 != 0;
                 system.chip.n = result & 0x80 != 0;
@@ -129,8 +169,7 @@ impl Instruction {
                 system.chip.a = result;
             }
             Self::And(mode) | Self::Ora(mode) | Self::Eor(mode) => {
-                let address_value = mode.execute(system, &mut clocks);
-                clocks += 1;
+                let address_value = mode.execute(system);
                 let value = match address_value {
                     AddressValue::Value(val) => val,
                     AddressValue::Address {
@@ -153,7 +192,7 @@ impl Instruction {
                 system.chip.n = system.chip.a & 0x80 != 0;
             }
             Self::Asl(mode) | Self::Lsr(mode) | Self::Rol(mode) | Self::Ror(mode) => {
-                let address_value = mode.execute(system, &mut clocks);
+                let address_value = mode.execute(system);
                 let carry = system.chip.c as u8;
                 let mut c = false;
                 let mut z = false;
@@ -183,18 +222,12 @@ impl Instruction {
 
                 match address_value {
                     AddressValue::None => {
-                        clocks += 2;
                         system.chip.a = calc(system.chip.a);
                     }
-                    AddressValue::Address {
-                        addr, is_offset, ..
-                    } => {
-                        clocks += 3 + is_offset as usize;
-                        let val = {
-                            let val = system.memory_get(addr);
-                            calc(val)
-                        };
-                        system.memory_set(addr, val);
+                    AddressValue::Address { addr, .. } => {
+                        let value = system.memory_get(addr);
+                        let result = calc(value);
+                        write_back(system, addr, clocks, value, result);
                     }
                     _ => unreachable!(),
                 }
@@ -203,8 +236,7 @@ impl Instruction {
                 system.chip.n = n;
             }
             Self::Bit(mode) => {
-                let address_value = mode.execute(system, &mut clocks);
-                clocks += 1;
+                let address_value = mode.execute(system);
                 let value = match address_value {
                     AddressValue::Address { addr, .. } => system.memory_get(addr),
                     _ => unreachable!(),
@@ -222,8 +254,7 @@ impl Instruction {
             | Self::Bcs(mode)
             | Self::Bne(mode)
             | Self::Beq(mode) => {
-                let address_value = mode.execute(system, &mut clocks);
-                clocks += 2;
+                let address_value = mode.execute(system);
                 let should_branch = match self {
                     Self::Bpl(_) => !system.chip.n,
                     Self::Bmi(_) => system.chip.n,
@@ -246,17 +277,43 @@ impl Instruction {
                     system.chip.pc = addr;
                 }
             }
-            Self::Brk(_) | Self::Rti(_) => {
+            Self::Brk(_) => {
                 // Break is special. My basic understanding is that it is used to cause
                 // program-controlled irq. It pushes the status register to the the stack and the
                 // PC + 2. It can be used for some rare, but interesting tricks.
                 // See: http://archive.6502.org/books/mcs6500_family_programming_manual.pdf page
                 // 144 for details and examples.
-                unimplemented!("BRK and RTI not implemented -- save for a fun stream topic")
+                // The byte after BRK is padding, skipped on return
+                let ret = system.chip.pc.wrapping_add(1);
+                system.catch_up(3);
+                system.memory_set(system.chip.stack_address(), (ret >> 8) as u8);
+                system.chip.sp -= 1;
+                system.catch_up(4);
+                system.memory_set(system.chip.stack_address(), ret as u8);
+                system.chip.sp -= 1;
+                // The B flag only exists on the stack, it's how the handler tells BRK from IRQ
+                system.catch_up(5);
+                system.memory_set(system.chip.stack_address(), system.status() | 0b0001_0000);
+                system.chip.sp -= 1;
+                system.chip.i = true;
+
+                let low = system.memory_get(0xFFFE) as u16;
+                let high = system.memory_get(0xFFFF) as u16;
+                system.chip.pc = (high << 8) + low;
+            }
+            Self::Rti(_) => {
+                system.chip.sp += 1;
+                let register = system.memory_get(system.chip.stack_address());
+                system.status_set(register);
+                system.chip.sp += 1;
+                let low = system.memory_get(system.chip.stack_address()) as u16;
+                system.chip.sp += 1;
+                let high = system.memory_get(system.chip.stack_address()) as u16;
+                // Unlike RTS, the address on the stack is the one to return to
+                system.chip.pc = (high << 8) + low;
             }
             Self::Cmp(mode) | Self::Cpx(mode) | Self::Cpy(mode) => {
-                let address_value = mode.execute(system, &mut clocks);
-                clocks += 1;
+                let address_value = mode.execute(system);
                 let base = match self {
                     Self::Cmp(_) => system.chip.a,
                     Self::Cpx(_) => system.chip.x,
@@ -282,95 +339,76 @@ impl Instruction {
                 system.chip.c = base > value;
             }
             Self::Dec(mode) => {
-                let address_value = mode.execute(system, &mut clocks);
-                clocks += 3;
+                let address_value = mode.execute(system);
                 let addr = match address_value {
-                    AddressValue::Address {
-                        addr, is_offset, ..
-                    } => {
-                        clocks += is_offset as usize;
-                        addr
-                    }
+                    AddressValue::Address { addr, .. } => addr,
                     _ => unreachable!(),
                 };
                 let value = system.memory_get(addr);
                 let result = value.wrapping_sub(1);
                 system.chip.z = result == 0;
                 system.chip.n = result & 0x80 != 0;
-                system.memory_set(addr, result);
+                write_back(system, addr, clocks, value, result);
             }
             Self::Clc(_) => {
-                clocks += 2;
                 system.chip.c = false;
             }
             Self::Sec(_) => {
-                clocks += 2;
                 system.chip.c = true;
             }
             Self::Cli(_) => {
-                clocks += 2;
                 system.chip.i = false;
             }
             Self::Sei(_) => {
-                clocks += 2;
                 system.chip.i = true;
             }
             Self::Clv(_) => {
-                clocks += 2;
                 system.chip.v = false;
             }
             Self::Cld(_) => {
-                clocks += 2;
                 system.chip.d = false;
             }
             Self::Sed(_) => {
-                clocks += 2;
                 system.chip.d = true;
             }
             Self::Inc(mode) => {
-                let address_value = mode.execute(system, &mut clocks);
-                clocks += 3;
+                let address_value = mode.execute(system);
                 let addr = match address_value {
-                    AddressValue::Address {
-                        addr, is_offset, ..
-                    } => {
-                        clocks += is_offset as usize;
-                        addr
-                    }
+                    AddressValue::Address { addr, .. } => addr,
                     _ => unreachable!(),
                 };
                 let value = system.memory_get(addr);
                 let result = value.wrapping_add(1);
                 system.chip.z = result == 0;
                 system.chip.n = result & 0x80 != 0;
-                system.memory_set(addr, result);
+                write_back(system, addr, clocks, value, result);
             }
             Self::Jmp(mode) => {
-                let address_value = mode.execute(system, &mut clocks);
+                let address_value = mode.execute(system);
                 system.chip.pc = match address_value {
                     AddressValue::Address { addr, .. } => addr,
                     _ => unreachable!(),
                 };
             }
-            Self::Jsr(mode) => {
-                let address_value = mode.execute(system, &mut clocks);
-                clocks += 3;
-                let addr = match address_value {
-                    AddressValue::Address { addr, .. } => addr,
-                    _ => unreachable!(),
-                };
-
-                let ret_low = system.chip.pc as u8;
-                let ret_high = (system.chip.pc >> 8) as u8;
-                system.memory_set(system.chip.sp as u16, ret_high);
+            Self::Jsr(_) => {
+                // The return address is pushed between reading the low and high bytes of the
+                // target, like the hardware does. FE bank switching watches for the high byte right
+                // after the push.
+                let low = system.next_byte() as u16;
+                // PC is on the last byte of the instruction, one before the return address. RTS
+                // adds the one back.
+                let ret = system.chip.pc;
+                system.catch_up(4);
+                system.memory_set(system.chip.stack_address(), (ret >> 8) as u8);
                 system.chip.sp -= 1;
-                system.memory_set(system.chip.sp as u16, ret_low - 1);
+                system.catch_up(5);
+                system.memory_set(system.chip.stack_address(), ret as u8);
                 system.chip.sp -= 1;
-                system.chip.pc = addr;
+                let high = system.next_byte() as u16;
+                system.chip.pc = (high << 8) + low;
             }
             Self::Lda(mode) | Self::Ldx(mode) | Self::Ldy(mode) => {
-                let address_value = mode.execute(system, &mut clocks);
-                clocks += 1;
+                let address_value = mode.execute(system);
                 let value = match address_value {
                     AddressValue::Value(val) => val,
                     AddressValue::Address {
@@ -393,9 +431,8 @@ impl Instruction {
                 system.chip.n = value & 0x80 != 0;
                 *register = value;
             }
-            Self::Nop(_) => clocks += 2,
+            Self::Nop(_) => {}
             Self::Tax(_) | Self::Txa(_) | Self::Tay(_) | Self::Tya(_) => {
-                clocks += 2;
                 let (source, dest) = match self {
                     Self::Tax(_) => (system.chip.a, &mut system.chip.x),
                     Self::Txa(_) => (system.chip.x, &mut system.chip.a),
@@ -408,7 +445,6 @@ impl Instruction {
                 system.chip.n = source & 0x80 != 0;
             }
             Self::Dex(_) | Self::Dey(_) => {
-                clocks += 2;
                 let register = match self {
                     Self::Dex(_) => &mut system.chip.x,
                     Self::Dey(_) => &mut system.chip.y,
@@ -419,7 +455,6 @@ impl Instruction {
                 system.chip.n = *register & 0x80 != 0;
             }
             Self::Inx(_) | Self::Iny(_) => {
-                clocks += 2;
                 let register = match self {
                     Self::Inx(_) => &mut system.chip.x,
                     Self::Iny(_) => &mut system.chip.y,
@@ -430,15 +465,13 @@ impl Instruction {
                 system.chip.n = *register & 0x80 != 0;
             }
             Self::Rts(_) => {
-                clocks += 6;
                 system.chip.sp += 1;
-                let low = system.memory_get(system.chip.sp as u16) as u16;
+                let low = system.memory_get(system.chip.stack_address()) as u16;
                 system.chip.sp += 1;
-                let high = system.memory_get(system.chip.sp as u16) as u16;
+                let high = system.memory_get(system.chip.stack_address()) as u16;
                 system.chip.pc = (high << 8) + low + 1;
             }
             Self::Txs(_) | Self::Tsx(_) => {
-                clocks += 2;
                 let (source, dest) = match self {
                     Self::Tsx(_) => (system.chip.sp, &mut system.chip.x),
                     Self::Txs(_) => (system.chip.x, &mut system.chip.sp),
@@ -447,36 +480,28 @@ impl Instruction {
                 *dest = source;
             }
             Self::Pha(_) | Self::Php(_) => {
-                clocks += 3;
                 let value = match self {
                     Self::Pha(_) => system.chip.a,
                     Self::Php(_) => system.status(),
                     _ => unreachable!(),
                 };
-                system.memory_set(system.chip.sp as u16, value);
+                system.catch_up(clocks);
+                system.memory_set(system.chip.stack_address(), value);
                 system.chip.sp -= 1;
             }
             Self::Pla(_) => {
-                clocks += 4;
                 system.chip.sp += 1;
-                system.chip.a = system.memory_get(system.chip.sp as u16);
+                system.chip.a = system.memory_get(system.chip.stack_address());
             }
             Self::Plp(_) => {
-                clocks += 4;
                 system.chip.sp += 1;
-                let register = system.memory_get(system.chip.sp as u16);
+                let register = system.memory_get(system.chip.stack_address());
                 system.status_set(register);
             }
             Self::Sta(mode) | Self::Stx(mode) | Self::Sty(mode) => {
-                let address_value = mode.execute(system, &mut clocks);
-                clocks += 1;
+                let address_value = mode.execute(system);
                 let addr = match address_value {
-                    AddressValue::Address {
-                        addr, is_offset, ..
-                    } => {
-                        clocks += is_offset as usize;
-                        addr
-                    }
+                    AddressValue::Address { addr, .. } => addr,
                     _ => unreachable!(),
                 };
                 let value = match self {
@@ -486,27 +511,89 @@ impl Instruction {
                     _ => unreachable!(),
                 };
 
+                // The write lands on the last cycle, see `System::catch_up`
+                system.catch_up(clocks);
                 system.memory_set(addr, value);
             }
             // Illegal opcodes
-            Self::Dop(mode) => {
-                mode.execute(system, &mut clocks);
-                clocks += 1
+            // Double and triple byte NOPs decode their operand like any other instruction, then
+            // do nothing with it. Indexing across a page still costs a cycle.
+            Self::Dop(mode) | Self::Top(mode) => {
+                if let AddressValue::Address {
+                    page_boundary_crossed,
+                    ..
+                } = mode.execute(system)
+                {
+                    clocks += page_boundary_crossed as usize;
+                }
+            }
+            // KIL halts the real processor until it is reset, so there is no way to continue
+            Self::Kil(_) => {
+                return Err(StanleyError::CpuJammed(system.chip.pc.wrapping_sub(1)));
             }
         }
         Ok(clocks)
     }
 
-    pub fn format_arguments<'a, T>(
-        &self,
-        iter: &mut T,
-        symbol_map: &HashMap<u16, String>,
-        pc: u16,
-    ) -> String
-    where
-        T: Iterator<Item = (usize, &'a u8)>,
-    {
-        let mode = match self {
+    /// Decodes an opcode that was read from `pc`
+    pub fn decode(opcode: u8, pc: u16) -> Result<Self> {
+        Self::try_from(opcode).map_err(|opcode| StanleyError::UnknownOpcode(opcode, pc))
+    }
+
+    /// The cycles an instruction takes, and whether it takes one more when indexing crosses a page.
+    /// Branches take one more when they're taken, and another when they land on a different page,
+    /// so they're flagged too. KIL never finishes. This is where `execute` gets its timing from.
+    pub fn base_cycles(&self) -> (usize, bool) {
+        use AddressMode::*;
+        let mode = self.address_mode();
+        match self {
+            Self::Bpl(_)
+            | Self::Bmi(_)
+            | Self::Bvc(_)
+            | Self::Bvs(_)
+            | Self::Bcc(_)
+            | Self::Bcs(_)
+            | Self::Bne(_)
+            | Self::Beq(_) => (2, true),
+            Self::Brk(_) => (7, false),
+            Self::Jsr(_) | Self::Rts(_) | Self::Rti(_) => (6, false),
+            Self::Jmp(_) => (if *mode == AbsoluteI { 5 } else { 3 }, false),
+            Self::Pha(_) | Self::Php(_) => (3, false),
+            Self::Pla(_) | Self::Plp(_) => (4, false),
+            Self::Kil(_) => (0, false),
+            // Stores always take the time to fix up the high byte of an indexed address
+            Self::Sta(_) | Self::Stx(_) | Self::Sty(_) => match mode {
+                ZeroPage => (3, false),
+                ZeroPageX | ZeroPageY | Absolute => (4, false),
+                AbsoluteX | AbsoluteY => (5, false),
+                _ => (6, false),
+            },
+            // Read-modify-write instructions, which also always fix up the address
+            Self::Asl(_)
+            | Self::Lsr(_)
+            | Self::Rol(_)
+            | Self::Ror(_)
+            | Self::Inc(_)
+            | Self::Dec(_) => match mode {
+                Accumulator => (2, false),
+                ZeroPage => (5, false),
+                ZeroPageX | Absolute => (6, false),
+                _ => (7, false),
+            },
+            // Everything else reads its operand, or takes no operand at all
+            _ => match mode {
+                Implied | Accumulator | Immediate => (2, false),
+                ZeroPage => (3, false),
+                ZeroPageX | ZeroPageY | Absolute => (4, false),
+                AbsoluteX | AbsoluteY => (4, true),
+                ZeroPageIY => (5, true),
+                _ => (6, false),
+            },
+        }
+    }
+
+    pub fn address_mode(&self) -> &AddressMode {
+        match self {
             Self::Adc(mode)
             | Self::And(mode)
             | Self::Asl(mode)
@@ -564,8 +651,22 @@ impl Instruction {
             | Self::Stx(mode)
             | Self::Sty(mode)
             // Illegal opcodes
-            | Self::Dop(mode)=> mode,
-        };
+            | Self::Dop(mode)
+            | Self::Top(mode)
+            | Self::Kil(mode) => mode,
+        }
+    }
+
+    pub fn format_arguments<'a, T>(
+        &self,
+        iter: &mut T,
+        symbol_map: &HashMap<u16, String>,
+        pc: u16,
+    ) -> String
+    where
+        T: Iterator<Item = (usize, &'a u8)>,
+    {
+        let mode = self.address_mode();
 
         match mode {
             AddressMode::Absolute => {
@@ -614,11 +715,12 @@ impl Instruction {
             }
             AddressMode::Relative => {
                 let value = *iter.next().unwrap().1 as i8;
-                let addr = (pc + 2).wrapping_add(value as u16);
+                // Branches are relative to the instruction after them
+                let addr = pc.wrapping_add(2).wrapping_add(value as u16);
                 symbol_map
                     .get(&(addr & 0x1FFF))
                     .map(|sym| sym.to_owned())
-                    .unwrap_or_else(|| format!("${addr:02X}"))
+                    .unwrap_or_else(|| format!("${addr:04X}"))
             }
             AddressMode::ZeroPage => {
                 let addr = *iter.next().unwrap().1 as u16;
@@ -633,7 +735,7 @@ impl Instruction {
                     .get(&(addr & 0x1FFF))
                     .map(|sym| sym.to_owned())
                     .unwrap_or_else(|| format!("${addr:02X}"));
-                format!("(${addr}, X)")
+                format!("({addr}, X)")
             }
             AddressMode::ZeroPageY => {
                 let addr = *iter.next().unwrap().1 as u16;
@@ -641,7 +743,7 @@ impl Instruction {
                     .get(&(addr & 0x1FFF))
                     .map(|sym| sym.to_owned())
                     .unwrap_or_else(|| format!("${addr:02X}"));
-                format!("${addr}, Y")
+                format!("{addr}, Y")
             }
             AddressMode::ZeroPageX => {
                 let addr = *iter.next().unwrap().1 as u16;
@@ -649,7 +751,7 @@ impl Instruction {
                     .get(&(addr & 0x1FFF))
                     .map(|sym| sym.to_owned())
                     .unwrap_or_else(|| format!("${addr:02X}"));
-                format!("${addr}, X")
+                format!("{addr}, X")
             }
             AddressMode::ZeroPageIY => {
                 let addr = *iter.next().unwrap().1 as u16;
@@ -657,17 +759,26 @@ impl Instruction {
                     .get(&(addr & 0x1FFF))
                     .map(|sym| sym.to_owned())
                     .unwrap_or_else(|| format!("${addr:02X}"));
-                format!("(${addr}), Y")
+                format!("({addr}), Y")
             }
         }
     }
 }
 
+/// Writes the result of a read-modify-write instruction on its last cycle. The 6502 writes the
+/// value it read back the cycle before, so a strobe register like HMOVE is strobed twice.
+fn write_back(system: &mut System, addr: u16, clocks: usize, value: u8, result: u8) {
+    system.catch_up(clocks - 1);
+    system.memory_set(addr, value);
+    system.catch_up(clocks);
+    system.memory_set(addr, result);
+}
+
 impl fmt::Display for Instruction {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let name = match self {
             Self::Adc(_) => "ADC",
-            Self::And(_) => "ADD",
+            Self::And(_) => "AND",
             Self::Asl(_) => "ASL",
             Self::Bit(_) => "BIT",
             Self::Bpl(_) => "BPL",
@@ -724,6 +835,8 @@ impl fmt::Display for Instruction {
             Self::Sty(_) => "STY",
             // Illegal Opcodes
             Self::Dop(_) => "DOP",
+            Self::Top(_) => "TOP",
+            Self::Kil(_) => "KIL",
         };
         write!(f, "{}", name.to_owned())
     }
@@ -743,7 +856,7 @@ impl fmt::Display for Instruction {
 // 13. Zero Page Indexed with X zp,x 4 (3) 4 (3) 2 2
 // 14. Zero Page Indexed with Y zp,y 4 4 2 2
 // 16. Zero Page Indirect Indexed with Y (zp),y
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum AddressMode {
     Absolute,
     AbsoluteX,
@@ -790,16 +903,34 @@ impl AddressValue {
 }
 
 impl AddressMode {
-    pub fn execute(&self, system: &mut System, clocks: &mut usize) -> AddressValue {
+    /// The number of operand bytes that follow the opcode
+    pub fn operand_bytes(&self) -> usize {
+        match self {
+            Self::Absolute | Self::AbsoluteX | Self::AbsoluteY | Self::AbsoluteI => 2,
+            Self::Accumulator | Self::Implied => 0,
+            Self::Immediate
+            | Self::Relative
+            | Self::ZeroPage
+            | Self::ZeroPageIX
+            | Self::ZeroPageY
+            | Self::ZeroPageX
+            | Self::ZeroPageIY => 1,
+        }
+    }
+
+    /// The number of bytes an instruction takes, counting the opcode
+    pub fn byte_length(&self) -> u16 {
+        1 + self.operand_bytes() as u16
+    }
+
+    pub fn execute(&self, system: &mut System) -> AddressValue {
         match self {
             Self::Absolute => {
-                *clocks += 3;
                 let low = system.next_byte() as u16;
                 let high = system.next_byte() as u16;
                 AddressValue::addr((high << 8) + low)
             }
             Self::AbsoluteX | Self::AbsoluteY => {
-                *clocks += 3;
                 let offset = match self {
                     Self::AbsoluteX => system.chip.x,
                     Self::AbsoluteY => system.chip.y,
@@ -813,52 +944,41 @@ impl AddressMode {
                 AddressValue::offset_addr(offset_addr, page_boundary_crossed)
             }
             Self::AbsoluteI => {
-                *clocks += 5;
                 let low = system.next_byte() as u16;
                 let high = system.next_byte() as u16;
                 let addr = (high << 8) + low;
                 let low_indirect = system.memory_get(addr) as u16;
-                let high_indirect = system.memory_get(addr + 1) as u16;
+                // The 6502 doesn't carry into the high byte of the pointer, so a pointer at $xxFF
+                // reads its high byte from $xx00
+                let high_addr = (addr & 0xFF00) | (addr.wrapping_add(1) & 0x00FF);
+                let high_indirect = system.memory_get(high_addr) as u16;
                 AddressValue::addr((high_indirect << 8) + low_indirect)
             }
-            Self::Immediate => {
-                *clocks += 1;
-                AddressValue::Value(system.next_byte())
-            }
+            Self::Immediate => AddressValue::Value(system.next_byte()),
             Self::Relative => {
                 let arg = system.next_byte() as i8;
                 AddressValue::addr(system.chip.pc.wrapping_add(arg as u16))
             }
-            Self::ZeroPage => {
-                *clocks += 2;
-                AddressValue::addr(system.next_byte() as u16)
-            }
-            // TODO: wrap-around
+            Self::ZeroPage => AddressValue::addr(system.next_byte() as u16),
+            // Indexing and pointers wrap around within the zero page
             Self::ZeroPageIX => {
-                *clocks += 5;
-                let addr = (system.next_byte() + system.chip.x) as u16;
-                let low_indirect = system.memory_get(addr) as u16;
-                let high_indirect = system.memory_get(addr + 1) as u16;
+                let addr = system.next_byte().wrapping_add(system.chip.x);
+                let low_indirect = system.memory_get(addr as u16) as u16;
+                let high_indirect = system.memory_get(addr.wrapping_add(1) as u16) as u16;
                 AddressValue::addr((high_indirect << 8) + low_indirect)
             }
-            // TODO: wrap-around
             Self::ZeroPageY => {
-                *clocks += 3;
-                AddressValue::addr((system.next_byte() + system.chip.y) as u16)
+                AddressValue::addr(system.next_byte().wrapping_add(system.chip.y) as u16)
             }
-            // TODO: wrap-around
             Self::ZeroPageX => {
-                *clocks += 3;
-                AddressValue::addr((system.next_byte() + system.chip.x) as u16)
+                AddressValue::addr(system.next_byte().wrapping_add(system.chip.x) as u16)
             }
-            // TODO: wrap-around
             Self::ZeroPageIY => {
-                *clocks += 4;
                 let offset = system.chip.y as i8;
-                let addr = system.next_byte() as u16;
+                let addr = system.next_byte();
 
-                let low_indirect = system.memory_get(addr) as u16;
-                let high_indirect = system.memory_get(addr + 1) as u16;
+                let low_indirect = system.memory_get(addr as u16) as u16;
+                let high_indirect = system.memory_get(addr.wrapping_add(1) as u16) as u16;
                 let addr = (high_indirect << 8) + low_indirect;
 
                 let offset_addr = addr.wrapping_add(offset as u16);
@@ -871,10 +991,11 @@ impl AddressMode {
     }
 }
 
+/// Fails with the opcode when it isn't one we know
 impl TryFrom<u8> for Instruction {
-    type Error = String;
+    type Error = u8;
 
-    fn try_from(value: u8) -> Result<Self, Self::Error> {
+    fn try_from(value: u8) -> std::result::Result<Self, Self::Error> {
         use AddressMode::*;
         use Instruction::*;
 
@@ -1031,8 +1152,16 @@ impl TryFrom<u8> for Instruction {
             0x94 => Sty(ZeroPageY),
             0x8C => Sty(Absolute),
             // Illegal opcodes
-            0x04 => Dop(ZeroPage),
-            _ => return Err(format!("Unknown instruction: {:02X}", value)),
+            0x1A | 0x3A | 0x5A | 0x7A | 0xDA | 0xFA => Nop(Implied),
+            0x80 | 0x82 | 0x89 | 0xC2 | 0xE2 => Dop(Immediate),
+            0x04 | 0x44 | 0x64 => Dop(ZeroPage),
+            0x14 | 0x34 | 0x54 | 0x74 | 0xD4 | 0xF4 => Dop(ZeroPageX),
+            0x0C => Top(Absolute),
+            0x1C | 0x3C | 0x5C | 0x7C | 0xDC | 0xFC => Top(AbsoluteX),
+            0x02 | 0x12 | 0x22 | 0x32 | 0x42 | 0x52 | 0x62 | 0x72 | 0x92 | 0xB2 | 0xD2 | 0xF2 => {
+                Kil(Implied)
+            }
+            _ => return Err(value),
         })
     }
 }
@@ -1044,7 +1173,158 @@ mod test {
     use super::AddressMode::*;
     use super::Instruction::*;
     use super::*;
-    use crate::system::System;
+    use crate::system::cartridge::BankSwitching;
+    use crate::system::{System, SystemConfig};
+
+    /// Steps the system, returning the clocks the instruction took
+    fn step_clocks(system: &mut System) -> usize {
+        let start_clocks = system.clocks;
+        system.step().unwrap();
+        system.clocks - start_clocks
+    }
+
+    /// Formats the operand of an instruction at an address
+    fn format(inst: &Instruction, operands: &[u8], pc: u16) -> String {
+        inst.format_arguments(&mut operands.iter().enumerate(), &HashMap::new(), pc)
+    }
+
+    #[test]
+    fn format_branch_targets() {
+        // Backward and forward from the start of the cartridge
+        assert_eq!(format(&Bne(Relative), &[0xFC], 0xF010), "$F00E");
+        assert_eq!(format(&Beq(Relative), &[0x10], 0xF010), "$F022");
+        // Low addresses keep all four digits, and the target wraps around the address space
+        assert_eq!(format(&Bcc(Relative), &[0x02], 0x0080), "$0084");
+        assert_eq!(format(&Bcs(Relative), &[0x00], 0xFFFE), "$0000");
+
+        let symbols = HashMap::from([(0x100E, "loop".to_owned())]);
+        let target =
+            Bne(Relative).format_arguments(&mut [0xFC].iter().enumerate(), &symbols, 0xF010);
+        assert_eq!(target, "loop");
+    }
+
+    #[test]
+    fn format_zero_page_indexed() {
+        assert_eq!(format(&Lda(ZeroPageIX), &[0x80], 0xF000), "($80, X)");
+        assert_eq!(format(&Lda(ZeroPageIY), &[0x80], 0xF000), "($80), Y");
+        assert_eq!(format(&Lda(ZeroPageX), &[0x80], 0xF000), "$80, X");
+        assert_eq!(format(&Ldx(ZeroPageY), &[0x80], 0xF000), "$80, Y");
+
+        // Symbols don't get a $
+        let symbols = HashMap::from([(0x0080, "ptr".to_owned())]);
+        let format = |inst: Instruction| {
+            inst.format_arguments(&mut [0x80].iter().enumerate(), &symbols, 0xF000)
+        };
+        assert_eq!(format(Lda(ZeroPageIX)), "(ptr, X)");
+        assert_eq!(format(Lda(ZeroPageIY)), "(ptr), Y");
+        assert_eq!(format(Lda(ZeroPageX)), "ptr, X");
+        assert_eq!(format(Ldx(ZeroPageY)), "ptr, Y");
+    }
+
+    #[test]
+    fn base_cycles() {
+        for (opcode, cycles) in [
+            (0xA9, (2, false)), // LDA #
+            (0xBD, (4, true)),  // LDA abs, X
+            (0x9D, (5, false)), // STA abs, X
+            (0x91, (6, false)), // STA (zp), Y
+            (0xB1, (5, true)),  // LDA (zp), Y
+            (0xFE, (7, false)), // INC abs, X
+            (0x6C, (5, false)), // JMP (ind)
+            (0xD0, (2, true)),  // BNE
+            (0x00, (7, false)), // BRK
+            (0x68, (4, false)), // PLA
+        ] {
+            let inst = Instruction::try_from(opcode).unwrap();
+            assert_eq!(inst.base_cycles(), cycles, "{opcode:02X} {inst}");
+        }
+    }
+
+    #[test]
+    fn base_cycles_match_documented_timing() {
+        // NMOS 6502 cycles for each opcode, including the illegal ones.
+        // See: https://www.masswerk.at/6502/6502_instruction_set.html
+        #[rustfmt::skip]
+        const CYCLES: [usize; 256] = [
+            7, 6, 0, 8, 3, 3, 5, 5, 3, 2, 2, 2, 4, 4, 6, 6,
+            2, 5, 0, 8, 4, 4, 6, 6, 2, 4, 2, 7, 4, 4, 7, 7,
+            6, 6, 0, 8, 3, 3, 5, 5, 4, 2, 2, 2, 4, 4, 6, 6,
+            2, 5, 0, 8, 4, 4, 6, 6, 2, 4, 2, 7, 4, 4, 7, 7,
+            6, 6, 0, 8, 3, 3, 5, 5, 3, 2, 2, 2, 3, 4, 6, 6,
+            2, 5, 0, 8, 4, 4, 6, 6, 2, 4, 2, 7, 4, 4, 7, 7,
+            6, 6, 0, 8, 3, 3, 5, 5, 4, 2, 2, 2, 5, 4, 6, 6,
+            2, 5, 0, 8, 4, 4, 6, 6, 2, 4, 2, 7, 4, 4, 7, 7,
+            2, 6, 2, 6, 3, 3, 3, 3, 2, 2, 2, 2, 4, 4, 4, 4,
+            2, 6, 0, 6, 4, 4, 4, 4, 2, 5, 2, 5, 5, 5, 5, 5,
+            2, 6, 2, 6, 3, 3, 3, 3, 2, 2, 2, 2, 4, 4, 4, 4,
+            2, 5, 0, 5, 4, 4, 4, 4, 2, 4, 2, 4, 4, 4, 4, 4,
+            2, 6, 2, 8, 3, 3, 5, 5, 2, 2, 2, 2, 4, 4, 6, 6,
+            2, 5, 0, 8, 4, 4, 6, 6, 2, 4, 2, 7, 4, 4, 7, 7,
+            2, 6, 2, 8, 3, 3, 5, 5, 2, 2, 2, 2, 4, 4, 6, 6,
+            2, 5, 0, 8, 4, 4, 6, 6, 2, 4, 2, 7, 4, 4, 7, 7,
+        ];
+        // Opcodes that take a cycle more when indexing crosses a page, or a branch is taken
+        const EXTRA: [u8; 40] = [
+            0x10, 0x11, 0x19, 0x1C, 0x1D, 0x30, 0x31, 0x39, 0x3C, 0x3D, 0x50, 0x51, 0x59, 0x5C,
+            0x5D, 0x70, 0x71, 0x79, 0x7C, 0x7D, 0x90, 0xB0, 0xB1, 0xB3, 0xB9, 0xBB, 0xBC, 0xBD,
+            0xBE, 0xBF, 0xD0, 0xD1, 0xD9, 0xDC, 0xDD, 0xF0, 0xF1, 0xF9, 0xFC, 0xFD,
+        ];
+
+        let mut checked = 0;
+        for opcode in 0..=0xFF {
+            let inst = match Instruction::try_from(opcode) {
+                // KIL never finishes, so it has no timing
+                Ok(Kil(_)) | Err(_) => continue,
+                Ok(inst) => inst,
+            };
+            assert_eq!(
+                inst.base_cycles(),
+                (CYCLES[opcode as usize], EXTRA.contains(&opcode)),
+                "{opcode:02X} {inst}"
+            );
+            checked += 1;
+        }
+        // Every official opcode is covered
+        assert!(checked >= 151);
+    }
+
+    #[test]
+    fn base_cycles_match_execution() {
+        for opcode in 0..=0xFF {
+            let inst = match Instruction::try_from(opcode) {
+                Ok(Kil(_)) | Err(_) => continue,
+                Ok(inst) => inst,
+            };
+            // Operands point at RAM, and X and Y are 0, so nothing crosses a page
+            let mut program = [0; 4096];
+            program[0..3].copy_from_slice(&[opcode, 0x80, 0x00]);
+            let mut system = System::new(program);
+            // Room to push and pull a few bytes
+            system.chip.sp = 0xFC;
+            let clocks = step_clocks(&mut system);
+
+            let (cycles, extra) = inst.base_cycles();
+            match inst.address_mode() {
+                // Branches with the flags clear are taken or not depending on the branch, and the
+                // ones taken go back across a page
+                AddressMode::Relative => assert!(
+                    clocks == cycles || clocks == cycles + 2,
+                    "{opcode:02X} {inst}"
+                ),
+                _ => assert_eq!(clocks, cycles, "{opcode:02X} {inst}"),
+            }
+            assert!(extra || clocks == cycles, "{opcode:02X} {inst}");
+        }
+    }
+
+    #[test]
+    fn decoded_instructions_compare() {
+        let decoded: Instruction = 0xA9.try_into().unwrap();
+        assert_eq!(decoded, Lda(Immediate));
+        assert_eq!(decoded.clone(), Instruction::try_from(0xA9).unwrap());
+        assert_ne!(decoded, Instruction::try_from(0xA5).unwrap());
+        assert_ne!(decoded, Ldx(Immediate));
+    }
 
     #[test]
     fn adds_cycle_with_page_boundary_cross() {
@@ -1339,57 +1619,50 @@ mod test {
     #[test]
     fn test_address_mode_absolute_execute() {
         let mut system = System::new([0u8; 4096]);
-        let mut clocks = 0;
         let pc = system.chip.pc;
 
         system.program[0] = 0xFF;
         system.program[1] = 0x00;
         assert_eq!(
-            AddressMode::Absolute.execute(&mut system, &mut clocks),
+            AddressMode::Absolute.execute(&mut system),
             AddressValue::addr(0x00FF)
         );
-        assert_eq!(clocks, 3);
         assert_eq!(system.chip.pc, pc + 2);
     }
 
     #[test]
     fn test_address_mode_absolute_x_execute() {
         let mut system = System::new([0u8; 4096]);
-        let mut clocks = 0;
         let pc = system.chip.pc;
         system.chip.x = 1;
 
         system.program[0] = 0xFF;
         system.program[1] = 0x00;
         assert_eq!(
-            AddressMode::AbsoluteX.execute(&mut system, &mut clocks),
+            AddressMode::AbsoluteX.execute(&mut system),
             AddressValue::offset_addr(0x0100, true)
         );
-        assert_eq!(clocks, 3);
         assert_eq!(system.chip.pc, pc + 2);
     }
 
     #[test]
     fn test_address_mode_absolute_y_execute() {
         let mut system = System::new([0u8; 4096]);
-        let mut clocks = 0;
         let pc = system.chip.pc;
         system.chip.y = 1;
 
         system.program[0] = 0xFE;
         system.program[1] = 0xFF;
         assert_eq!(
-            AddressMode::AbsoluteY.execute(&mut system, &mut clocks),
+            AddressMode::AbsoluteY.execute(&mut system),
             AddressValue::offset_addr(0xFFFF, false)
         );
-        assert_eq!(clocks, 3);
         assert_eq!(system.chip.pc, pc + 2);
     }
 
     #[test]
     fn test_address_mode_absolute_i_execute() {
         let mut system = System::new([0u8; 4096]);
-        let mut clocks = 0;
         let pc = system.chip.pc;
 
         system.program[0] = 128;
@@ -1397,55 +1670,65 @@ mod test {
         system.memory[0] = 0x11;
         system.memory[1] = 0xE8;
         assert_eq!(
-            AddressMode::AbsoluteI.execute(&mut system, &mut clocks),
+            AddressMode::AbsoluteI.execute(&mut system),
             AddressValue::addr(0xE811)
         );
-        assert_eq!(clocks, 5);
         assert_eq!(system.chip.pc, pc + 2);
     }
 
+    #[test]
+    fn test_address_mode_absolute_i_page_wrap() {
+        let mut system = System::new([0u8; 4096]);
+
+        // JMP ($10FF)
+        system.chip.pc = 0x1800;
+        system.program[0x800] = 0xFF;
+        system.program[0x801] = 0x10;
+        system.program[0x0FF] = 0x34;
+        system.program[0x000] = 0x12;
+        system.program[0x100] = 0x56;
+        assert_eq!(
+            AddressMode::AbsoluteI.execute(&mut system),
+            AddressValue::addr(0x1234)
+        );
+    }
+
     #[test]
     fn test_address_mode_accumulator_execute() {
         // TODO: accumulator mode seems to be faster than other modes. It's only used with shifts
         // and rotates. The normal math won't work
         let mut system = System::new([0u8; 4096]);
-        let mut clocks = 0;
         let pc = system.chip.pc;
 
         assert_eq!(
-            AddressMode::Accumulator.execute(&mut system, &mut clocks),
+            AddressMode::Accumulator.execute(&mut system),
             AddressValue::None
         );
-        assert_eq!(clocks, 0);
         assert_eq!(system.chip.pc, pc);
     }
 
     #[test]
     fn test_address_mode_immediate_execute() {
         let mut system = System::new([0u8; 4096]);
-        let mut clocks = 0;
         let pc = system.chip.pc;
 
         system.program[0] = 0x76;
         assert_eq!(
-            AddressMode::Immediate.execute(&mut system, &mut clocks),
+            AddressMode::Immediate.execute(&mut system),
             AddressValue::Value(0x76)
         );
-        assert_eq!(clocks, 1);
         assert_eq!(system.chip.pc, pc + 1);
     }
 
     #[test]
     fn test_address_mode_implied_execute() {
         let mut system = System::new([0u8; 4096]);
-        let mut clocks = 0;
         let pc = system.chip.pc;
 
         assert_eq!(
-            AddressMode::Implied.execute(&mut system, &mut clocks),
+            AddressMode::Implied.execute(&mut system),
             AddressValue::None
         );
-        assert_eq!(clocks, 0);
         assert_eq!(system.chip.pc, pc);
     }
 
@@ -1454,46 +1737,40 @@ mod test {
         // TODO: All clock calculations will have to be done in the instruction itself since the
         // state of the chip is required to determine if clocks are added
         let mut system = System::new([0u8; 4096]);
-        let mut clocks = 0;
         let pc = system.chip.pc;
 
         system.program[0] = 0x76;
         system.program[1] = 0xFE;
         // Address with offset from end of instruction
         assert_eq!(
-            AddressMode::Relative.execute(&mut system, &mut clocks),
+            AddressMode::Relative.execute(&mut system),
             AddressValue::addr(0x1077)
         );
-        assert_eq!(clocks, 0);
         assert_eq!(system.chip.pc, pc + 1);
 
         assert_eq!(
-            AddressMode::Relative.execute(&mut system, &mut clocks),
+            AddressMode::Relative.execute(&mut system),
             AddressValue::addr(0x1000)
         );
-        assert_eq!(clocks, 0);
         assert_eq!(system.chip.pc, pc + 2);
     }
 
     #[test]
     fn test_address_mode_zero_page_execute() {
         let mut system = System::new([0u8; 4096]);
-        let mut clocks = 0;
         let pc = system.chip.pc;
 
         system.program[0] = 0x76;
         assert_eq!(
-            AddressMode::ZeroPage.execute(&mut system, &mut clocks),
+            AddressMode::ZeroPage.execute(&mut system),
             AddressValue::addr(0x0076)
         );
-        assert_eq!(clocks, 2);
         assert_eq!(system.chip.pc, pc + 1);
     }
 
     #[test]
     fn test_address_mode_zero_page_ix_execute() {
         let mut system = System::new([0u8; 4096]);
-        let mut clocks = 0;
         let pc = system.chip.pc;
         system.chip.x = 1;
 
@@ -1501,49 +1778,71 @@ mod test {
         system.memory[1] = 0xEF;
         system.memory[2] = 0xBE;
         assert_eq!(
-            AddressMode::ZeroPageIX.execute(&mut system, &mut clocks),
+            AddressMode::ZeroPageIX.execute(&mut system),
             AddressValue::addr(0xBEEF)
         );
-        assert_eq!(clocks, 5);
         assert_eq!(system.chip.pc, pc + 1);
     }
 
     #[test]
     fn test_address_mode_zero_page_y_execute() {
         let mut system = System::new([0u8; 4096]);
-        let mut clocks = 0;
         let pc = system.chip.pc;
         system.chip.y = 0x10;
 
         system.program[0] = 0x00;
         assert_eq!(
-            AddressMode::ZeroPageY.execute(&mut system, &mut clocks),
+            AddressMode::ZeroPageY.execute(&mut system),
             AddressValue::addr(0x0010)
         );
-        assert_eq!(clocks, 3);
         assert_eq!(system.chip.pc, pc + 1);
     }
 
     #[test]
     fn test_address_mode_zero_page_x_execute() {
         let mut system = System::new([0u8; 4096]);
-        let mut clocks = 0;
         let pc = system.chip.pc;
         system.chip.x = 0x10;
 
         system.program[0] = 0x00;
         assert_eq!(
-            AddressMode::ZeroPageX.execute(&mut system, &mut clocks),
+            AddressMode::ZeroPageX.execute(&mut system),
             AddressValue::addr(0x0010)
         );
-        assert_eq!(clocks, 3);
         assert_eq!(system.chip.pc, pc + 1);
     }
 
+    #[test]
+    fn test_address_mode_zero_page_wrap_around() {
+        let mut system = System::new([0u8; 4096]);
+        system.chip.x = 0x20;
+        system.chip.y = 0x30;
+
+        system.program[0] = 0xF0;
+        system.program[1] = 0xF0;
+        assert_eq!(
+            AddressMode::ZeroPageX.execute(&mut system),
+            AddressValue::addr(0x0010)
+        );
+        assert_eq!(
+            AddressMode::ZeroPageY.execute(&mut system),
+            AddressValue::addr(0x0020)
+        );
+
+        // $F0 + $90 reads the pointer from $80
+        system.chip.x = 0x90;
+        system.program[2] = 0xF0;
+        system.memory[0] = 0xEF;
+        system.memory[1] = 0xBE;
+        assert_eq!(
+            AddressMode::ZeroPageIX.execute(&mut system),
+            AddressValue::addr(0xBEEF)
+        );
+    }
+
     #[test]
     fn test_address_mode_zero_page_iy_execute() {
         let mut system = System::new([0u8; 4096]);
-        let mut clocks = 0;
         let pc = system.chip.pc;
         system.chip.y = 0x10;
 
@@ -1551,10 +1850,9 @@ mod test {
         system.memory[0] = 0xEF;
         system.memory[1] = 0xBE;
         assert_eq!(
-            AddressMode::ZeroPageIY.execute(&mut system, &mut clocks),
+            AddressMode::ZeroPageIY.execute(&mut system),
             AddressValue::offset_addr(0xBEEF + 0x10, false)
         );
-        assert_eq!(clocks, 4);
         assert_eq!(system.chip.pc, pc + 1);
     }
 
@@ -1605,6 +1903,53 @@ mod test {
         assert!(system.chip.v);
     }
 
+    #[test]
+    fn test_adc_decimal_mode() {
+        let mut system = System::new([0u8; 4096]);
+        system.chip.d = true;
+
+        let mut add = |a, value, carry| {
+            system.chip.a = a;
+            system.chip.c = carry;
+            system.chip.pc = 0x1000;
+            system.program[0] = value;
+            Adc(Immediate).execute(&mut system).unwrap();
+            (system.chip.a, system.chip.c)
+        };
+        assert_eq!(add(0x09, 0x01, false), (0x10, false));
+        assert_eq!(add(0x50, 0x50, false), (0x00, true));
+        assert_eq!(add(0x99, 0x01, false), (0x00, true));
+        assert_eq!(add(0x58, 0x46, true), (0x05, true));
+        assert_eq!(add(0x12, 0x34, false), (0x46, false));
+
+        // N and V come from the result before the high digit is adjusted
+        add(0x79, 0x01, false);
+        assert!(system.chip.n);
+        assert!(system.chip.v);
+        assert!(!system.chip.z);
+    }
+
+    #[test]
+    fn test_sbc_decimal_mode() {
+        let mut system = System::new([0u8; 4096]);
+        system.chip.d = true;
+
+        let mut subtract = |a, value, carry| {
+            system.chip.a = a;
+            system.chip.c = carry;
+            system.chip.pc = 0x1000;
+            system.program[0] = value;
+            Sbc(Immediate).execute(&mut system).unwrap();
+            (system.chip.a, system.chip.c)
+        };
+        assert_eq!(subtract(0x10, 0x01, true), (0x09, true));
+        assert_eq!(subtract(0x46, 0x12, true), (0x34, true));
+        assert_eq!(subtract(0x40, 0x13, true), (0x27, true));
+        assert_eq!(subtract(0x32, 0x02, false), (0x29, true));
+        assert_eq!(subtract(0x00, 0x01, true), (0x99, false));
+        assert_eq!(subtract(0x12, 0x21, true), (0x91, false));
+    }
+
     #[test]
     fn test_instruction_type_sbc_execute() {
         let mut system = System::new([0u8; 4096]);
@@ -1710,7 +2055,7 @@ mod test {
         // ASL accumulator
         // 2 clocks
         system.chip.a = 0b1010_1010;
-        let clocks = Asl(Implied).execute(&mut system).unwrap();
+        let clocks = Asl(Accumulator).execute(&mut system).unwrap();
         assert_eq!(system.chip.a, 0b0101_0100);
         assert_eq!(clocks, 2);
         assert!(!system.chip.n);
@@ -1731,6 +2076,51 @@ mod test {
     }
 
     // Bit,
+    #[test]
+    fn test_shift_accumulator_and_memory_flags_match() {
+        for carry in [false, true] {
+            for value in [0x00, 0x01, 0x40, 0x7F, 0x80, 0x81, 0xFF] {
+                let pairs = [
+                    (Asl(Accumulator), Asl(ZeroPage)),
+                    (Lsr(Accumulator), Lsr(ZeroPage)),
+                    (Rol(Accumulator), Rol(ZeroPage)),
+                    (Ror(Accumulator), Ror(ZeroPage)),
+                ];
+                for (accumulator, memory) in pairs {
+                    let mut accumulator_system = System::new([0u8; 4096]);
+                    accumulator_system.chip.a = value;
+                    accumulator_system.chip.c = carry;
+                    accumulator.execute(&mut accumulator_system).unwrap();
+
+                    let mut memory_system = System::new([0u8; 4096]);
+                    memory_system.program[0] = 0x80;
+                    memory_system.memory[0] = value;
+                    memory_system.chip.c = carry;
+                    memory.execute(&mut memory_system).unwrap();
+
+                    let message = format!("{accumulator} {value:02X} carry: {carry}");
+                    assert_eq!(
+                        accumulator_system.chip.a, memory_system.memory[0],
+                        "{message}"
+                    );
+                    assert_eq!(accumulator_system.chip.n, memory_system.chip.n, "{message}");
+                    assert_eq!(accumulator_system.chip.z, memory_system.chip.z, "{message}");
+                    assert_eq!(accumulator_system.chip.c, memory_system.chip.c, "{message}");
+                    assert_eq!(
+                        memory_system.chip.z,
+                        memory_system.memory[0] == 0,
+                        "{message}"
+                    );
+                    assert_eq!(
+                        memory_system.chip.n,
+                        memory_system.memory[0] & 0x80 != 0,
+                        "{message}"
+                    );
+                }
+            }
+        }
+    }
+
     #[test]
     fn test_instruction_type_bit_execute() {
         let mut system = System::new([0u8; 4096]);
@@ -1889,11 +2279,51 @@ mod test {
     }
 
     #[test]
-    #[should_panic(expected = "BRK and RTI not implemented -- save for a fun stream topic")]
     fn test_instruction_type_brk_execute() {
         let mut system = System::new([0u8; 4096]);
+        system.program[0xFFE] = 0x34;
+        system.program[0xFFF] = 0xF2;
+        system.chip.sp = 0xFF;
+        system.chip.pc = 0x1001;
+        system.chip.c = true;
+
+        let clocks = Instruction::Brk(Implied).execute(&mut system).unwrap();
+        assert_eq!(clocks, 7);
+        assert_eq!(system.chip.pc, 0xF234);
+        assert_eq!(system.chip.sp, 0xFC);
+        assert!(system.chip.i);
+        // PC + 2 from the BRK, then the status with B set
+        assert_eq!(system.memory[0x7F], 0x10);
+        assert_eq!(system.memory[0x7E], 0x02);
+        assert_eq!(system.memory[0x7D], 0b0011_0001);
+    }
+
+    #[test]
+    fn test_brk_rti_round_trip() {
+        let mut program = [0xEA; 4096];
+        // BRK; <padding>; NOP
+        program[0] = 0x00;
+        // RTI at $1100
+        program[0x100] = 0x40;
+        program[0xFFE] = 0x00;
+        program[0xFFF] = 0x11;
+        let mut system = System::new(program);
+        system.chip.sp = 0xFF;
+        system.chip.n = true;
+        system.chip.c = true;
 
-        Instruction::Brk(Implied).execute(&mut system).unwrap();
+        assert_eq!(step_clocks(&mut system), 7);
+        assert_eq!(system.chip.pc, 0x1100);
+        assert!(system.chip.i);
+        system.chip.n = false;
+        system.chip.c = false;
+
+        assert_eq!(step_clocks(&mut system), 6);
+        assert_eq!(system.chip.pc, 0x1002);
+        assert_eq!(system.chip.sp, 0xFF);
+        assert!(system.chip.n);
+        assert!(system.chip.c);
+        assert!(!system.chip.i);
     }
 
     #[test]
@@ -2143,6 +2573,48 @@ mod test {
         assert_eq!(clocks, 6);
     }
 
+    #[test]
+    fn test_jsr_return_address_crosses_page() {
+        let mut program = [0xEAu8; 4096];
+        // $10FD: JSR $1200, returning to $1100
+        program[0x0FD..0x100].copy_from_slice(&[0x20, 0x00, 0x12]);
+        // $1200: RTS
+        program[0x200] = 0x60;
+        let mut system = System::new(program);
+        system.chip.pc = 0x10FD;
+        system.chip.sp = 0xFF;
+
+        system.step().unwrap();
+        assert_eq!(system.chip.pc, 0x1200);
+        assert_eq!(system.memory[0x7F], 0x10);
+        assert_eq!(system.memory[0x7E], 0xFF);
+
+        system.step().unwrap();
+        assert_eq!(system.chip.pc, 0x1100);
+    }
+
+    #[test]
+    fn test_jsr_pushes_before_reading_the_high_byte() {
+        let mut rom = vec![0xEA; 8192];
+        // JSR $D010, into the second bank
+        rom[..3].copy_from_slice(&[0x20, 0x10, 0xD0]);
+        rom[4096 + 0x10] = 0x42;
+        let config = SystemConfig {
+            bank_switching: BankSwitching::Fe,
+            ..SystemConfig::default()
+        };
+        let mut system = System::with_config(&rom, config).unwrap();
+        system.chip.sp = 0xFF;
+
+        // FE switches on the read right after the push to $01FE, which is the high byte of the
+        // target only if the return address is pushed first
+        assert_eq!(step_clocks(&mut system), 6);
+        assert_eq!(system.chip.pc, 0xD010);
+        assert_eq!(system.program[0x10], 0x42);
+        assert_eq!(system.memory[0x7F], 0x10);
+        assert_eq!(system.memory[0x7E], 0x02);
+    }
+
     #[test]
     fn test_instruction_type_lda_execute() {
         let mut system = System::new([0u8; 4096]);
@@ -2507,11 +2979,38 @@ mod test {
     }
 
     #[test]
-    #[should_panic(expected = "BRK and RTI not implemented -- save for a fun stream topic")]
     fn test_instruction_type_rti_execute() {
         let mut system = System::new([0u8; 4096]);
 
-        Rti(Implied).execute(&mut system).unwrap();
+        system.chip.sp = 0xFC;
+        system.memory[0x7D] = 0b1000_0011;
+        system.memory[0x7E] = 0x56;
+        system.memory[0x7F] = 0xF3;
+        let clocks = Rti(Implied).execute(&mut system).unwrap();
+        assert_eq!(clocks, 6);
+        assert_eq!(system.chip.pc, 0xF356);
+        assert_eq!(system.chip.sp, 0xFF);
+        assert!(system.chip.n);
+        assert!(system.chip.z);
+        assert!(system.chip.c);
+    }
+
+    #[test]
+    fn test_stack_is_on_page_one() {
+        let mut system = System::new([0u8; 4096]);
+        system.chip.sp = 0xFF;
+        system.chip.a = 0x42;
+
+        Pha(Implied).execute(&mut system).unwrap();
+        assert_eq!(system.chip.sp, 0xFE);
+        assert_eq!(system.memory_get(0x01FF), 0x42);
+        // Page one mirrors the same RAM as the zero page
+        assert_eq!(system.memory[0x7F], 0x42);
+
+        system.memory_set(0x01FF, 0x24);
+        Pla(Implied).execute(&mut system).unwrap();
+        assert_eq!(system.chip.a, 0x24);
+        assert_eq!(system.chip.sp, 0xFF);
     }
 
     #[test]
@@ -2655,4 +3154,167 @@ mod test {
         let clocks = Dop(ZeroPage).execute(&mut system).unwrap();
         assert_eq!(clocks, 3);
     }
+
+    #[test]
+    fn test_illegal_nop_clocks() {
+        let cases = [
+            (0x1A, 2),
+            (0x3A, 2),
+            (0x5A, 2),
+            (0x7A, 2),
+            (0xDA, 2),
+            (0xFA, 2),
+            (0x80, 2),
+            (0x82, 2),
+            (0x89, 2),
+            (0xC2, 2),
+            (0xE2, 2),
+            (0x04, 3),
+            (0x44, 3),
+            (0x64, 3),
+            (0x14, 4),
+            (0x34, 4),
+            (0x54, 4),
+            (0x74, 4),
+            (0xD4, 4),
+            (0xF4, 4),
+            (0x0C, 4),
+            (0x1C, 4),
+            (0x3C, 4),
+            (0x5C, 4),
+            (0x7C, 4),
+            (0xDC, 4),
+            (0xFC, 4),
+        ];
+        for (opcode, expected) in cases {
+            let mut program = [0u8; 4096];
+            program[0] = opcode;
+            let mut system = System::new(program);
+            assert_eq!(step_clocks(&mut system), expected, "{opcode:02X}");
+            let inst = Instruction::try_from(opcode).unwrap();
+            assert_eq!(
+                system.chip.pc,
+                0x1001 + inst.address_mode().operand_bytes() as u16,
+                "{opcode:02X}"
+            );
+        }
+
+        // Crossing a page costs a cycle
+        let mut program = [0u8; 4096];
+        program[0..3].copy_from_slice(&[0x1C, 0xFF, 0x00]);
+        let mut system = System::new(program);
+        system.chip.x = 1;
+        assert_eq!(step_clocks(&mut system), 5);
+    }
+
+    #[test]
+    fn test_instruction_type_kil_execute() {
+        let mut system = System::new([0u8; 4096]);
+        system.program[0x10] = 0x02;
+        system.chip.pc = 0x1010;
+
+        let instruction: Instruction = system.next_byte().try_into().unwrap();
+        let err = instruction.execute(&mut system).unwrap_err();
+        assert!(matches!(err, StanleyError::CpuJammed(0x1010)));
+        assert_eq!(err.to_string(), "CPU jammed at $1010");
+    }
+
+    #[test]
+    fn test_display_mnemonics() {
+        let cases = [
+            (0x69, "ADC"),
+            (0x29, "AND"),
+            (0x0A, "ASL"),
+            (0x90, "BCC"),
+            (0xB0, "BCS"),
+            (0xF0, "BEQ"),
+            (0x24, "BIT"),
+            (0x30, "BMI"),
+            (0xD0, "BNE"),
+            (0x10, "BPL"),
+            (0x00, "BRK"),
+            (0x50, "BVC"),
+            (0x70, "BVS"),
+            (0x18, "CLC"),
+            (0xD8, "CLD"),
+            (0x58, "CLI"),
+            (0xB8, "CLV"),
+            (0xC9, "CMP"),
+            (0xE0, "CPX"),
+            (0xC0, "CPY"),
+            (0xC6, "DEC"),
+            (0xCA, "DEX"),
+            (0x88, "DEY"),
+            (0x49, "EOR"),
+            (0xE6, "INC"),
+            (0xE8, "INX"),
+            (0xC8, "INY"),
+            (0x4C, "JMP"),
+            (0x20, "JSR"),
+            (0xA9, "LDA"),
+            (0xA2, "LDX"),
+            (0xA0, "LDY"),
+            (0x4A, "LSR"),
+            (0xEA, "NOP"),
+            (0x09, "ORA"),
+            (0x48, "PHA"),
+            (0x08, "PHP"),
+            (0x68, "PLA"),
+            (0x28, "PLP"),
+            (0x2A, "ROL"),
+            (0x6A, "ROR"),
+            (0x40, "RTI"),
+            (0x60, "RTS"),
+            (0xE9, "SBC"),
+            (0x38, "SEC"),
+            (0xF8, "SED"),
+            (0x78, "SEI"),
+            (0x85, "STA"),
+            (0x86, "STX"),
+            (0x84, "STY"),
+            (0xAA, "TAX"),
+            (0xA8, "TAY"),
+            (0xBA, "TSX"),
+            (0x8A, "TXA"),
+            (0x9A, "TXS"),
+            (0x98, "TYA"),
+        ];
+        for (opcode, mnemonic) in cases {
+            let inst = Instruction::try_from(opcode).unwrap();
+            assert_eq!(inst.to_string(), mnemonic, "{opcode:02X}");
+        }
+    }
+
+    #[test]
+    fn test_decode_unknown_opcode() {
+        assert_eq!(Instruction::try_from(0xFF), Err(0xFF));
+        let err = Instruction::decode(0xFF, 0xF123).unwrap_err();
+        assert!(matches!(err, StanleyError::UnknownOpcode(0xFF, 0xF123)));
+        assert!(matches!(
+            Instruction::decode(0xEA, 0xF123),
+            Ok(Nop(Implied))
+        ));
+    }
+
+    #[test]
+    fn test_byte_length() {
+        let cases = [
+            (Absolute, 3),
+            (AbsoluteX, 3),
+            (AbsoluteY, 3),
+            (AbsoluteI, 3),
+            (Accumulator, 1),
+            (Immediate, 2),
+            (Implied, 1),
+            (Relative, 2),
+            (ZeroPage, 2),
+            (ZeroPageIX, 2),
+            (ZeroPageY, 2),
+            (ZeroPageX, 2),
+            (ZeroPageIY, 2),
+        ];
+        for (mode, expected) in cases {
+            assert_eq!(mode.byte_length(), expected, "{mode:?}");
+        }
+    }
 }
@@ -1,17 +1,36 @@
+use std::collections::VecDeque;
 use std::fmt;
 
+pub mod audio;
+pub mod cartridge;
 pub mod colors;
 pub mod instructions;
+pub mod ram;
 mod riot;
+pub mod state;
 pub mod tia;
+pub mod tv;
 
-use crate::renderer::WindowEvent;
+use crate::error::{Result, StanleyError};
+use crate::input::WindowEvent;
+use crate::profiler::Profiler;
+use cartridge::{BankSwitching, Cartridge};
 use instructions::Instruction;
+use ram::RamInit;
 use riot::Riot;
+use state::{StateReader, StateWriter};
 use tia::Tia;
+use tv::TvStandard;
 
 const MEMORY_SIZE: usize = 0x00FF - 0x0080 + 1;
 const PROGRAM_SIZE: usize = 0x1FFF - 0x1000 + 1;
+const STACK_PAGE: u16 = 0x0100;
+/// Where the cpu reads the address of its interrupt handlers
+const NMI_VECTOR: u16 = 0xFFFA;
+const IRQ_VECTOR: u16 = 0xFFFE;
+const INTERRUPT_CLOCKS: usize = 7;
+/// The number of program counters kept for crash reports
+pub const RECENT_PCS: usize = 32;
 
 pub struct System {
     pub chip: Nmos6507,
@@ -19,26 +38,112 @@ pub struct System {
     pub tia: Tia,
     pub memory: [u8; MEMORY_SIZE],
     pub program: [u8; PROGRAM_SIZE],
+    /// Switches banks of the cartridge ROM into `program`
+    pub cartridge: Cartridge,
     // TODO temporarily track clocks
     pub clocks: usize,
+    pub profiler: Option<Profiler>,
+    /// Addresses of the most recently executed instructions, oldest first
+    pub recent_pcs: VecDeque<u16>,
+    /// Stop with an error when the program counter runs somewhere code can't be, instead of
+    /// wrapping around and executing whatever is there. On by default in debug builds.
+    pub check_pc: bool,
+    /// The IRQ line, held by a peripheral that wants the cpu's attention. The interrupt is taken
+    /// between instructions whenever the line is held and the I flag is clear. The 2600's 6507
+    /// has no interrupt pins, so nothing in the console drives it.
+    pub irq_pending: bool,
+    /// Set by a peripheral to trigger a non-maskable interrupt, which is taken before the next
+    /// instruction whatever the I flag says
+    pub nmi_pending: bool,
+    // The last value on the data bus, which reads of addresses nothing drives pick up
+    last_bus_value: u8,
+    // A write to an address nothing is mapped to, reported at the end of the instruction
+    unmapped_access: Option<u16>,
+    // Clocks of the instruction being executed that the chips have already been ticked for
+    instruction_clocks: usize,
+    // The cycle of the instruction being executed that the last bus access happened on. `None`
+    // between instructions, when accesses don't move the chips along.
+    bus_cycle: Option<usize>,
+}
+
+/// The options a system is built with. The defaults are a 4K NTSC cartridge with RAM cleared.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SystemConfig {
+    pub tv_standard: TvStandard,
+    pub ram_init: RamInit,
+    pub bank_switching: BankSwitching,
+    /// See `System::check_pc`
+    pub check_pc: bool,
+    /// See `Tia::set_debug_colors`
+    pub debug_colors: bool,
+}
+
+impl Default for SystemConfig {
+    fn default() -> Self {
+        Self {
+            tv_standard: TvStandard::default(),
+            ram_init: RamInit::default(),
+            bank_switching: BankSwitching::default(),
+            check_pc: cfg!(debug_assertions),
+            debug_colors: false,
+        }
+    }
 }
 
 impl System {
     pub fn new(program: [u8; 4096]) -> Self {
+        Self::with_config(&program, SystemConfig::default()).expect("a 4K program always fits")
+    }
+
+    /// Creates a system running a ROM, set up the way the config says
+    pub fn with_config(rom: &[u8], config: SystemConfig) -> Result<Self> {
+        let cartridge = Cartridge::new(rom.to_vec(), config.bank_switching)?;
+        let mut system = Self::with_cartridge(cartridge);
+        system.tia.set_tv_standard(config.tv_standard);
+        system.init_ram(config.ram_init);
+        system.check_pc = config.check_pc;
+        system.tia.set_debug_colors(config.debug_colors);
+        Ok(system)
+    }
+
+    /// Creates a system with a bank switched cartridge, starting in its power on banks
+    pub fn with_cartridge(cartridge: Cartridge) -> Self {
         Self {
             chip: Nmos6507::new(),
             riot: Riot::new(),
             tia: Tia::default(),
             clocks: 0,
             memory: [0; MEMORY_SIZE],
-            program,
+            program: cartridge.program(),
+            cartridge,
+            profiler: None,
+            recent_pcs: VecDeque::with_capacity(RECENT_PCS),
+            check_pc: cfg!(debug_assertions),
+            irq_pending: false,
+            nmi_pending: false,
+            last_bus_value: 0,
+            unmapped_access: None,
+            instruction_clocks: 0,
+            bus_cycle: None,
         }
     }
 
+    /// Sets what RAM holds at power on
+    pub fn init_ram(&mut self, init: RamInit) {
+        init.fill(&mut self.memory);
+    }
+
     pub fn memory_set(&mut self, index: u16, value: u8) {
+        // Instructions catch up to their writes themselves, but later reads still count them
+        self.next_bus_cycle();
+        self.last_bus_value = value;
         if (index & 0x1000) != 0 {
+            if self.cartridge.is_hotspot(index) {
+                return self.cartridge.access(index, value, &mut self.program);
+            }
             panic!("assignment to program memory");
         }
+        self.cartridge.access(index, value, &mut self.program);
 
         // Memory
         if (!index & 0x1200) == 0x1200 && (index & 0x0080) != 0 {
@@ -56,10 +161,21 @@ impl System {
         if (!index & 0x1000) == 0x1000 && (index & 0x0294) != 0 {
             return self.riot.set(index & 0x001F, value);
         }
-        todo!("set not implemented for {:04X}", index);
+        self.unmapped_access = Some(index);
     }
 
     pub fn memory_get(&mut self, index: u16) -> u8 {
+        // A read sees the chips as they are on its cycle, like a write does
+        if let Some(cycle) = self.next_bus_cycle() {
+            self.catch_up(cycle);
+        }
+        let value = self.bus_get(index);
+        self.cartridge.access(index, value, &mut self.program);
+        self.last_bus_value = value;
+        value
+    }
+
+    fn bus_get(&mut self, index: u16) -> u8 {
         // Program memory
         if (index & 0x1000) != 0 {
             return self.program[(index & 0x0FFF) as usize];
@@ -72,38 +188,194 @@ impl System {
 
         // TIA Read
         if (!index & 0x1080) == 0x1080 {
-            return self.tia.get(index & 0x000F);
+            return self.tia.get(index & 0x000F).unwrap_or(self.last_bus_value);
         }
 
         if (!index & 0x1000) == 0x1000 && (index & 0x0480) != 0 {
             return self.riot.get(index);
         }
 
-        todo!("index not implemented for {:04X}", index);
+        // Nothing drove the bus, so the read picks up whatever was last on it. Usually that's the
+        // high byte of the address, the last byte of the instruction fetched.
+        self.last_bus_value
+    }
+
+    /// Reads a byte from the bus the same way the CPU does, including any side effects the read
+    /// has on the chips, like clearing the RIOT timer interrupt flag.
+    pub fn read(&mut self, addr: u16) -> u8 {
+        self.memory_get(addr)
+    }
+
+    /// Writes a byte to the bus the same way the CPU does. Writes to program memory are ignored,
+    /// since the cartridge is read only.
+    pub fn write(&mut self, addr: u16, value: u8) {
+        if (addr & 0x1000) != 0 && !self.cartridge.is_hotspot(addr) {
+            return;
+        }
+        self.memory_set(addr, value);
+    }
+
+    /// Reads a byte from the bus without disturbing any chip state. Meant for tooling, like the
+    /// debugger, that needs to look at memory without changing how the program runs.
+    pub fn peek(&self, addr: u16) -> u8 {
+        // Program memory
+        if (addr & 0x1000) != 0 {
+            return self.program[(addr & 0x0FFF) as usize];
+        }
+
+        // Memory
+        if (!addr & 0x1200) == 0x1200 && (addr & 0x0080) != 0 {
+            return self.memory[(addr & 0x007F) as usize];
+        }
+
+        // TIA Read
+        if (!addr & 0x1080) == 0x1080 {
+            return self.tia.get(addr & 0x000F).unwrap_or(self.last_bus_value);
+        }
+
+        if (!addr & 0x1000) == 0x1000 && (addr & 0x0480) != 0 {
+            return self.riot.peek(addr);
+        }
+
+        self.last_bus_value
     }
 
     pub fn next_byte(&mut self) -> u8 {
         let byte = self.memory_get(self.chip.pc);
-        self.chip.pc += 1;
+        self.chip.pc = self.chip.pc.wrapping_add(1);
         byte
     }
 
+    /// Fetches, decodes, and executes the next instruction, ticking the chips for the clocks it
+    /// took, including any time spent waiting on WSYNC. Returns the instruction that ran.
+    pub fn step(&mut self) -> Result<Instruction> {
+        let pc = self.chip.pc;
+        if self.recent_pcs.len() == RECENT_PCS {
+            self.recent_pcs.pop_front();
+        }
+        self.recent_pcs.push_back(pc);
+        if self.check_pc && !System::is_executable(pc) {
+            return Err(StanleyError::PcOutOfBounds(pc));
+        }
+
+        let start_clocks = self.clocks;
+        let opcode = self.next_byte();
+        let instruction = Instruction::decode(opcode, pc)?;
+        self.execute(instruction.clone())?;
+        if let Some(addr) = self.unmapped_access.take() {
+            return Err(StanleyError::UnmappedAddress(addr, pc));
+        }
+
+        if let Some(profiler) = self.profiler.as_mut() {
+            profiler.record(pc, opcode, self.clocks - start_clocks);
+        }
+        Ok(instruction)
+    }
+
+    /// Code runs from the cartridge, or from RAM for programs that copy routines there
+    fn is_executable(addr: u16) -> bool {
+        (addr & 0x1000) != 0 || ((!addr & 0x1200) == 0x1200 && (addr & 0x0080) != 0)
+    }
+
+    /// Steps until the beam starts a new frame, either by wrapping past the bottom of the frame or
+    /// by VSYNC restarting it. Returns the number of clocks that passed.
+    pub fn step_frame(&mut self) -> Result<usize> {
+        let start_clocks = self.clocks;
+        // RSYNC moves the beam back too, so only the TIA knows when a frame has finished
+        let frames = self.tia.frames();
+        while self.tia.frames() == frames {
+            self.step()?;
+        }
+        Ok(self.clocks - start_clocks)
+    }
+
+    /// Runs until the TIA finishes exactly one more frame and returns the picture, for shells that
+    /// draw it themselves, like a browser canvas. The pixels are in the order `Buffer` keeps them,
+    /// see `Buffer::as_rgba` for plain RGBA.
+    pub fn run_frame(&mut self) -> Result<&[u8]> {
+        self.step_frame()?;
+        self.tia.clear_frame_ready();
+        Ok(&self.tia.buffer.0)
+    }
+
+    /// Passes an input to the console, for shells that read the keyboard or gamepad themselves
+    pub fn set_input(&mut self, event: WindowEvent) {
+        self.input_event(&event);
+    }
+
+    /// Advances only the TIA, see `Tia::tick_color_clocks`
+    pub fn tick_color_clocks(&mut self, color_clocks: usize) {
+        self.tia.tick_color_clocks(color_clocks);
+    }
+
     pub fn tick(&mut self, clocks: usize) {
         self.clocks += clocks;
         self.riot.tick(clocks);
         self.tia.tick(clocks);
     }
 
-    pub fn execute(&mut self, inst: Instruction) -> super::Result<()> {
-        let ticks = inst.execute(self)?;
-        self.tick(ticks);
+    // Counts a bus access of the instruction being executed, returning the cycle it happens on.
+    // Dummy accesses aren't made, so this can fall behind the real cycle but never gets ahead.
+    fn next_bus_cycle(&mut self) -> Option<usize> {
+        let cycle = self.bus_cycle.as_mut()?;
+        *cycle += 1;
+        Some(*cycle)
+    }
+
+    /// Ticks the chips up to the end of a cycle of the instruction being executed. Instructions
+    /// call this before they write, and reads call it on the cycle they count to, so each access
+    /// lands on the cycle it happens on rather than at the start of the instruction. Without it a
+    /// mid-line color change would show up a few pixels early.
+    pub fn catch_up(&mut self, cycle: usize) {
+        let clocks = cycle.saturating_sub(self.instruction_clocks);
+        self.instruction_clocks += clocks;
+        self.tick(clocks);
+    }
+
+    pub fn execute(&mut self, inst: Instruction) -> Result<()> {
+        // The cpu polls for an IRQ before the last cycle of an instruction, so a CLI or PLP that
+        // clears I only lets one in after the next instruction. RTI restores I in time.
+        let irq_masked = self.chip.i;
+        let is_rti = matches!(inst, Instruction::Rti(_));
+        self.instruction_clocks = 0;
+        // The opcode was fetched on the first cycle
+        self.bus_cycle = Some(1);
+        let result = inst.execute(self);
+        self.bus_cycle = None;
+        let ticks = result?;
+        let irq_masked = if is_rti { self.chip.i } else { irq_masked };
+        let caught_up = std::mem::take(&mut self.instruction_clocks);
+        debug_assert!(caught_up <= ticks, "{inst:?} caught up past its last cycle");
+        self.tick(ticks.saturating_sub(caught_up));
         self.riot.timer_reset = false;
 
         let wsync_clocks = self.tia.sync().value;
         self.tick(wsync_clocks);
+
+        if std::mem::take(&mut self.nmi_pending) {
+            self.interrupt(NMI_VECTOR);
+        } else if self.irq_pending && !irq_masked {
+            self.interrupt(IRQ_VECTOR);
+        }
         Ok(())
     }
 
+    /// Takes an interrupt like BRK does, except the B flag is clear in the status pushed, and the
+    /// return address is the next instruction
+    fn interrupt(&mut self, vector: u16) {
+        let pc = self.chip.pc;
+        let status = self.status() & !0b0001_0000;
+        for value in [(pc >> 8) as u8, pc as u8, status] {
+            self.memory_set(self.chip.stack_address(), value);
+            self.chip.sp = self.chip.sp.wrapping_sub(1);
+        }
+        self.chip.i = true;
+        let low = self.memory_get(vector) as u16;
+        let high = self.memory_get(vector + 1) as u16;
+        self.chip.pc = (high << 8) + low;
+        self.tick(INTERRUPT_CLOCKS);
+    }
+
     pub fn status(&self) -> u8 {
         (self.chip.c as u8)
             | ((self.chip.z as u8) << 1)
@@ -125,6 +397,42 @@ impl System {
         self.chip.n = register & 128 != 0;
     }
 
+    /// Saves everything needed to carry on running from this point later. The ROM isn't saved,
+    /// so a state can only be loaded back into the same program.
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut state = StateWriter::new();
+        self.chip.save_state(&mut state);
+        state.bytes(&self.memory);
+        state.bytes(&self.program);
+        state.usize(self.clocks);
+        self.cartridge.save_state(&mut state);
+        self.riot.save_state(&mut state);
+        self.tia.save_state(&mut state);
+        state.finish()
+    }
+
+    /// Loads a state made by `save_state`. The system is left as it was if the state is invalid.
+    pub fn load_state(&mut self, data: &[u8]) -> Result<()> {
+        let backup = self.save_state();
+        let result = self.read_state(data);
+        if result.is_err() {
+            self.read_state(&backup)?;
+        }
+        result
+    }
+
+    fn read_state(&mut self, data: &[u8]) -> Result<()> {
+        let mut state = StateReader::new(data)?;
+        self.chip.load_state(&mut state)?;
+        state.bytes(&mut self.memory)?;
+        state.bytes(&mut self.program)?;
+        self.clocks = state.usize()?;
+        self.cartridge.load_state(&mut state)?;
+        self.riot.load_state(&mut state)?;
+        self.tia.load_state(&mut state)?;
+        state.finish()
+    }
+
     pub fn input_event(&mut self, event: &WindowEvent) {
         self.riot.input_event(event);
         self.tia.input_event(event);
@@ -189,6 +497,40 @@ impl Nmos6507 {
             ..Default::default()
         }
     }
+
+    fn save_state(&self, state: &mut StateWriter) {
+        for value in [self.x, self.y, self.a, self.sp] {
+            state.u8(value);
+        }
+        state.u16(self.pc);
+        for flag in [self.n, self.z, self.c, self.v, self.d, self.i, self.b] {
+            state.bool(flag);
+        }
+    }
+
+    fn load_state(&mut self, state: &mut StateReader) -> Result<()> {
+        for value in [&mut self.x, &mut self.y, &mut self.a, &mut self.sp] {
+            *value = state.u8()?;
+        }
+        self.pc = state.u16()?;
+        for flag in [
+            &mut self.n,
+            &mut self.z,
+            &mut self.c,
+            &mut self.v,
+            &mut self.d,
+            &mut self.i,
+            &mut self.b,
+        ] {
+            *flag = state.bool()?;
+        }
+        Ok(())
+    }
+
+    /// The address the stack pointer points at. The stack lives in page one.
+    pub fn stack_address(&self) -> u16 {
+        STACK_PAGE | self.sp as u16
+    }
 }
 
 impl fmt::Display for Nmos6507 {
@@ -208,6 +550,301 @@ Flags: Z({}) N({}) C({})\r\n
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input::InputType;
+
+    #[test]
+    fn step_frame() {
+        // JMP $1000
+        let mut program = [0; PROGRAM_SIZE];
+        program[..3].copy_from_slice(&[0x4C, 0x00, 0x10]);
+        let mut system = System::new(program);
+
+        let clocks = system.step_frame().unwrap();
+        // The frame wraps during the last JMP, and not before
+        assert!((tia::CLOCKS_PER_FRAME..tia::CLOCKS_PER_FRAME + 3).contains(&clocks));
+        assert!(system.tia.frame_position() < 3 * 3);
+
+        let clocks = system.step_frame().unwrap();
+        assert!((tia::CLOCKS_PER_FRAME - 3..tia::CLOCKS_PER_FRAME + 3).contains(&clocks));
+    }
+
+    #[test]
+    fn step_frame_after_rsync() {
+        // STA RSYNC; JMP $1002
+        let mut program = [0; PROGRAM_SIZE];
+        program[..5].copy_from_slice(&[0x85, 0x03, 0x4C, 0x02, 0x10]);
+        let mut system = System::new(program);
+
+        // Restarting the line moves the beam back, but doesn't finish the frame
+        let clocks = system.step_frame().unwrap();
+        assert_eq!(system.tia.frames(), 1);
+        assert!(clocks >= tia::CLOCKS_PER_FRAME);
+    }
+
+    #[test]
+    fn run_frame() {
+        // JMP $1000
+        let mut program = [0; PROGRAM_SIZE];
+        program[..3].copy_from_slice(&[0x4C, 0x00, 0x10]);
+        let mut system = System::new(program);
+
+        for frame in 1..=3 {
+            let picture = system.run_frame().unwrap();
+            assert_eq!(picture.len(), tia::BUFF_SIZE);
+            assert_eq!(system.tia.frames(), frame);
+            assert!(!system.tia.frame_ready());
+        }
+
+        system.set_input(WindowEvent::InputStart(InputType::Joystick1Left));
+        assert_eq!(system.riot.peek(0x0280), 0b1011_1111);
+
+        // STA RSYNC; JMP $1002
+        let mut program = [0; PROGRAM_SIZE];
+        program[..5].copy_from_slice(&[0x85, 0x03, 0x4C, 0x02, 0x10]);
+        let mut system = System::new(program);
+        system.run_frame().unwrap();
+        assert_eq!(system.tia.frames(), 1);
+        assert!(system.clocks >= tia::CLOCKS_PER_FRAME);
+    }
+
+    #[test]
+    fn reads_land_on_their_cycle() {
+        // NOP; LDA INTIM, reading the timer on its last cycle
+        let mut program = [0; PROGRAM_SIZE];
+        program[..4].copy_from_slice(&[0xEA, 0xAD, 0x84, 0x02]);
+        let mut system = System::new(program);
+        system.write(0x0294, 0x10);
+        system.step().unwrap();
+        let before = system.peek(0x0284);
+
+        system.step().unwrap();
+        assert_eq!(system.clocks, 2 + 4);
+        assert_ne!(system.chip.a, before);
+        assert_eq!(system.chip.a, system.peek(0x0284));
+    }
+
+    #[test]
+    fn unused_tia_reads_are_open_bus() {
+        let mut program = [0xEA; PROGRAM_SIZE];
+        // LDA $0F; LDA $020E
+        program[0..5].copy_from_slice(&[0xA5, 0x0F, 0xAD, 0x0E, 0x02]);
+        let mut system = System::new(program);
+        // The operand was the last thing on the bus
+        system.step().unwrap();
+        assert_eq!(system.chip.a, 0x0F);
+        system.step().unwrap();
+        assert_eq!(system.chip.a, 0x02);
+    }
+
+    #[test]
+    fn reads_below_the_cartridge_never_fail() {
+        let mut system = System::new([0xEA; PROGRAM_SIZE]);
+        system.write(0x80, 0x5A);
+        for addr in 0x0000..0x1000 {
+            // Set up the bus the way an absolute LDA leaves it
+            system.last_bus_value = (addr >> 8) as u8;
+            let peeked = system.peek(addr);
+            assert_eq!(system.read(addr), peeked, "${addr:04X}");
+            assert_eq!(system.unmapped_access, None);
+        }
+        // RAM and its mirrors
+        assert_eq!(system.read(0x0180), 0x5A);
+        // TIA addresses without a register read back the bus
+        system.last_bus_value = 0x03;
+        assert_eq!(system.read(0x030F), 0x03);
+        // The RIOT's data direction registers
+        assert_eq!(system.read(0x0281), 0);
+        assert_eq!(system.read(0x0283), 0);
+    }
+
+    #[test]
+    fn with_config() {
+        let mut rom = vec![0xEA; PROGRAM_SIZE * 2];
+        rom[PROGRAM_SIZE] = 0x42;
+        let config = SystemConfig {
+            tv_standard: TvStandard::Pal,
+            ram_init: RamInit::Ones,
+            bank_switching: BankSwitching::F8,
+            check_pc: true,
+            debug_colors: false,
+        };
+        let system = System::with_config(&rom, config).unwrap();
+        assert_eq!(system.tia.tv_standard(), TvStandard::Pal);
+        assert_eq!(system.memory, [0xFF; MEMORY_SIZE]);
+        // F8 carts power on in their last bank
+        assert_eq!(system.program[0], 0x42);
+        assert!(system.check_pc);
+
+        // The ROM has to fit the bank switching scheme
+        assert!(System::with_config(&rom, SystemConfig::default()).is_err());
+    }
+
+    #[test]
+    fn irq_is_taken_when_i_is_clear() {
+        let mut program = [0xEA; PROGRAM_SIZE];
+        // SEI; NOP; CLI; NOP; NOP, with the handler at $F100
+        program[0..3].copy_from_slice(&[0x78, 0xEA, 0x58]);
+        program[0xFFE..].copy_from_slice(&[0x00, 0xF1]);
+        let mut system = System::new(program);
+        system.chip.sp = 0xFF;
+        system.chip.i = true;
+        system.irq_pending = true;
+
+        system.step().unwrap();
+        system.step().unwrap();
+        assert_eq!(system.chip.pc, 0x1002);
+
+        // CLI clears I too late for the IRQ to be taken before the next instruction
+        system.step().unwrap();
+        assert_eq!(system.chip.pc, 0x1003);
+        assert!(!system.chip.i);
+
+        let clocks = system.clocks;
+        system.step().unwrap();
+        assert_eq!(system.chip.pc, 0xF100);
+        assert_eq!(system.clocks - clocks, 2 + 7);
+        assert!(system.chip.i);
+        assert_eq!(system.chip.sp, 0xFC);
+        // Returns to the instruction after the NOP, with B clear in the status pushed
+        assert_eq!(system.peek(0x01FF), 0x10);
+        assert_eq!(system.peek(0x01FE), 0x04);
+        assert_eq!(system.peek(0x01FD) & 0b0001_0100, 0);
+    }
+
+    #[test]
+    fn irq_waits_an_instruction_after_plp() {
+        let mut program = [0xEA; PROGRAM_SIZE];
+        // PLP, pulling a status with I clear
+        program[0] = 0x28;
+        program[0xFFE..].copy_from_slice(&[0x00, 0xF1]);
+        let mut system = System::new(program);
+        system.chip.sp = 0xFE;
+        system.chip.i = true;
+        system.irq_pending = true;
+
+        system.step().unwrap();
+        assert_eq!(system.chip.pc, 0x1001);
+        assert!(!system.chip.i);
+        system.step().unwrap();
+        assert_eq!(system.chip.pc, 0xF100);
+
+        // SEI is also too late to stop an IRQ, which pushes the status with I set
+        let mut program = [0xEA; PROGRAM_SIZE];
+        program[0] = 0x78;
+        program[0xFFE..].copy_from_slice(&[0x00, 0xF1]);
+        let mut system = System::new(program);
+        system.chip.sp = 0xFF;
+        system.irq_pending = true;
+        system.step().unwrap();
+        assert_eq!(system.chip.pc, 0xF100);
+        assert_eq!(system.peek(0x01FD) & 0b0000_0100, 0b0000_0100);
+    }
+
+    #[test]
+    fn nmi_ignores_the_i_flag() {
+        let mut program = [0xEA; PROGRAM_SIZE];
+        program[0] = 0x78;
+        program[0xFFA..0xFFC].copy_from_slice(&[0x00, 0xF2]);
+        let mut system = System::new(program);
+        system.chip.sp = 0xFF;
+        system.nmi_pending = true;
+
+        system.step().unwrap();
+        assert_eq!(system.chip.pc, 0xF200);
+        assert!(!system.nmi_pending);
+        system.step().unwrap();
+        assert_eq!(system.chip.pc, 0xF201);
+    }
+
+    #[test]
+    fn step_returns_the_instruction() {
+        let mut program = [0; PROGRAM_SIZE];
+        program[..2].copy_from_slice(&[0xA9, 0x42]);
+        let mut system = System::new(program);
+        assert_eq!(
+            system.step().unwrap(),
+            Instruction::Lda(instructions::AddressMode::Immediate)
+        );
+        assert_eq!(system.chip.a, 0x42);
+        assert_eq!(system.chip.pc, 0x1002);
+        assert_eq!(system.clocks, 2);
+    }
+
+    #[test]
+    fn mid_line_color_change_splits_the_line() {
+        let mut program = [0; PROGRAM_SIZE];
+        #[rustfmt::skip]
+        let code = [
+            0x85, 0x02,       // STA WSYNC
+            0xA9, 0x0E,       // LDA #$0E     cycle 2
+            0x85, 0x09,       // STA COLUBK   cycle 5
+            0xEA, 0xEA, 0xEA, 0xEA, 0xEA, 0xEA, 0xEA, 0xEA, 0xEA, 0xEA, // cycle 25
+            0xA9, 0x44,       // LDA #$44     cycle 27
+            0x85, 0x09,       // STA COLUBK   cycle 30
+            0x4C, 0x00, 0x10, // JMP $1000
+        ];
+        program[..code.len()].copy_from_slice(&code);
+        let mut system = System::new(program);
+        system.step_frame().unwrap();
+        system.step_frame().unwrap();
+
+        // The second write finishes on cycle 30, color clock 90, which is pixel 22
+        let pixel = |column: usize| {
+            let start = (100 * tia::WIDTH as usize + column) * tia::STRIDE;
+            system.tia.buffer.0[start..start + tia::STRIDE].to_vec()
+        };
+        let background = tv::TvStandard::Ntsc.color(0x0E).as_slice();
+        let changed = tv::TvStandard::Ntsc.color(0x44).as_slice();
+        assert_eq!(pixel(0), background);
+        assert_eq!(pixel(21), background);
+        assert_eq!(pixel(22), changed);
+        assert_eq!(pixel(159), changed);
+    }
+
+    #[test]
+    fn running_past_the_end_of_the_cartridge() {
+        // NOPs all the way to the end
+        let mut system = System::new([0xEA; PROGRAM_SIZE]);
+        system.check_pc = true;
+        system.chip.pc = 0x1FFF;
+        system.step().unwrap();
+        assert!(matches!(
+            system.step(),
+            Err(StanleyError::PcOutOfBounds(0x2000))
+        ));
+
+        // Code copied to RAM is fine
+        system.memory[0] = 0xEA;
+        system.chip.pc = 0x0080;
+        system.step().unwrap();
+    }
+
+    #[test]
+    fn bank_switching_through_the_bus() {
+        let rom = (0..8192).map(|i| (i / 1024) as u8).collect();
+        let cartridge = Cartridge::new(rom, cartridge::BankSwitching::E0).unwrap();
+        let mut system = System::with_cartridge(cartridge);
+        assert_eq!(system.read(0xF000), 4);
+
+        system.read(0xFFE1);
+        assert_eq!(system.read(0xF000), 1);
+        system.write(0xFFE3, 0);
+        assert_eq!(system.read(0xF000), 3);
+        assert_eq!(system.peek(0xFC00), 7);
+    }
+
+    #[test]
+    fn f8_bank_switching_through_the_bus() {
+        let rom = (0..8192).map(|i| (i / 4096) as u8).collect();
+        let cartridge = Cartridge::new(rom, cartridge::BankSwitching::F8).unwrap();
+        let mut system = System::with_cartridge(cartridge);
+        assert_eq!(system.read(0xF000), 1);
+
+        system.write(0xFFF8, 0);
+        assert_eq!(system.read(0xF000), 0);
+        system.write(0x1FF9, 0);
+        assert_eq!(system.read(0xF000), 1);
+    }
 
     #[test]
     fn program_read() {
@@ -273,4 +910,76 @@ mod tests {
         system.memory_set(0x0DFF, 90);
         assert_eq!(system.memory_get(0x0DFF), 90);
     }
+
+    #[test]
+    fn save_state_round_trip() {
+        let mut program = [0; PROGRAM_SIZE];
+        // loop: INC $80; STA WSYNC; JMP loop
+        program[0..7].copy_from_slice(&[0xE6, 0x80, 0x85, 0x02, 0x4C, 0x00, 0xF0]);
+        let mut system = System::new(program);
+        for _ in 0..100 {
+            system.step().unwrap();
+        }
+        let state = system.save_state();
+        let saved = format!("{system}{}{}", system.riot, system.tia);
+
+        for _ in 0..100 {
+            system.step().unwrap();
+        }
+        assert_ne!(system.memory_get(0x80), 34);
+        system.load_state(&state).unwrap();
+        assert_eq!(format!("{system}{}{}", system.riot, system.tia), saved);
+        assert_eq!(system.memory_get(0x80), 34);
+        assert_eq!(system.save_state(), state);
+
+        // A broken state doesn't touch the system
+        assert!(system.load_state(&state[..state.len() - 1]).is_err());
+        assert_eq!(system.save_state(), state);
+    }
+
+    #[test]
+    fn step_errors() {
+        let mut program = [0; PROGRAM_SIZE];
+        // NOP; <unknown>
+        program[0..2].copy_from_slice(&[0xEA, 0xFF]);
+        let mut system = System::new(program);
+        assert!(system.step().is_ok());
+        assert!(matches!(
+            system.step(),
+            Err(StanleyError::UnknownOpcode(0xFF, 0x1001))
+        ));
+    }
+
+    #[test]
+    fn read_write_roundtrip() {
+        let mut system = System::new([0; PROGRAM_SIZE]);
+        system.write(0x0080, 1);
+        assert_eq!(system.read(0x0080), 1);
+        // Mirrors decode to the same byte
+        assert_eq!(system.read(0x0180), 1);
+        assert_eq!(system.peek(0x0580), 1);
+
+        system.write(0x0DFF, 90);
+        assert_eq!(system.read(0x00FF), 90);
+    }
+
+    #[test]
+    fn write_to_program_is_ignored() {
+        let mut system = System::new([1; PROGRAM_SIZE]);
+        system.write(0xF000, 0);
+        assert_eq!(system.read(0xF000), 1);
+        assert_eq!(system.peek(0x1000), 1);
+    }
+
+    #[test]
+    fn peek_matches_read() {
+        let mut system = System::new([0; PROGRAM_SIZE]);
+        system.write(0x0295, 10);
+        system.riot.timer_reset = false;
+        system.tick(16);
+
+        assert_eq!(system.peek(0x0284), 8);
+        assert_eq!(system.read(0x0284), 8);
+        assert_eq!(system.peek(0x0280), system.read(0x0280));
+    }
 }
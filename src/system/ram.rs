@@ -0,0 +1,88 @@
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// What RAM holds when the console powers on. Real hardware comes up with whatever the chips
+/// settle into, and some games seed their random numbers from it, so a seed makes those runs
+/// repeatable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RamInit {
+    #[default]
+    Zero,
+    Ones,
+    /// Different every run
+    Random,
+    Seeded(u64),
+}
+
+impl RamInit {
+    pub fn fill(&self, memory: &mut [u8]) {
+        let seed = match *self {
+            RamInit::Zero => return memory.fill(0),
+            RamInit::Ones => return memory.fill(0xFF),
+            RamInit::Random => SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |time| time.as_nanos() as u64),
+            RamInit::Seeded(seed) => seed,
+        };
+        // xorshift64, which gets stuck at 0, so that seed is moved off it
+        let mut state = seed.max(1);
+        for byte in memory {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            *byte = (state >> 32) as u8;
+        }
+    }
+}
+
+impl FromStr for RamInit {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let s = s.to_lowercase();
+        Ok(match s.as_str() {
+            "zero" => RamInit::Zero,
+            "ones" => RamInit::Ones,
+            "random" => RamInit::Random,
+            _ => match s.strip_prefix("seeded:").map(str::parse) {
+                Some(Ok(seed)) => RamInit::Seeded(seed),
+                _ => return Err(format!("Unknown RAM init mode: {s}")),
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seeded_is_deterministic() {
+        let (mut first, mut second, mut other) = ([0; 128], [0; 128], [0; 128]);
+        RamInit::Seeded(42).fill(&mut first);
+        RamInit::Seeded(42).fill(&mut second);
+        RamInit::Seeded(43).fill(&mut other);
+        assert_eq!(first, second);
+        assert_ne!(first, other);
+        assert!(first.iter().any(|&byte| byte != first[0]));
+    }
+
+    #[test]
+    fn test_parse_ram_init() {
+        assert_eq!("zero".parse(), Ok(RamInit::Zero));
+        assert_eq!("Ones".parse(), Ok(RamInit::Ones));
+        assert_eq!("random".parse(), Ok(RamInit::Random));
+        assert_eq!("seeded:1234".parse(), Ok(RamInit::Seeded(1234)));
+        assert!("seeded:abc".parse::<RamInit>().is_err());
+        assert!("noise".parse::<RamInit>().is_err());
+    }
+
+    #[test]
+    fn test_fill() {
+        let mut memory = [0x55; 128];
+        RamInit::Ones.fill(&mut memory);
+        assert_eq!(memory, [0xFF; 128]);
+        RamInit::Zero.fill(&mut memory);
+        assert_eq!(memory, [0; 128]);
+    }
+}
@@ -1,13 +1,37 @@
-use crate::renderer::{InputType, WindowEvent};
+use super::state::{StateReader, StateWriter};
+use crate::error::Result;
+use crate::input::{InputType, WindowEvent};
 use std::fmt;
 
+// Console switches, a cleared bit is a pressed button
+const SWCHB_RESET: u8 = 0b0000_0001;
+const SWCHB_SELECT: u8 = 0b0000_0010;
+// Set for color, cleared for black and white
+const SWCHB_COLOR: u8 = 0b0000_1000;
+/// Color, both difficulty switches on B, and nothing pressed
+const SWCHB_DEFAULT: u8 = SWCHB_COLOR | SWCHB_SELECT | SWCHB_RESET;
+/// The Gray code a driving controller puts on the up and down pins of its port as it turns
+/// clockwise. Only one bit changes each step, so the game can tell which way it turned.
+const DRIVING_GRAY_CODE: [u8; 4] = [0b11, 0b01, 0b00, 0b10];
+
 #[derive(Default, Debug)]
 pub struct Riot {
     timer: u8,
     clocks: usize,
     clocks_per_interval: usize,
+    // Set when the timer underflows, and cleared by reading INTIM or writing a new timer
     timint: bool,
     swcha: u8,
+    swchb: u8,
+    // The data direction registers, SWACNT and SWBCNT. A set bit makes the pin an output, which
+    // reads back what the program last wrote to the port instead of the controller.
+    swacnt: u8,
+    swbcnt: u8,
+    // What the program last wrote to SWCHA and SWCHB
+    swa_output: u8,
+    swb_output: u8,
+    // The step of player 1's driving controller in the Gray code
+    driving1: usize,
     pub timer_reset: bool,
 }
 
@@ -15,12 +39,24 @@ impl Riot {
     pub fn new() -> Self {
         Self {
             swcha: 0xFF,
+            swchb: SWCHB_DEFAULT,
             ..Default::default()
         }
     }
 
-    // TODO: There are other things to set other than the timer. This will fail eventually
+    // TODO: There are other things to set other than the ports and timer. This will fail
+    // eventually
     pub fn set(&mut self, index: u16, value: u8) {
+        match index {
+            0x00 => self.swa_output = value,
+            0x01 => self.swacnt = value,
+            0x02 => self.swb_output = value,
+            0x03 => self.swbcnt = value,
+            _ => self.set_timer(index, value),
+        }
+    }
+
+    fn set_timer(&mut self, index: u16, value: u8) {
         self.timint = false;
         self.timer_reset = true;
         self.timer = value;
@@ -36,15 +72,35 @@ impl Riot {
     }
 
     pub fn get(&mut self, index: u16) -> u8 {
-        if index & 0x0284 == 0x0284 {
+        // Reading INTIM clears the interrupt flag, reading TIMINT leaves it alone
+        if index & 0x0005 == 0x0004 {
             self.timint = false;
-            return self.timer;
         }
+        self.peek(index)
+    }
 
-        if index & 0x280 == 0x280 {
-            return self.swcha;
+    /// Reads a register without the side effects of a real read, like clearing TIMINT.
+    pub fn peek(&self, index: u16) -> u8 {
+        // A2 picks the timer over the ports, and A0 picks the interrupt flag over the timer
+        if index & 0x0004 != 0 {
+            return if index & 0x0001 == 0 {
+                self.timer
+            } else {
+                (self.timint as u8) << 7
+            };
         }
-        todo!("RIOT read not implemented for {:X}", index);
+
+        match index & 0x0003 {
+            0x00 => Self::port(self.swcha, self.swa_output, self.swacnt),
+            0x01 => self.swacnt,
+            0x02 => Self::port(self.swchb, self.swb_output, self.swbcnt),
+            _ => self.swbcnt,
+        }
+    }
+
+    // Input pins read the controller, output pins read back what was written
+    fn port(input: u8, output: u8, direction: u8) -> u8 {
+        (input & !direction) | (output & direction)
     }
 
     pub fn tick(&mut self, clocks: usize) {
@@ -69,6 +125,32 @@ impl Riot {
         }
     }
 
+    /// Saves the timer and what the program set the ports to. The switches follow the keyboard,
+    /// so they aren't saved.
+    pub fn save_state(&self, state: &mut StateWriter) {
+        state.u8(self.timer);
+        state.usize(self.clocks);
+        state.usize(self.clocks_per_interval);
+        state.bool(self.timint);
+        state.bool(self.timer_reset);
+        for value in [self.swacnt, self.swbcnt, self.swa_output, self.swb_output] {
+            state.u8(value);
+        }
+    }
+
+    pub fn load_state(&mut self, state: &mut StateReader) -> Result<()> {
+        self.timer = state.u8()?;
+        self.clocks = state.usize()?;
+        self.clocks_per_interval = state.usize()?;
+        self.timint = state.bool()?;
+        self.timer_reset = state.bool()?;
+        self.swacnt = state.u8()?;
+        self.swbcnt = state.u8()?;
+        self.swa_output = state.u8()?;
+        self.swb_output = state.u8()?;
+        Ok(())
+    }
+
     pub fn input_event(&mut self, event: &WindowEvent) {
         match event {
             WindowEvent::InputStart(InputType::Joystick1Up) => self.swcha &= 0b1110_1111,
@@ -79,9 +161,30 @@ impl Riot {
             WindowEvent::InputEnd(InputType::Joystick1Left) => self.swcha |= 0b0100_0000,
             WindowEvent::InputStart(InputType::Joystick1Right) => self.swcha &= 0b0111_1111,
             WindowEvent::InputEnd(InputType::Joystick1Right) => self.swcha |= 0b1000_0000,
+            WindowEvent::InputStart(InputType::Joystick2Up) => self.swcha &= 0b1111_1110,
+            WindowEvent::InputEnd(InputType::Joystick2Up) => self.swcha |= 0b0000_0001,
+            WindowEvent::InputStart(InputType::Joystick2Down) => self.swcha &= 0b1111_1101,
+            WindowEvent::InputEnd(InputType::Joystick2Down) => self.swcha |= 0b0000_0010,
+            WindowEvent::InputStart(InputType::Joystick2Left) => self.swcha &= 0b1111_1011,
+            WindowEvent::InputEnd(InputType::Joystick2Left) => self.swcha |= 0b0000_0100,
+            WindowEvent::InputStart(InputType::Joystick2Right) => self.swcha &= 0b1111_0111,
+            WindowEvent::InputEnd(InputType::Joystick2Right) => self.swcha |= 0b0000_1000,
+            WindowEvent::InputStart(InputType::Driving1Clockwise) => self.turn_driving1(1),
+            WindowEvent::InputStart(InputType::Driving1CounterClockwise) => self.turn_driving1(3),
+            WindowEvent::InputStart(InputType::Select) => self.swchb &= !SWCHB_SELECT,
+            WindowEvent::InputEnd(InputType::Select) => self.swchb |= SWCHB_SELECT,
+            WindowEvent::InputStart(InputType::Reset) => self.swchb &= !SWCHB_RESET,
+            WindowEvent::InputEnd(InputType::Reset) => self.swchb |= SWCHB_RESET,
             _ => (),
         }
     }
+
+    /// Turns the driving controller a number of steps clockwise. The wheel stays where it was
+    /// left, so there's nothing to do when the input ends.
+    fn turn_driving1(&mut self, steps: usize) {
+        self.driving1 = (self.driving1 + steps) % DRIVING_GRAY_CODE.len();
+        self.swcha = (self.swcha & 0b1100_1111) | DRIVING_GRAY_CODE[self.driving1] << 4;
+    }
 }
 
 impl fmt::Display for Riot {
@@ -90,9 +193,9 @@ impl fmt::Display for Riot {
             f,
             "
 RIOT\r\n
-Timer: {:03}  | Timer Width  {:04} | TIMINT: {} | SWCHA {:08b}\r\n\r\n
+Timer: {:03}  | Timer Width  {:04} | TIMINT: {} | SWCHA {:08b} | SWCHB {:08b}\r\n\r\n
             ",
-            self.timer, self.clocks_per_interval, self.timint, self.swcha
+            self.timer, self.clocks_per_interval, self.timint, self.swcha, self.swchb
         )
     }
 }
@@ -183,4 +286,128 @@ mod tests {
         riot.tick(1);
         assert_eq!(riot.get(0x0284), 98);
     }
+
+    #[test]
+    fn test_peek_does_not_clear_timint() {
+        let mut riot = Riot::new();
+        riot.set(0x14, 0);
+        riot.timer_reset = false;
+        riot.tick(2);
+        assert!(riot.timint);
+
+        assert_eq!(riot.peek(0x0284), 0xFE);
+        assert!(riot.timint);
+
+        assert_eq!(riot.get(0x0284), 0xFE);
+        assert!(!riot.timint);
+    }
+
+    #[test]
+    fn test_timer_and_interrupt_flag_addresses() {
+        let mut riot = Riot::new();
+        riot.set(0x14, 0);
+        riot.timer_reset = false;
+        riot.tick(2);
+
+        // Reading the flag doesn't clear it
+        assert_eq!(riot.get(0x0285), 0b1000_0000);
+        assert_eq!(riot.get(0x0285), 0b1000_0000);
+        assert_eq!(riot.get(0x0284), 0xFE);
+        assert_eq!(riot.get(0x0285), 0);
+        // The registers are mirrored at A1
+        assert_eq!(riot.get(0x0286), 0xFE);
+        assert_eq!(riot.get(0x0287), 0);
+    }
+
+    #[test]
+    fn test_data_direction_registers() {
+        let mut riot = Riot::new();
+        assert_eq!(riot.get(0x0281), 0);
+        assert_eq!(riot.get(0x0283), 0);
+
+        // Output pins read back the value written, input pins still read the controller
+        riot.set(0x01, 0xF0);
+        riot.set(0x00, 0x5A);
+        riot.input_event(&WindowEvent::InputStart(InputType::Joystick2Up));
+        assert_eq!(riot.get(0x0281), 0xF0);
+        assert_eq!(riot.get(0x0280), 0x5E);
+
+        riot.set(0x03, 0x01);
+        riot.set(0x02, 0x00);
+        assert_eq!(riot.get(0x0283), 0x01);
+        assert_eq!(riot.get(0x0282), SWCHB_DEFAULT & !SWCHB_RESET);
+        // The timer isn't touched
+        assert_eq!(riot.clocks_per_interval, 0);
+    }
+
+    #[test]
+    fn test_timint_stays_set_until_intim_is_read() {
+        let mut riot = Riot::new();
+        riot.set(0x15, 1);
+        riot.timer_reset = false;
+        riot.tick(8);
+        assert_eq!(riot.get(0x0285), 0);
+
+        riot.tick(8);
+        assert_eq!(riot.get(0x0285), 0b1000_0000);
+        // The timer keeps counting down a clock at a time, the flag stays set
+        riot.tick(100);
+        assert_eq!(riot.get(0x0285), 0b1000_0000);
+
+        riot.get(0x0284);
+        assert_eq!(riot.get(0x0285), 0);
+    }
+
+    #[test]
+    fn test_joystick_directions() {
+        let mut riot = Riot::new();
+        riot.input_event(&WindowEvent::InputStart(InputType::Joystick1Up));
+        assert_eq!(riot.get(0x0280), 0b1110_1111);
+        riot.input_event(&WindowEvent::InputStart(InputType::Joystick1Left));
+        assert_eq!(riot.get(0x0280), 0b1010_1111);
+
+        // Player 2 is on the low bits
+        riot.input_event(&WindowEvent::InputStart(InputType::Joystick2Right));
+        assert_eq!(riot.get(0x0280), 0b1010_0111);
+
+        riot.input_event(&WindowEvent::InputEnd(InputType::Joystick1Up));
+        riot.input_event(&WindowEvent::InputEnd(InputType::Joystick1Left));
+        riot.input_event(&WindowEvent::InputEnd(InputType::Joystick2Right));
+        assert_eq!(riot.get(0x0280), 0xFF);
+    }
+
+    #[test]
+    fn test_driving_controller() {
+        let mut riot = Riot::new();
+        let mut turn = |input| {
+            riot.input_event(&WindowEvent::InputStart(input));
+            riot.input_event(&WindowEvent::InputEnd(input));
+            riot.get(0x0280) >> 4 & 0b11
+        };
+        let clockwise: Vec<u8> = (0..5).map(|_| turn(InputType::Driving1Clockwise)).collect();
+        assert_eq!(clockwise, [0b01, 0b00, 0b10, 0b11, 0b01]);
+        let back: Vec<u8> = (0..5)
+            .map(|_| turn(InputType::Driving1CounterClockwise))
+            .collect();
+        assert_eq!(back, [0b11, 0b10, 0b00, 0b01, 0b11]);
+        // Player 2's port and the other pins are left alone
+        assert_eq!(riot.get(0x0280), 0xFF);
+    }
+
+    #[test]
+    fn test_console_switches() {
+        let mut riot = Riot::new();
+        assert_eq!(riot.get(0x0282), 0b0000_1011);
+
+        riot.input_event(&WindowEvent::InputStart(InputType::Select));
+        assert_eq!(riot.get(0x0282), 0b0000_1001);
+        riot.input_event(&WindowEvent::InputStart(InputType::Reset));
+        assert_eq!(riot.get(0x0282), 0b0000_1000);
+
+        riot.input_event(&WindowEvent::InputEnd(InputType::Select));
+        riot.input_event(&WindowEvent::InputEnd(InputType::Reset));
+        assert_eq!(riot.get(0x0282), 0b0000_1011);
+        // The joystick port is separate
+        assert_eq!(riot.get(0x0280), 0xFF);
+    }
 }
@@ -0,0 +1,146 @@
+use crate::error::{Result, StanleyError};
+
+/// Every save state starts with these bytes, followed by the format version
+const MAGIC: &[u8; 4] = b"STNL";
+/// Bumped whenever the layout changes, so old states are rejected instead of loaded wrong
+const VERSION: u8 = 4;
+
+/// Writes the parts of the system into a save state, in the order they're read back
+pub struct StateWriter(Vec<u8>);
+
+impl StateWriter {
+    pub fn new() -> Self {
+        let mut writer = Self(MAGIC.to_vec());
+        writer.u8(VERSION);
+        writer
+    }
+
+    pub fn finish(self) -> Vec<u8> {
+        self.0
+    }
+
+    pub fn u8(&mut self, value: u8) {
+        self.0.push(value);
+    }
+
+    pub fn bool(&mut self, value: bool) {
+        self.u8(value as u8);
+    }
+
+    pub fn u16(&mut self, value: u16) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    pub fn usize(&mut self, value: usize) {
+        self.bytes(&(value as u64).to_le_bytes());
+    }
+
+    pub fn bytes(&mut self, bytes: &[u8]) {
+        self.0.extend_from_slice(bytes);
+    }
+}
+
+impl Default for StateWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Reads back the parts of a save state written by `StateWriter`
+pub struct StateReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> StateReader<'a> {
+    pub fn new(data: &'a [u8]) -> Result<Self> {
+        let mut reader = Self { data };
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(StanleyError::SaveState("not a save state".to_owned()));
+        }
+        let version = reader.u8()?;
+        if version != VERSION {
+            return Err(StanleyError::SaveState(format!(
+                "version {version} isn't supported, expected {VERSION}"
+            )));
+        }
+        Ok(reader)
+    }
+
+    /// Checks the whole state was read
+    pub fn finish(self) -> Result<()> {
+        if self.data.is_empty() {
+            Ok(())
+        } else {
+            Err(StanleyError::SaveState(format!(
+                "{} bytes left over",
+                self.data.len()
+            )))
+        }
+    }
+
+    pub fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub fn bool(&mut self) -> Result<bool> {
+        Ok(self.u8()? != 0)
+    }
+
+    pub fn u16(&mut self) -> Result<u16> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    pub fn usize(&mut self) -> Result<usize> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()) as usize)
+    }
+
+    pub fn bytes(&mut self, bytes: &mut [u8]) -> Result<()> {
+        bytes.copy_from_slice(self.take(bytes.len())?);
+        Ok(())
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.data.len() < len {
+            return Err(StanleyError::SaveState("ended early".to_owned()));
+        }
+        let (taken, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(taken)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let mut writer = StateWriter::new();
+        writer.u8(0x12);
+        writer.bool(true);
+        writer.u16(0xF123);
+        writer.usize(123_456_789);
+        writer.bytes(&[1, 2, 3]);
+        let data = writer.finish();
+
+        let mut reader = StateReader::new(&data).unwrap();
+        assert_eq!(reader.u8().unwrap(), 0x12);
+        assert!(reader.bool().unwrap());
+        assert_eq!(reader.u16().unwrap(), 0xF123);
+        assert_eq!(reader.usize().unwrap(), 123_456_789);
+        let mut bytes = [0; 3];
+        reader.bytes(&mut bytes).unwrap();
+        assert_eq!(bytes, [1, 2, 3]);
+        assert!(reader.finish().is_ok());
+    }
+
+    #[test]
+    fn test_rejects_bad_states() {
+        assert!(StateReader::new(b"nope").is_err());
+        assert!(StateReader::new(&[b'S', b'T', b'N', b'L', VERSION + 1]).is_err());
+
+        let data = StateWriter::new().finish();
+        let mut reader = StateReader::new(&data).unwrap();
+        assert!(reader.u16().is_err());
+    }
+}
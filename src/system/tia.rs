@@ -1,42 +1,136 @@
 use std::fmt;
 
-use super::colors::COLOR_MAP;
-use crate::renderer::{InputType, WindowEvent};
+use super::audio::{self, AudioChannel, COLOR_CLOCKS_PER_SAMPLE, SAMPLE_RATE};
+use super::state::{StateReader, StateWriter};
+use super::tv::TvStandard;
+use crate::error::Result;
+use crate::input::{InputType, WindowEvent};
 
 const COLOR_CLOCKS_PER_LINE: usize = 228;
+// The length of an NTSC frame. PAL and SECAM frames are longer, see `TvStandard::scan_lines`.
 const COLOR_CLOCKS_PER_FRAME: usize = COLOR_CLOCKS_PER_LINE * SCAN_LINES;
-const SCAN_LINES: usize = 262;
+const SCAN_LINES: usize = TvStandard::Ntsc.scan_lines();
 const COLOR_CLOCKS_PER_SYSTEM_CLOCK: usize = 3;
+pub const CLOCKS_PER_LINE: usize = COLOR_CLOCKS_PER_LINE / COLOR_CLOCKS_PER_SYSTEM_CLOCK;
+pub const CLOCKS_PER_FRAME: usize = COLOR_CLOCKS_PER_FRAME / COLOR_CLOCKS_PER_SYSTEM_CLOCK;
+/// How long each clock takes on the real console, in nanoseconds
+pub const CLOCK_NANOS: usize = 837;
 
 pub const WIDTH: u32 = 160;
 pub const HEIGHT: u32 = 192;
 pub const STRIDE: usize = 4;
 pub const BUFF_SIZE: usize = (WIDTH * HEIGHT * STRIDE as u32) as usize;
-const DRAWING_START_ROW: usize = 40;
 const DRAWING_START_COLUMN: usize = 68;
 const DRAWING_ROWS: usize = 192;
 const DRAWING_COLUMNS: usize = 160;
+const HMOVE_BLANK_COLUMNS: usize = 8;
+// Each of the playfield's 40 bits is 4 color clocks wide
+const PLAYFIELD_BITS: usize = 40;
+const PLAYFIELD_BIT_WIDTH: usize = DRAWING_COLUMNS / PLAYFIELD_BITS;
+// The lines a paddle's capacitor takes to charge at its highest resistance
+const PADDLE_CHARGE_LINES: usize = 190;
+// A second of audio
+const MAX_AUDIO_SAMPLES: usize = SAMPLE_RATE as usize;
+// The colors each object is drawn in with debug colors on
+const DEBUG_COLOR_P0: u8 = 0x46;
+const DEBUG_COLOR_M0: u8 = 0x3A;
+const DEBUG_COLOR_P1: u8 = 0xC6;
+const DEBUG_COLOR_M1: u8 = 0xDA;
+const DEBUG_COLOR_PF: u8 = 0x86;
+const DEBUG_COLOR_BL: u8 = 0x68;
 
 // TIA Register Constants
+const CXM0P: usize = 0x0;
+const CXM1P: usize = 0x1;
+const CXP0FB: usize = 0x2;
+const CXP1FB: usize = 0x3;
+const CXM0FB: usize = 0x4;
+const CXM1FB: usize = 0x5;
+const CXBLPF: usize = 0x6;
+const CXPPMM: usize = 0x7;
+const INPT0: u16 = 0x8;
+const INPT1: u16 = 0x9;
+const INPT3: u16 = 0xB;
 const INPT4: u16 = 0xC;
+const INPT5: u16 = 0xD;
 
 pub struct WsyncClocks {
     pub value: usize,
 }
 
 pub struct Buffer(pub [u8; BUFF_SIZE]);
+
+impl Buffer {
+    /// The picture as red, green, and blue bytes for each pixel. The buffer itself is in the
+    /// order SDL's RGBA8888 textures take, which is blue first in memory.
+    pub fn as_rgb(&self) -> Vec<u8> {
+        self.0
+            .chunks(STRIDE)
+            .flat_map(|pixel| [pixel[2], pixel[1], pixel[0]])
+            .collect()
+    }
+
+    /// The picture as red, green, blue, and alpha bytes for each pixel
+    pub fn as_rgba(&self) -> Vec<u8> {
+        self.0
+            .chunks(STRIDE)
+            .flat_map(|pixel| [pixel[2], pixel[1], pixel[0], pixel[3]])
+            .collect()
+    }
+}
+
 impl fmt::Debug for Buffer {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "[{}, {}, ...]", self.0[0], self.0[1])
     }
 }
 
-#[derive(Debug)]
+/// Which objects are drawing at a pixel
+struct Objects {
+    pf: bool,
+    bl: bool,
+    p0: bool,
+    p1: bool,
+    m0: bool,
+    m1: bool,
+}
+
+/// The number, spacing, and size of the copies of a player, from the low bits of NUSIZ
+#[derive(Debug, Clone, Copy)]
 enum Nusize {
     OneCopy,
+    TwoCopiesClose,
+    TwoCopiesMedium,
+    ThreeCopiesClose,
+    TwoCopiesWide,
+    Double,
+    ThreeCopiesMedium,
     Quad,
 }
 
+impl Nusize {
+    /// The color clocks from the player's position to the start of each copy
+    fn copies(&self) -> &'static [usize] {
+        match self {
+            Self::OneCopy | Self::Double | Self::Quad => &[0],
+            Self::TwoCopiesClose => &[0, 16],
+            Self::TwoCopiesMedium => &[0, 32],
+            Self::ThreeCopiesClose => &[0, 16, 32],
+            Self::TwoCopiesWide => &[0, 64],
+            Self::ThreeCopiesMedium => &[0, 32, 64],
+        }
+    }
+
+    /// The color clocks each bit of the graphics is drawn for
+    fn stretch(&self) -> usize {
+        match self {
+            Self::Double => 2,
+            Self::Quad => 4,
+            _ => 1,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct Tia {
     vsync: bool,
@@ -44,6 +138,10 @@ pub struct Tia {
     pub wsync: bool,
 
     set_resp0: bool,
+    set_resp1: bool,
+    set_resm0: bool,
+    set_resm1: bool,
+    set_resbl: bool,
 
     // colors
     colupf: u8,
@@ -53,22 +151,85 @@ pub struct Tia {
 
     //ctrlpf
     pf_reflected: bool,
+    // The playfield takes the players' colors, player 0's on the left and player 1's on the right
+    pf_score: bool,
+    // The playfield and ball are drawn in front of the players and missiles
+    pf_priority: bool,
 
     //pf registers
     pf0: u8,
     pf1: u8,
     pf2: u8,
 
+    // Ball
+    enabl: bool,
+    // width in color clocks, from CTRLPF
+    ball_size: usize,
+    resbl: usize,
+
     // color clocks this frame
     color_clocks: usize,
+    // Frames the beam has finished, and whether one finished since the picture was last taken
+    frames: usize,
+    frame_ready: bool,
+    // The length of the frame, where the picture starts, and the palette
+    tv_standard: TvStandard,
+    // color clocks the beam has been moved ahead of the cpu
+    lead: usize,
+    // Draw each object in a fixed color instead of the one the program set
+    debug_colors: bool,
 
     // Input handling
     joystick1_trigger_pressed: bool,
+    joystick1_button_b_pressed: bool,
+    // VBLANK D6, the fire buttons stay pressed once pressed until it's cleared
+    input_latch: bool,
+    joystick1_trigger_latched: bool,
+    joystick2_trigger_pressed: bool,
+    joystick2_trigger_latched: bool,
+    // VBLANK D7, the paddle lines are grounded
+    dump_ports: bool,
+    // Positions of the paddles plugged in, from 0 to 255. Higher positions are more resistance, so
+    // the capacitor takes longer to charge.
+    paddles: [Option<u8>; 4],
+    // Scan lines the paddle capacitors have been charging since the ports were last grounded
+    paddle_charge: usize,
 
     // Player 1 Sprite
     nusize0: Nusize,
     resp0: usize,
     grp0: u8,
+    hmp0: i8,
+
+    // Player 2 Sprite
+    nusize1: Nusize,
+    resp1: usize,
+    grp1: u8,
+
+    // Missiles, drawn in their player's color and copied like their player
+    enam0: bool,
+    enam1: bool,
+    // width in color clocks, from NUSIZ
+    missile0_size: usize,
+    missile1_size: usize,
+    resm0: usize,
+    resm1: usize,
+
+    // Horizontal motion of the other objects, signed like HMP0
+    hmp1: i8,
+    hmm0: i8,
+    hmm1: i8,
+    hmbl: i8,
+
+    // The scan line HMOVE was last strobed on during horizontal blank
+    hmove_blank_line: Option<usize>,
+
+    // Collision latches, in read address order
+    collisions: [u8; 8],
+
+    audio: [AudioChannel; 2],
+    // Samples made since they were last taken
+    audio_samples: Vec<i16>,
 
     pub buffer: Buffer,
 }
@@ -82,6 +243,10 @@ impl Default for Tia {
             wsync: false,
 
             set_resp0: false,
+            set_resp1: false,
+            set_resm0: false,
+            set_resm1: false,
+            set_resbl: false,
 
             // colors
             colupf: 0,
@@ -91,22 +256,66 @@ impl Default for Tia {
 
             //ctrlpf
             pf_reflected: false,
+            pf_score: false,
+            pf_priority: false,
 
             //pf registers
             pf0: 0,
             pf1: 0,
             pf2: 0,
 
+            // ball
+            enabl: false,
+            ball_size: 1,
+            resbl: 0,
+
             // color clocks this frame
             color_clocks: 0,
+            frames: 0,
+            frame_ready: false,
+            tv_standard: TvStandard::default(),
+            lead: 0,
+            debug_colors: false,
 
             // input handling
             joystick1_trigger_pressed: false,
+            joystick1_button_b_pressed: false,
+            input_latch: false,
+            joystick1_trigger_latched: false,
+            joystick2_trigger_pressed: false,
+            joystick2_trigger_latched: false,
+            dump_ports: false,
+            paddles: [None; 4],
+            paddle_charge: 0,
 
             // player info
             nusize0: Nusize::OneCopy,
             resp0: 0,
             grp0: 0,
+            hmp0: 0,
+            nusize1: Nusize::OneCopy,
+            resp1: 0,
+            grp1: 0,
+
+            // missiles
+            enam0: false,
+            enam1: false,
+            missile0_size: 1,
+            missile1_size: 1,
+            resm0: 0,
+            resm1: 0,
+
+            hmp1: 0,
+            hmm0: 0,
+            hmm1: 0,
+            hmbl: 0,
+
+            hmove_blank_line: None,
+
+            collisions: [0; 8],
+
+            audio: Default::default(),
+            audio_samples: vec![],
 
             buffer: Buffer([0xFF; BUFF_SIZE]),
         }
@@ -116,98 +325,382 @@ impl Default for Tia {
 impl Tia {
     pub fn set(&mut self, index: u16, value: u8) {
         match index {
-            0x00 => self.vsync = (value & 0x02) != 0,
-            // TODO: vblank does other thing on D6 and D7 pins, will need to be implemented
-            0x01 => self.vblank = (value & 0x02) != 0,
+            0x00 => {
+                let vsync = (value & 0x02) != 0;
+                // Turning VSYNC on starts the TV's vertical retrace, the beam goes back to the top
+                // of the frame and stays blank for as long as VSYNC is held. Horizontal timing
+                // carries on through it, so the beam keeps its column.
+                if vsync && !self.vsync {
+                    // A frame that already wrapped around to the top isn't finished twice
+                    if self.color_clocks >= COLOR_CLOCKS_PER_LINE {
+                        self.finish_frame();
+                    }
+                    self.color_clocks %= COLOR_CLOCKS_PER_LINE;
+                }
+                self.vsync = vsync;
+            }
+            0x01 => {
+                self.vblank = (value & 0x02) != 0;
+                self.input_latch = (value & 0x40) != 0;
+                // Turning the latch off resets it, turning it on catches a button held down.
+                // Writing VBLANK again with the latch on keeps a press that was already caught.
+                self.joystick1_trigger_latched = self.input_latch
+                    && (self.joystick1_trigger_latched || self.joystick1_trigger_pressed);
+                self.joystick2_trigger_latched = self.input_latch
+                    && (self.joystick2_trigger_latched || self.joystick2_trigger_pressed);
+                self.dump_ports = (value & 0x80) != 0;
+            }
             0x02 => self.wsync = true,
-            // TODO: RSYNC: can be ignored in most cases. There is one game that depends on this being
-            // handled correctly
-            0x03 => (),
-            0x04 => self.set_player1_nusize(value),
-            0x05 => (), // Ignored for now
-            0x06 => self.colup0 = value,
-            0x07 => self.colup1 = value,
-            0x08 => self.colupf = value,
-            0x09 => self.colubk = value,
-            // TODO: other parts of ctrlpf
-            0x0A => self.pf_reflected = (value & 0x01) == 1,
+            // RSYNC resets the horizontal counter, so the beam starts the line over from the left
+            // without moving down. Almost nothing uses it, it's meant for testing the chip.
+            0x03 => self.color_clocks -= self.beam_position(),
+            0x04 => {
+                self.nusize0 = Tia::nusize(value);
+                self.missile0_size = 1 << ((value >> 4) & 0x03);
+            }
+            0x05 => {
+                self.nusize1 = Tia::nusize(value);
+                self.missile1_size = 1 << ((value >> 4) & 0x03);
+            }
+            // D0 of the color registers isn't connected
+            0x06 => self.colup0 = value & 0xFE,
+            0x07 => self.colup1 = value & 0xFE,
+            0x08 => self.colupf = value & 0xFE,
+            0x09 => self.colubk = value & 0xFE,
+            0x0A => {
+                self.pf_reflected = (value & 0x01) == 1;
+                self.pf_score = (value & 0x02) != 0;
+                self.pf_priority = (value & 0x04) != 0;
+                self.ball_size = 1 << ((value >> 4) & 0x03);
+            }
             0x0B..=0x0C => (), // Ignored for now
             0x0D => self.pf0 = value & 0xF0,
             0x0E => self.pf1 = value,
             0x0F => self.pf2 = value,
             0x10 => self.set_resp0 = true,
-            0x11..=0x1A => (), // Ignored for now
+            0x11 => self.set_resp1 = true,
+            0x12 => self.set_resm0 = true,
+            0x13 => self.set_resm1 = true,
+            0x14 => self.set_resbl = true,
+            0x15 => self.audio[0].set_audc(value),
+            0x16 => self.audio[1].set_audc(value),
+            0x17 => self.audio[0].set_audf(value),
+            0x18 => self.audio[1].set_audf(value),
+            0x19 => self.audio[0].set_audv(value),
+            0x1A => self.audio[1].set_audv(value),
             0x1B => self.grp0 = value,
-            0x1C..=0x2C => (), // Ignored for now
+            0x1C => self.grp1 = value,
+            0x1D => self.enam0 = (value & 0x02) != 0,
+            0x1E => self.enam1 = (value & 0x02) != 0,
+            0x1F => self.enabl = (value & 0x02) != 0,
+            // Motion is the signed upper nibble, positive values move left
+            0x20 => self.hmp0 = (value as i8) >> 4,
+            0x21 => self.hmp1 = (value as i8) >> 4,
+            0x22 => self.hmm0 = (value as i8) >> 4,
+            0x23 => self.hmm1 = (value as i8) >> 4,
+            0x24 => self.hmbl = (value as i8) >> 4,
+            0x25..=0x29 => (), // Ignored for now
+            0x2A => self.hmove(),
+            0x2B => {
+                self.hmp0 = 0;
+                self.hmp1 = 0;
+                self.hmm0 = 0;
+                self.hmm1 = 0;
+                self.hmbl = 0;
+            }
+            0x2C => self.collisions = [0; 8],
             0x2D..=0x3F => (), // Unused
             _ => unreachable!("Tia set not implemented for {:04X} index", index),
         }
     }
 
-    pub fn get(&self, index: u16) -> u8 {
+    /// Reads a register. Nothing drives the data bus for the addresses without a register, so
+    /// those return None and the bus keeps the last value that was on it.
+    pub fn get(&self, index: u16) -> Option<u8> {
+        if let Some(&collisions) = self.collisions.get((index & 0x000F) as usize) {
+            return Some(collisions);
+        }
         // TODO: Needs a real implementation
         // If it ends in 0xC, it's trying to read player 0 input in this case 0
         // is pressed and 1 in the sign bit is the default state. We want to
         // return the default state until we implement input
         if (index & 0x000F) == INPT4 {
-            return if !self.joystick1_trigger_pressed {
+            return Some(
+                if !self.joystick1_trigger_pressed && !self.joystick1_trigger_latched {
+                    0b1000_0000
+                } else {
+                    0
+                },
+            );
+        }
+        if (index & 0x000F) == INPT5 {
+            return Some(
+                if !self.joystick2_trigger_pressed && !self.joystick2_trigger_latched {
+                    0b1000_0000
+                } else {
+                    0
+                },
+            );
+        }
+        if (INPT0..=INPT3).contains(&(index & 0x000F)) {
+            let paddle = (index & 0x000F) - INPT0;
+            let charged = match self.paddles[paddle as usize] {
+                Some(position) => self.paddle_charge >= Tia::paddle_charge_lines(position),
+                // The second button of a Genesis controller is wired to the paddle line. Unlike
+                // the fire button it connects the line to power when pressed, so pressed reads as
+                // 1.
+                None => index & 0x000F == INPT1 && self.joystick1_button_b_pressed,
+            };
+            return Some(if charged && !self.dump_ports {
                 0b1000_0000
             } else {
                 0
-            };
-        }
-        // This is not a valid address, but is used to waste time in some programs.
-        if (index & 0x000F) == 0x00E {
-            return 0;
+            });
         }
-        unimplemented!("Tia get not implemented for {:04X} index", index);
+        // $0E and $0F aren't registers. Some programs read them anyway, just to waste time.
+        None
     }
 
     // TODO: Use pf_colors
     pub fn tick(&mut self, clocks: usize) {
-        let new_color_clocks = self.color_clocks + clocks * COLOR_CLOCKS_PER_SYSTEM_CLOCK;
+        let color_clocks = clocks * COLOR_CLOCKS_PER_SYSTEM_CLOCK;
+        // Don't redraw anything the beam already drew while it was ahead of the cpu
+        let caught_up = color_clocks.min(self.lead);
+        self.lead -= caught_up;
+        self.advance(color_clocks - caught_up);
+    }
+
+    /// Moves the beam ahead of the cpu by a number of color clocks, without the cpu doing
+    /// anything. The beam holds its position until the cpu catches up, so timing is unchanged.
+    /// Used by the debugger to watch the beam move inside an instruction.
+    pub fn tick_color_clocks(&mut self, color_clocks: usize) {
+        self.advance(color_clocks);
+        self.lead += color_clocks;
+    }
+
+    fn advance(&mut self, color_clocks: usize) {
+        let new_color_clocks = self.color_clocks + color_clocks;
         let pf = self.get_playfield();
 
-        for i in self.color_clocks..=new_color_clocks {
+        if self.dump_ports {
+            self.paddle_charge = 0;
+        } else {
+            self.paddle_charge += new_color_clocks / COLOR_CLOCKS_PER_LINE
+                - self.color_clocks / COLOR_CLOCKS_PER_LINE;
+        }
+
+        let samples = new_color_clocks / COLOR_CLOCKS_PER_SAMPLE
+            - self.color_clocks / COLOR_CLOCKS_PER_SAMPLE;
+        for _ in 0..samples {
+            self.clock_audio();
+        }
+
+        for i in self.color_clocks..new_color_clocks {
             let column = Tia::column(i);
-            let line = Tia::row(i);
-            if column < DRAWING_COLUMNS && line < DRAWING_ROWS {
-                // Playfield Draw
-                let pixel = line * WIDTH as usize + column;
-                let pixel_start = pixel * STRIDE;
-                let pf_index = 40 - column / STRIDE;
-                let color = if pf & (1 << (pf_index - 1)) != 0 {
-                    COLOR_MAP[self.colupf as usize].as_slice()
+            let line = self.row(i);
+            if column >= DRAWING_COLUMNS {
+                continue;
+            }
+            // Objects collide even while the beam is blanked
+            let objects = self.objects(pf, column);
+            self.detect_collisions(&objects);
+
+            if line >= DRAWING_ROWS {
+                continue;
+            }
+            // Player 0 and missile 0 are drawn over player 1 and missile 1, which are drawn over
+            // the playfield and ball, unless the playfield has priority
+            let pf_color = if self.pf_score && objects.pf {
+                if column < DRAWING_COLUMNS / 2 {
+                    self.colup0
                 } else {
-                    COLOR_MAP[self.colubk as usize].as_slice()
-                };
-                self.buffer.0[pixel_start..=pixel_start + 3].copy_from_slice(&color);
-
-                // GRP0 Draw
-                // grp0[clocks on row - start]
-                if let Nusize::Quad = self.nusize0 {
-                    let sprite_start = self.resp0.wrapping_sub(DRAWING_START_COLUMN);
-                    if column >= sprite_start && column < sprite_start + (8 * 4) {
-                        let offset = column - sprite_start;
-                        if self.grp0 & 1 << (7 - (offset / 4)) != 0 {
-                            self.buffer.0[pixel_start..=pixel_start + 3]
-                                .copy_from_slice(&COLOR_MAP[self.colup0 as usize].as_slice());
-                        }
-                    }
+                    self.colup1
                 }
-            }
+            } else {
+                self.colupf
+            };
+            let color = if self.debug_colors {
+                self.debug_color(&objects)
+            } else if self.pf_priority && (objects.pf || objects.bl) {
+                pf_color
+            } else if objects.p0 || objects.m0 {
+                self.colup0
+            } else if objects.p1 || objects.m1 {
+                self.colup1
+            } else if objects.pf || objects.bl {
+                pf_color
+            } else {
+                self.colubk
+            };
+            // VBLANK and VSYNC force the beam black, so garbage left in the registers during
+            // blanking never makes it to the screen. HMOVE during horizontal blank extends it over
+            // the start of the line, leaving the "comb" of black lines down the left edge.
+            let color = if self.vblank
+                || self.vsync
+                || column < HMOVE_BLANK_COLUMNS
+                    && self.hmove_blank_line == Some(i / COLOR_CLOCKS_PER_LINE)
+            {
+                0
+            } else {
+                color
+            };
+
+            let pixel_start = (line * WIDTH as usize + column) * STRIDE;
+            self.buffer.0[pixel_start..=pixel_start + 3]
+                .copy_from_slice(&self.tv_standard.color(color).as_slice());
+        }
+        if new_color_clocks >= self.color_clocks_per_frame() {
+            self.finish_frame();
         }
-        self.color_clocks =
-            (self.color_clocks + clocks * COLOR_CLOCKS_PER_SYSTEM_CLOCK) % COLOR_CLOCKS_PER_FRAME;
+        self.color_clocks = new_color_clocks % self.color_clocks_per_frame();
     }
 
+    /// The color of the object on top at a pixel with debug colors on, stacked the same way the
+    /// program's colors are
+    fn debug_color(&self, objects: &Objects) -> u8 {
+        let playfield = if objects.bl {
+            DEBUG_COLOR_BL
+        } else {
+            DEBUG_COLOR_PF
+        };
+        if self.pf_priority && (objects.pf || objects.bl) {
+            playfield
+        } else if objects.p0 {
+            DEBUG_COLOR_P0
+        } else if objects.m0 {
+            DEBUG_COLOR_M0
+        } else if objects.p1 {
+            DEBUG_COLOR_P1
+        } else if objects.m1 {
+            DEBUG_COLOR_M1
+        } else if objects.pf || objects.bl {
+            playfield
+        } else {
+            self.colubk
+        }
+    }
+
+    fn finish_frame(&mut self) {
+        self.frames += 1;
+        self.frame_ready = true;
+    }
+
+    fn paddle_charge_lines(position: u8) -> usize {
+        position as usize * PADDLE_CHARGE_LINES / u8::MAX as usize
+    }
+
+    fn clock_audio(&mut self) {
+        let sample = audio::mix(self.audio[0].clock(), self.audio[1].clock());
+        // Nobody is taking the samples, don't let them pile up forever
+        if self.audio_samples.len() < MAX_AUDIO_SAMPLES {
+            self.audio_samples.push(sample);
+        }
+    }
+
+    /// Takes the audio samples made since the last call, at `SAMPLE_RATE`
+    pub fn take_audio_samples(&mut self) -> Vec<i16> {
+        std::mem::take(&mut self.audio_samples)
+    }
+
+    /// The objects drawing at a column of the current line
+    fn objects(&self, pf: u64, column: usize) -> Objects {
+        Objects {
+            pf: Tia::playfield_covers(pf, column),
+            bl: self.enabl && Tia::covers(self.resbl, self.ball_size, column),
+            p0: Tia::player_covers(self.grp0, self.resp0, &self.nusize0, column),
+            p1: Tia::player_covers(self.grp1, self.resp1, &self.nusize1, column),
+            m0: self.enam0
+                && Tia::missile_covers(self.resm0, self.missile0_size, &self.nusize0, column),
+            m1: self.enam1
+                && Tia::missile_covers(self.resm1, self.missile1_size, &self.nusize1, column),
+        }
+    }
+
+    /// Latches a bit for every pair of objects drawing at the same time. Each collision register
+    /// holds two pairs, in D7 and D6.
+    fn detect_collisions(&mut self, o: &Objects) {
+        let pairs = [
+            (CXM0P, o.m0 && o.p1, o.m0 && o.p0),
+            (CXM1P, o.m1 && o.p0, o.m1 && o.p1),
+            (CXP0FB, o.p0 && o.pf, o.p0 && o.bl),
+            (CXP1FB, o.p1 && o.pf, o.p1 && o.bl),
+            (CXM0FB, o.m0 && o.pf, o.m0 && o.bl),
+            (CXM1FB, o.m1 && o.pf, o.m1 && o.bl),
+            (CXBLPF, o.bl && o.pf, false),
+            (CXPPMM, o.p0 && o.p1, o.m0 && o.m1),
+        ];
+        for (register, d7, d6) in pairs {
+            self.collisions[register] |= ((d7 as u8) << 7) | ((d6 as u8) << 6);
+        }
+    }
+
+    /// The number of system clocks until the start of the next line. Rounds up so that the beam
+    /// never stops short of the line boundary.
     fn wsync_ticks(&self) -> usize {
-        (COLOR_CLOCKS_PER_LINE - self.color_clocks % COLOR_CLOCKS_PER_LINE)
-            / COLOR_CLOCKS_PER_SYSTEM_CLOCK
+        let cpu_color_clocks = self.color_clocks + self.color_clocks_per_frame() - self.lead;
+        (COLOR_CLOCKS_PER_LINE - cpu_color_clocks % COLOR_CLOCKS_PER_LINE)
+            .div_ceil(COLOR_CLOCKS_PER_SYSTEM_CLOCK)
+    }
+
+    /// The rows of the picture, top to bottom. Each row is `WIDTH` pixels of `STRIDE` bytes, in
+    /// the same order as `Buffer`: blue, green, red, then alpha.
+    pub fn scanlines(&self) -> impl Iterator<Item = &[u8]> {
+        self.buffer.0.chunks(WIDTH as usize * STRIDE)
     }
 
     pub fn is_drawing(&self) -> bool {
-        Tia::row(self.color_clocks) < DRAWING_ROWS
+        self.row(self.color_clocks) < DRAWING_ROWS
+    }
+
+    pub fn tv_standard(&self) -> TvStandard {
+        self.tv_standard
+    }
+
+    /// Changes the length of the frame and the palette. The beam wraps to the top if it's past
+    /// the end of the new frame.
+    pub fn set_tv_standard(&mut self, tv_standard: TvStandard) {
+        self.tv_standard = tv_standard;
+        self.color_clocks %= self.color_clocks_per_frame();
+    }
+
+    /// Draws the playfield, players, missiles, and ball each in a fixed color, so their placement
+    /// shows whatever colors the program picks
+    pub fn set_debug_colors(&mut self, debug_colors: bool) {
+        self.debug_colors = debug_colors;
+    }
+
+    /// The cpu clocks in a frame of the TV standard
+    pub fn clocks_per_frame(&self) -> usize {
+        self.color_clocks_per_frame() / COLOR_CLOCKS_PER_SYSTEM_CLOCK
+    }
+
+    fn color_clocks_per_frame(&self) -> usize {
+        COLOR_CLOCKS_PER_LINE * self.tv_standard.scan_lines()
+    }
+
+    pub fn is_vsync(&self) -> bool {
+        self.vsync
+    }
+
+    /// Frames the beam has finished, either by running off the bottom of the picture or by the
+    /// program starting a new one with VSYNC
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+
+    /// Whether the beam has finished a frame since `clear_frame_ready` was last called, so the
+    /// picture is ready to show
+    pub fn frame_ready(&self) -> bool {
+        self.frame_ready
+    }
+
+    pub fn clear_frame_ready(&mut self) {
+        self.frame_ready = false;
+    }
+
+    /// The beam's position in color clocks since the start of the frame
+    pub fn frame_position(&self) -> usize {
+        self.color_clocks
     }
 
     /// Sync syncs the tia, and returns a number of ticks to advance the clock. Used for the wsync
@@ -219,29 +712,197 @@ impl Tia {
             self.resp0 = self.beam_position() + 6;
             self.set_resp0 = false;
         }
+        if self.set_resp1 {
+            self.resp1 = self.beam_position() + 6;
+            self.set_resp1 = false;
+        }
+        if self.set_resm0 {
+            self.resm0 = self.beam_position() + 5;
+            self.set_resm0 = false;
+        }
+        if self.set_resm1 {
+            self.resm1 = self.beam_position() + 5;
+            self.set_resm1 = false;
+        }
+        if self.set_resbl {
+            // The ball starts drawing a clock sooner than the players
+            self.resbl = self.beam_position() + 5;
+            self.set_resbl = false;
+        }
 
         if self.wsync {
-            let clocks = WsyncClocks {
+            self.wsync = false;
+            return WsyncClocks {
                 value: self.wsync_ticks(),
             };
-            if self.vsync {
-                self.color_clocks = 228 * 3;
-            }
-            self.wsync = false;
-            return clocks;
         }
         WsyncClocks { value: 0 }
     }
 
+    /// Saves the registers and the beam. The picture is redrawn by the next frame, and the
+    /// controllers follow the keyboard, so neither is saved.
+    pub fn save_state(&self, state: &mut StateWriter) {
+        for value in [
+            self.vsync,
+            self.vblank,
+            self.wsync,
+            self.set_resp0,
+            self.set_resp1,
+            self.set_resm0,
+            self.set_resm1,
+            self.set_resbl,
+            self.pf_reflected,
+            self.pf_score,
+            self.pf_priority,
+            self.enabl,
+            self.input_latch,
+            self.joystick1_trigger_latched,
+            self.joystick2_trigger_latched,
+            self.dump_ports,
+            self.enam0,
+            self.enam1,
+        ] {
+            state.bool(value);
+        }
+        for value in [
+            self.colupf,
+            self.colubk,
+            self.colup0,
+            self.colup1,
+            self.pf0,
+            self.pf1,
+            self.pf2,
+            self.nusize0 as u8,
+            self.grp0,
+            self.hmp0 as u8,
+            self.nusize1 as u8,
+            self.grp1,
+            self.hmp1 as u8,
+            self.hmm0 as u8,
+            self.hmm1 as u8,
+            self.hmbl as u8,
+        ] {
+            state.u8(value);
+        }
+        for value in [
+            self.paddle_charge,
+            self.ball_size,
+            self.resbl,
+            self.color_clocks,
+            self.lead,
+            self.resp0,
+            self.resp1,
+            self.missile0_size,
+            self.missile1_size,
+            self.resm0,
+            self.resm1,
+        ] {
+            state.usize(value);
+        }
+        state.bool(self.hmove_blank_line.is_some());
+        state.usize(self.hmove_blank_line.unwrap_or_default());
+        state.bytes(&self.collisions);
+        for channel in &self.audio {
+            channel.save_state(state);
+        }
+    }
+
+    pub fn load_state(&mut self, state: &mut StateReader) -> Result<()> {
+        for value in [
+            &mut self.vsync,
+            &mut self.vblank,
+            &mut self.wsync,
+            &mut self.set_resp0,
+            &mut self.set_resp1,
+            &mut self.set_resm0,
+            &mut self.set_resm1,
+            &mut self.set_resbl,
+            &mut self.pf_reflected,
+            &mut self.pf_score,
+            &mut self.pf_priority,
+            &mut self.enabl,
+            &mut self.input_latch,
+            &mut self.joystick1_trigger_latched,
+            &mut self.joystick2_trigger_latched,
+            &mut self.dump_ports,
+            &mut self.enam0,
+            &mut self.enam1,
+        ] {
+            *value = state.bool()?;
+        }
+        for value in [
+            &mut self.colupf,
+            &mut self.colubk,
+            &mut self.colup0,
+            &mut self.colup1,
+            &mut self.pf0,
+            &mut self.pf1,
+            &mut self.pf2,
+        ] {
+            *value = state.u8()?;
+        }
+        self.nusize0 = Tia::nusize(state.u8()?);
+        self.grp0 = state.u8()?;
+        self.hmp0 = state.u8()? as i8;
+        self.nusize1 = Tia::nusize(state.u8()?);
+        self.grp1 = state.u8()?;
+        for value in [
+            &mut self.hmp1,
+            &mut self.hmm0,
+            &mut self.hmm1,
+            &mut self.hmbl,
+        ] {
+            *value = state.u8()? as i8;
+        }
+        for value in [
+            &mut self.paddle_charge,
+            &mut self.ball_size,
+            &mut self.resbl,
+            &mut self.color_clocks,
+            &mut self.lead,
+            &mut self.resp0,
+            &mut self.resp1,
+            &mut self.missile0_size,
+            &mut self.missile1_size,
+            &mut self.resm0,
+            &mut self.resm1,
+        ] {
+            *value = state.usize()?;
+        }
+        let hmove_blanked = state.bool()?;
+        let hmove_blank_line = state.usize()?;
+        self.hmove_blank_line = hmove_blanked.then_some(hmove_blank_line);
+        state.bytes(&mut self.collisions)?;
+        for channel in &mut self.audio {
+            channel.load_state(state)?;
+        }
+        Ok(())
+    }
+
     /// Handles an input start or end event from the window, updating its internal state to match.
     pub fn input_event(&mut self, event: &WindowEvent) {
         match event {
             WindowEvent::InputStart(InputType::Joystick1Button) => {
-                self.joystick1_trigger_pressed = true
+                self.joystick1_trigger_pressed = true;
+                self.joystick1_trigger_latched = self.input_latch;
+            }
+            WindowEvent::PaddleMoved(position) => self.paddles[0] = Some(*position),
+            WindowEvent::InputStart(InputType::Joystick2Button) => {
+                self.joystick2_trigger_pressed = true;
+                self.joystick2_trigger_latched = self.input_latch;
+            }
+            WindowEvent::InputEnd(InputType::Joystick2Button) => {
+                self.joystick2_trigger_pressed = false
             }
             WindowEvent::InputEnd(InputType::Joystick1Button) => {
                 self.joystick1_trigger_pressed = false
             }
+            WindowEvent::InputStart(InputType::Joystick1ButtonB) => {
+                self.joystick1_button_b_pressed = true
+            }
+            WindowEvent::InputEnd(InputType::Joystick1ButtonB) => {
+                self.joystick1_button_b_pressed = false
+            }
             _ => (),
         }
     }
@@ -258,12 +919,45 @@ impl Tia {
             }
     }
 
+    /// Whether the playfield covers a column. The left most column is the playfield's highest bit.
+    /// Columns past the edge of the picture aren't covered.
+    fn playfield_covers(pf: u64, column: usize) -> bool {
+        match PLAYFIELD_BITS.checked_sub(column / PLAYFIELD_BIT_WIDTH + 1) {
+            Some(bit) => pf & (1 << bit) != 0,
+            None => false,
+        }
+    }
+
+    /// Whether an object at a beam position, and a number of color clocks wide, covers a column
+    fn covers(position: usize, width: usize, column: usize) -> bool {
+        column.wrapping_sub(position.wrapping_sub(DRAWING_START_COLUMN)) < width
+    }
+
+    /// Whether a set bit of a player's graphics covers a column. Each bit of the graphics is drawn
+    /// for as many color clocks as the player is stretched, with the first bit the left most.
+    fn player_covers(grp: u8, position: usize, nusize: &Nusize, column: usize) -> bool {
+        let stretch = nusize.stretch();
+        nusize.copies().iter().any(|copy| {
+            let offset = column.wrapping_sub((position + copy).wrapping_sub(DRAWING_START_COLUMN));
+            offset < 8 * stretch && grp & (0x80 >> (offset / stretch)) != 0
+        })
+    }
+
+    /// Whether any copy of a missile covers a column. Missiles are copied like their players, but
+    /// aren't stretched.
+    fn missile_covers(position: usize, width: usize, nusize: &Nusize, column: usize) -> bool {
+        nusize
+            .copies()
+            .iter()
+            .any(|copy| Tia::covers(position + copy, width, column))
+    }
+
     fn column(color_clocks: usize) -> usize {
         (color_clocks % COLOR_CLOCKS_PER_LINE).wrapping_sub(DRAWING_START_COLUMN)
     }
 
-    fn row(color_clocks: usize) -> usize {
-        (color_clocks / COLOR_CLOCKS_PER_LINE).wrapping_sub(DRAWING_START_ROW)
+    fn row(&self, color_clocks: usize) -> usize {
+        (color_clocks / COLOR_CLOCKS_PER_LINE).wrapping_sub(self.tv_standard.drawing_start_row())
     }
 
     fn scan_line(&self) -> usize {
@@ -274,12 +968,35 @@ impl Tia {
         self.color_clocks % COLOR_CLOCKS_PER_LINE
     }
 
-    fn set_player1_nusize(&mut self, value: u8) {
-        self.nusize0 = match value {
+    /// Applies the motion registers to the object positions. Every strobe moves the objects again,
+    /// so kernels can strobe it on consecutive lines to keep moving an object.
+    fn hmove(&mut self) {
+        self.resp0 = Tia::apply_motion(self.resp0, self.hmp0);
+        self.resp1 = Tia::apply_motion(self.resp1, self.hmp1);
+        self.resm0 = Tia::apply_motion(self.resm0, self.hmm0);
+        self.resm1 = Tia::apply_motion(self.resm1, self.hmm1);
+        self.resbl = Tia::apply_motion(self.resbl, self.hmbl);
+        if self.beam_position() < DRAWING_START_COLUMN {
+            self.hmove_blank_line = Some(self.scan_line());
+        }
+    }
+
+    fn apply_motion(position: usize, motion: i8) -> usize {
+        let column = position as isize - DRAWING_START_COLUMN as isize - motion as isize;
+        column.rem_euclid(DRAWING_COLUMNS as isize) as usize + DRAWING_START_COLUMN
+    }
+
+    fn nusize(value: u8) -> Nusize {
+        match value & 0x07 {
             0x00 => Nusize::OneCopy,
-            0x07 => Nusize::Quad,
-            _ => unimplemented!("No size, or number matching {value:02X}"),
-        };
+            0x01 => Nusize::TwoCopiesClose,
+            0x02 => Nusize::TwoCopiesMedium,
+            0x03 => Nusize::ThreeCopiesClose,
+            0x04 => Nusize::TwoCopiesWide,
+            0x05 => Nusize::Double,
+            0x06 => Nusize::ThreeCopiesMedium,
+            _ => Nusize::Quad,
+        }
     }
 }
 
@@ -293,6 +1010,11 @@ Colors: COLUBK: {:02X} | COLUPF: {:02X} | Current Line: {} | Beam Position: {}\r
 VSYNC: {} | VBLANK: {}\r\n
 Playfields: PF0({:08b}) PF1({:08b}) PF2({:08b})\r\n
 Player 1 Sprite: Musize({:?}) RESP0({}) GRP0({:08b})\r\n
+Player 2 Sprite: Musize({:?}) RESP1({}) GRP1({:08b})\r\n
+Missiles: ENAM0({}) RESM0({}) Size({}) | ENAM1({}) RESM1({}) Size({})\r\n
+Ball: ENABL({}) RESBL({}) Size({})\r\n
+Motion: HMP0({}) HMP1({}) HMM0({}) HMM1({}) HMBL({})\r\n
+Collisions: {:02X?}\r\n
             ",
             self.colubk,
             self.colupf,
@@ -306,6 +1028,776 @@ Player 1 Sprite: Musize({:?}) RESP0({}) GRP0({:08b})\r\n
             self.nusize0,
             self.resp0,
             self.grp0,
+            self.nusize1,
+            self.resp1,
+            self.grp1,
+            self.enam0,
+            self.resm0,
+            self.missile0_size,
+            self.enam1,
+            self.resm1,
+            self.missile1_size,
+            self.enabl,
+            self.resbl,
+            self.ball_size,
+            self.hmp0,
+            self.hmp1,
+            self.hmm0,
+            self.hmm1,
+            self.hmbl,
+            self.collisions,
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DRAWING_START_ROW: usize = TvStandard::Ntsc.drawing_start_row();
+
+    fn color(value: u8) -> [u8; 4] {
+        TvStandard::Ntsc.color(value).as_slice()
+    }
+
+    fn pixel(tia: &Tia, row: usize, column: usize) -> &[u8] {
+        let start = (row * WIDTH as usize + column) * STRIDE;
+        &tia.buffer.0[start..start + STRIDE]
+    }
+
+    #[test]
+    fn test_vblank_suppresses_drawing() {
+        let mut tia = Tia::default();
+        tia.set(0x09, 0x0E);
+        tia.set(0x01, 0x02);
+        tia.tick(COLOR_CLOCKS_PER_FRAME / COLOR_CLOCKS_PER_SYSTEM_CLOCK);
+        // The blanked lines are drawn black rather than left as they were
+        assert!(tia.buffer.0.chunks(STRIDE).all(|pixel| pixel == color(0)));
+
+        tia.set(0x01, 0x00);
+        tia.tick(COLOR_CLOCKS_PER_FRAME / COLOR_CLOCKS_PER_SYSTEM_CLOCK);
+        assert_eq!(tia.buffer.0[0..4], color(0x0E));
+    }
+
+    #[test]
+    fn test_vsync_suppresses_drawing() {
+        let mut tia = Tia::default();
+        tia.set(0x09, 0x0E);
+        tia.set(0x00, 0x02);
+        tia.tick(COLOR_CLOCKS_PER_FRAME / COLOR_CLOCKS_PER_SYSTEM_CLOCK);
+        assert!(tia.buffer.0.chunks(STRIDE).all(|pixel| pixel == color(0)));
+    }
+
+    #[test]
+    fn test_wsync_lands_on_line_boundary() {
+        for start in [1, 2, 3, 10, 75, 76, 100, 300] {
+            let mut tia = Tia::default();
+            tia.tick(start);
+            tia.set(0x02, 0);
+            let clocks = tia.sync().value;
+            tia.tick(clocks);
+            assert_eq!(
+                tia.color_clocks % COLOR_CLOCKS_PER_LINE,
+                0,
+                "start: {start}"
+            );
+            assert!(tia.color_clocks > start * COLOR_CLOCKS_PER_SYSTEM_CLOCK);
+        }
+    }
+
+    /// Waits for the start of the next line, like STA WSYNC
+    fn wsync(tia: &mut Tia) {
+        tia.set(0x02, 0);
+        let clocks = tia.sync().value;
+        tia.tick(clocks);
+    }
+
+    /// Runs a frame the way a kernel does: 3 lines of VSYNC, then VBLANK until the picture, then
+    /// the rest of the lines. Each line draws the background in a color from the line number.
+    fn frame(tia: &mut Tia, lines: usize) {
+        tia.set(0x00, 0x02);
+        tia.set(0x01, 0x02);
+        for _ in 0..3 {
+            wsync(tia);
+        }
+        tia.set(0x00, 0x00);
+        for _ in 3..DRAWING_START_ROW {
+            wsync(tia);
+        }
+        tia.set(0x01, 0x00);
+        for line in DRAWING_START_ROW..lines {
+            tia.set(0x09, (line as u8) << 1);
+            wsync(tia);
+        }
+    }
+
+    #[test]
+    fn test_wsync_during_vsync_lands_on_line_boundary() {
+        let mut tia = Tia::default();
+        tia.tick(1000);
+        tia.set(0x00, 0x02);
+        // The frame starts over, in the same column
+        assert_eq!(tia.color_clocks, 1000 * 3 % COLOR_CLOCKS_PER_LINE);
+        for _ in 0..3 {
+            wsync(&mut tia);
+        }
+        assert_eq!(tia.color_clocks, COLOR_CLOCKS_PER_LINE * 3);
+        // Holding VSYNC doesn't restart the frame again
+        tia.set(0x00, 0x02);
+        assert_eq!(tia.color_clocks, COLOR_CLOCKS_PER_LINE * 3);
+    }
+
+    #[test]
+    fn test_frame_counter() {
+        let mut tia = Tia::default();
+        tia.tick(CLOCKS_PER_FRAME - 1);
+        assert_eq!(tia.frames(), 0);
+        assert!(!tia.frame_ready());
+
+        tia.tick(1);
+        assert_eq!(tia.frames(), 1);
+        assert!(tia.frame_ready());
+        tia.clear_frame_ready();
+        assert!(!tia.frame_ready());
+
+        // Starting VSYNC right after the wrap doesn't finish another frame
+        tia.set(0x00, 0x02);
+        assert_eq!(tia.frames(), 1);
+        tia.set(0x00, 0x00);
+        tia.tick(CLOCKS_PER_LINE * 10);
+        // A frame cut short by VSYNC counts
+        tia.set(0x00, 0x02);
+        assert_eq!(tia.frames(), 2);
+        assert!(tia.frame_ready());
+    }
+
+    #[test]
+    fn test_unused_reads_leave_the_bus_alone() {
+        let tia = Tia::default();
+        assert_eq!(tia.get(0x0E), None);
+        assert_eq!(tia.get(0x0F), None);
+        assert_eq!(tia.get(INPT4), Some(0b1000_0000));
+    }
+
+    #[test]
+    fn test_rsync_restarts_the_line() {
+        let mut tia = Tia::default();
+        tia.tick(CLOCKS_PER_LINE * 5 + 30);
+        assert_eq!(tia.beam_position(), 90);
+        tia.set(0x03, 0);
+        assert_eq!(tia.beam_position(), 0);
+        assert_eq!(tia.color_clocks, COLOR_CLOCKS_PER_LINE * 5);
+    }
+
+    #[test]
+    fn test_vsync_aligns_frames() {
+        let mut tia = Tia::default();
+        // Start somewhere in the middle of a line and a frame
+        tia.tick(12_345);
+        wsync(&mut tia);
+
+        frame(&mut tia, SCAN_LINES);
+        let first = tia.buffer.0;
+        // A short frame still starts the picture in the same place, the next VSYNC restarts it
+        frame(&mut tia, SCAN_LINES - 10);
+        frame(&mut tia, SCAN_LINES);
+        assert_eq!(tia.color_clocks, 0);
+        assert_eq!(tia.buffer.0, first);
+        assert_eq!(pixel(&tia, 0, 0), color((DRAWING_START_ROW as u8) << 1));
+    }
+
+    #[test]
+    fn test_paddle_charge() {
+        let mut tia = Tia::default();
+        tia.input_event(&WindowEvent::PaddleMoved(100));
+        let threshold = Tia::paddle_charge_lines(100);
+        assert_eq!(threshold, 74);
+
+        // Grounding the ports discharges the capacitor
+        tia.set(0x01, 0x80);
+        tia.tick(CLOCKS_PER_LINE * 200);
+        assert_eq!(tia.get(INPT0), Some(0));
+
+        tia.set(0x01, 0x00);
+        tia.tick(CLOCKS_PER_LINE * (threshold - 1));
+        assert_eq!(tia.get(INPT0), Some(0));
+        tia.tick(CLOCKS_PER_LINE);
+        assert_eq!(tia.get(INPT0), Some(0b1000_0000));
+        // Nothing is plugged into the other ports
+        assert_eq!(tia.get(INPT0 + 2), Some(0));
+    }
+
+    #[test]
+    fn test_joystick2_button() {
+        let mut tia = Tia::default();
+        assert_eq!(tia.get(INPT5), Some(0b1000_0000));
+        tia.input_event(&WindowEvent::InputStart(InputType::Joystick2Button));
+        assert_eq!(tia.get(INPT5), Some(0));
+        assert_eq!(tia.get(INPT4), Some(0b1000_0000));
+        tia.input_event(&WindowEvent::InputEnd(InputType::Joystick2Button));
+        assert_eq!(tia.get(INPT5), Some(0b1000_0000));
+    }
+
+    #[test]
+    fn test_joystick2_input_latch() {
+        let mut tia = Tia::default();
+        tia.set(0x01, 0x40);
+        tia.input_event(&WindowEvent::InputStart(InputType::Joystick2Button));
+        tia.input_event(&WindowEvent::InputEnd(InputType::Joystick2Button));
+        assert_eq!(tia.get(INPT5), Some(0));
+
+        // Rewriting VBLANK with D6 set keeps the press
+        tia.set(0x01, 0x40);
+        assert_eq!(tia.get(INPT5), Some(0));
+        tia.set(0x01, 0x00);
+        assert_eq!(tia.get(INPT5), Some(0b1000_0000));
+    }
+
+    #[test]
+    fn test_genesis_button_b() {
+        let mut tia = Tia::default();
+        assert_eq!(tia.get(INPT1), Some(0));
+        assert_eq!(tia.get(INPT4), Some(0b1000_0000));
+
+        tia.input_event(&WindowEvent::InputStart(InputType::Joystick1ButtonB));
+        assert_eq!(tia.get(INPT1), Some(0b1000_0000));
+        assert_eq!(tia.get(INPT4), Some(0b1000_0000));
+
+        tia.input_event(&WindowEvent::InputEnd(InputType::Joystick1ButtonB));
+        assert_eq!(tia.get(INPT1), Some(0));
+    }
+
+    #[test]
+    fn test_pal_frames() {
+        let mut tia = Tia::default();
+        assert_eq!(tia.clocks_per_frame(), CLOCKS_PER_FRAME);
+        tia.set_tv_standard(TvStandard::Pal);
+        assert_eq!(tia.clocks_per_frame(), CLOCKS_PER_LINE * 312);
+        tia.set(0x09, 0x24);
+        tia.tick(CLOCKS_PER_FRAME);
+        // An NTSC frame isn't long enough to wrap a PAL one
+        assert_eq!(tia.frame_position(), COLOR_CLOCKS_PER_FRAME);
+        tia.tick(CLOCKS_PER_LINE * 50);
+        assert_eq!(tia.frame_position(), 0);
+        assert_eq!(pixel(&tia, 0, 0), TvStandard::Pal.color(0x24).as_slice());
+        assert_ne!(pixel(&tia, 0, 0), color(0x24));
+    }
+
+    #[test]
+    fn test_odd_colors_ignore_d0() {
+        let mut odd = Tia::default();
+        odd.set(0x09, 0x0F);
+        odd.tick(COLOR_CLOCKS_PER_FRAME / COLOR_CLOCKS_PER_SYSTEM_CLOCK);
+
+        let mut even = Tia::default();
+        even.set(0x09, 0x0E);
+        even.tick(COLOR_CLOCKS_PER_FRAME / COLOR_CLOCKS_PER_SYSTEM_CLOCK);
+
+        assert_eq!(odd.colubk, 0x0E);
+        assert_eq!(odd.buffer.0, even.buffer.0);
+        for color_value in (0..=0xFF).step_by(2) {
+            assert_eq!(color(color_value), color(color_value | 1));
+        }
+    }
+
+    #[test]
+    fn test_tick_color_clocks() {
+        let mut tia = Tia::default();
+        tia.tick_color_clocks(1);
+        assert_eq!(tia.color_clocks, 1);
+        tia.tick_color_clocks(2);
+        assert_eq!(tia.color_clocks, 3);
+
+        // The cpu catching up doesn't move the beam any further
+        tia.tick(1);
+        assert_eq!(tia.color_clocks, 3);
+        tia.tick(1);
+        assert_eq!(tia.color_clocks, 6);
+
+        tia.tick_color_clocks(4);
+        tia.tick(2);
+        assert_eq!(tia.color_clocks, 12);
+    }
+
+    #[test]
+    fn test_playfield_covers_the_whole_line() {
+        let mut tia = Tia::default();
+        tia.set(0x09, 0x0E);
+        tia.set(0x08, 0x44);
+        for register in [0x0D, 0x0E, 0x0F] {
+            tia.set(register, 0xFF);
+        }
+        tia.tick(CLOCKS_PER_LINE * (DRAWING_START_ROW + 1));
+        for column in 0..DRAWING_COLUMNS {
+            assert_eq!(pixel(&tia, 0, column), color(0x44), "column {column}");
+        }
+        assert!(!Tia::playfield_covers(u64::MAX, DRAWING_COLUMNS));
+    }
+
+    #[test]
+    fn test_reflected_playfield_is_symmetric() {
+        let mut tia = Tia::default();
+        tia.set(0x09, 0x0E);
+        tia.set(0x08, 0x44);
+        // CTRLPF reflect
+        tia.set(0x0A, 0x01);
+        tia.set(0x0D, 0x10);
+        tia.set(0x0E, 0x81);
+        tia.set(0x0F, 0x03);
+        tia.tick(CLOCKS_PER_LINE * (DRAWING_START_ROW + 1));
+
+        // PF0 D4 is the left most bit, PF1 D7 and PF2 D0 are the first bits of their registers
+        assert_eq!(pixel(&tia, 0, 0), color(0x44));
+        assert_eq!(pixel(&tia, 0, 16), color(0x44));
+        assert_eq!(pixel(&tia, 0, 48), color(0x44));
+        assert_eq!(pixel(&tia, 0, 4), color(0x0E));
+        for column in 0..DRAWING_COLUMNS / 2 {
+            assert_eq!(
+                pixel(&tia, 0, column),
+                pixel(&tia, 0, DRAWING_COLUMNS - 1 - column),
+                "column {column}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_split_ticks_draw_the_same() {
+        let setup = || {
+            let mut tia = Tia::default();
+            tia.set(0x09, 0x0E);
+            tia.set(0x0D, 0xF0);
+            tia.set(0x1B, 0xAA);
+            tia.set(0x06, 0x44);
+            tia
+        };
+        let mut once = setup();
+        once.tick(CLOCKS_PER_LINE * (DRAWING_START_ROW + 10) + 31);
+        let mut split = setup();
+        split.tick(CLOCKS_PER_LINE * (DRAWING_START_ROW + 5) + 7);
+        split.tick(CLOCKS_PER_LINE * 5 + 24);
+
+        assert_eq!(once.color_clocks, split.color_clocks);
+        assert_eq!(once.buffer.0, split.buffer.0);
+        assert_eq!(once.collisions, split.collisions);
+    }
+
+    #[test]
+    fn test_tick_stops_before_the_beam() {
+        let mut tia = Tia::default();
+        let untouched = pixel(&tia, 0, 10).to_vec();
+        tia.set(0x09, 0x0E);
+        // Up to the color clock of pixel 10 on the first line of the picture
+        let color_clocks = COLOR_CLOCKS_PER_LINE * DRAWING_START_ROW + DRAWING_START_COLUMN + 10;
+        tia.tick(color_clocks / COLOR_CLOCKS_PER_SYSTEM_CLOCK);
+        assert_eq!(tia.color_clocks, color_clocks);
+        assert_eq!(pixel(&tia, 0, 9), color(0x0E));
+        // The beam is on pixel 10, which isn't drawn until the next tick
+        assert_eq!(pixel(&tia, 0, 10), untouched);
+        tia.tick(1);
+        assert_eq!(pixel(&tia, 0, 10), color(0x0E));
+    }
+
+    #[test]
+    fn test_ball() {
+        let mut tia = Tia::default();
+        tia.set(0x09, 0x0E);
+        tia.set(0x08, 0x44);
+        // Four clocks wide
+        tia.set(0x0A, 0x20);
+        tia.set(0x1F, 0x02);
+        tia.resbl = DRAWING_START_COLUMN + 20;
+        tia.tick(CLOCKS_PER_LINE * (DRAWING_START_ROW + 1));
+
+        let background = color(0x0E);
+        let ball = color(0x44);
+        assert_eq!(pixel(&tia, 0, 19), background);
+        for column in 20..24 {
+            assert_eq!(pixel(&tia, 0, column), ball);
+        }
+        assert_eq!(pixel(&tia, 0, 24), background);
+
+        // Disabled, it's gone on the next frame
+        tia.set(0x1F, 0x00);
+        tia.tick(CLOCKS_PER_FRAME - CLOCKS_PER_LINE * (DRAWING_START_ROW + 1));
+        tia.tick(CLOCKS_PER_LINE * (DRAWING_START_ROW + 1));
+        assert_eq!(pixel(&tia, 0, 20), background);
+    }
+
+    #[test]
+    fn test_playfield_score_mode() {
+        let mut tia = Tia::default();
+        tia.set(0x06, 0x44);
+        tia.set(0x07, 0x86);
+        tia.set(0x08, 0x1E);
+        tia.set(0x0D, 0xF0);
+        tia.set(0x0A, 0x02);
+        tia.tick(CLOCKS_PER_LINE * (DRAWING_START_ROW + 1));
+
+        // PF0 is repeated on both halves of the line
+        assert_eq!(pixel(&tia, 0, 0), color(0x44));
+        assert_eq!(pixel(&tia, 0, 80), color(0x86));
+    }
+
+    #[test]
+    fn test_scanlines() {
+        let mut tia = Tia::default();
+        tia.set(0x09, 0x0E);
+        tia.tick(CLOCKS_PER_LINE * (DRAWING_START_ROW + 1));
+
+        let lines: Vec<&[u8]> = tia.scanlines().collect();
+        assert_eq!(lines.len(), HEIGHT as usize);
+        assert!(lines
+            .iter()
+            .all(|line| line.len() == WIDTH as usize * STRIDE));
+        // Only the first line has been drawn
+        assert_eq!(lines[0][..STRIDE], color(0x0E));
+        assert_ne!(lines[1][..STRIDE], color(0x0E));
+    }
+
+    #[test]
+    fn test_buffer_channel_order() {
+        let mut buffer = Buffer([0; BUFF_SIZE]);
+        // Stored blue first, the way the palette writes it
+        buffer.0[..4].copy_from_slice(&[0x30, 0x20, 0x10, 0xFF]);
+        assert_eq!(buffer.as_rgb()[..3], [0x10, 0x20, 0x30]);
+        assert_eq!(buffer.as_rgba()[..4], [0x10, 0x20, 0x30, 0xFF]);
+        assert_eq!(buffer.as_rgb().len(), WIDTH as usize * HEIGHT as usize * 3);
+        assert_eq!(buffer.as_rgba().len(), BUFF_SIZE);
+    }
+
+    #[test]
+    fn test_debug_colors() {
+        let mut tia = Tia::default();
+        tia.set_debug_colors(true);
+        tia.set(0x08, 0x1E);
+        tia.set(0x09, 0x0E);
+        tia.set(0x0D, 0xF0);
+        tia.set(0x06, 0x44);
+        tia.set(0x1B, 0xFF);
+        tia.resp0 = DRAWING_START_COLUMN + 20;
+        tia.tick(CLOCKS_PER_LINE * (DRAWING_START_ROW + 1));
+
+        assert_eq!(pixel(&tia, 0, 0), color(DEBUG_COLOR_PF));
+        assert_ne!(pixel(&tia, 0, 0), color(0x1E));
+        assert_eq!(pixel(&tia, 0, 20), color(DEBUG_COLOR_P0));
+        // The background keeps the program's color
+        assert_eq!(pixel(&tia, 0, 40), color(0x0E));
+    }
+
+    #[test]
+    fn test_playfield_priority() {
+        let mut tia = Tia::default();
+        tia.set(0x06, 0x44);
+        tia.set(0x08, 0x1E);
+        tia.set(0x0D, 0xF0);
+        tia.set(0x1B, 0xFF);
+        tia.resp0 = DRAWING_START_COLUMN + 10;
+        tia.tick(CLOCKS_PER_LINE * (DRAWING_START_ROW + 1));
+        assert_eq!(pixel(&tia, 0, 12), color(0x44));
+
+        tia.set(0x0A, 0x04);
+        tia.tick(CLOCKS_PER_FRAME - CLOCKS_PER_LINE * (DRAWING_START_ROW + 1));
+        tia.tick(CLOCKS_PER_LINE * (DRAWING_START_ROW + 1));
+        assert_eq!(pixel(&tia, 0, 12), color(0x1E));
+        // Outside the playfield the player still shows
+        assert_eq!(pixel(&tia, 0, 17), color(0x44));
+    }
+
+    #[test]
+    fn test_resbl_uses_beam_position() {
+        let mut tia = Tia::default();
+        tia.tick(30);
+        tia.set(0x14, 0);
+        tia.sync();
+        assert_eq!(tia.resbl, 30 * COLOR_CLOCKS_PER_SYSTEM_CLOCK + 5);
+    }
+
+    #[test]
+    fn test_both_players() {
+        let mut tia = Tia::default();
+        tia.set(0x09, 0x0E);
+        tia.set(0x06, 0x44);
+        tia.set(0x07, 0x86);
+        tia.set(0x1B, 0b1000_0001);
+        tia.set(0x1C, 0b1100_0000);
+        tia.set(0x05, 0x07);
+        tia.resp0 = DRAWING_START_COLUMN + 10;
+        tia.resp1 = DRAWING_START_COLUMN + 100;
+        tia.tick(CLOCKS_PER_LINE * (DRAWING_START_ROW + 1));
+
+        let background = color(0x0E);
+        let player0 = color(0x44);
+        let player1 = color(0x86);
+        assert_eq!(pixel(&tia, 0, 9), background);
+        assert_eq!(pixel(&tia, 0, 10), player0);
+        assert_eq!(pixel(&tia, 0, 11), background);
+        assert_eq!(pixel(&tia, 0, 17), player0);
+        assert_eq!(pixel(&tia, 0, 18), background);
+
+        // Player 1 is quad sized
+        assert_eq!(pixel(&tia, 0, 99), background);
+        for column in 100..108 {
+            assert_eq!(pixel(&tia, 0, column), player1);
+        }
+        assert_eq!(pixel(&tia, 0, 108), background);
+    }
+
+    #[test]
+    fn test_nusize_modes() {
+        // The columns each mode draws a player with GRP0 of 1000_0001 at column 0
+        let cases: [(u8, &[usize]); 8] = [
+            (0x00, &[0, 7]),
+            (0x01, &[0, 7, 16, 23]),
+            (0x02, &[0, 7, 32, 39]),
+            (0x03, &[0, 7, 16, 23, 32, 39]),
+            (0x04, &[0, 7, 64, 71]),
+            (0x05, &[0, 1, 14, 15]),
+            (0x06, &[0, 7, 32, 39, 64, 71]),
+            (0x07, &[0, 1, 2, 3, 28, 29, 30, 31]),
+        ];
+        for (nusiz, columns) in cases {
+            let mut tia = Tia::default();
+            tia.set(0x09, 0x0E);
+            tia.set(0x06, 0x44);
+            // Missile bits don't change the player
+            tia.set(0x04, nusiz | 0x30);
+            tia.set(0x1B, 0b1000_0001);
+            tia.resp0 = DRAWING_START_COLUMN;
+            tia.tick(CLOCKS_PER_LINE * (DRAWING_START_ROW + 1));
+
+            let drawn: Vec<usize> = (0..DRAWING_COLUMNS)
+                .filter(|&column| pixel(&tia, 0, column) == color(0x44))
+                .collect();
+            assert_eq!(drawn, columns, "NUSIZ {nusiz:02X}");
+        }
+    }
+
+    #[test]
+    fn test_missiles() {
+        let mut tia = Tia::default();
+        tia.set(0x09, 0x0E);
+        tia.set(0x06, 0x44);
+        tia.set(0x07, 0x86);
+        // Missile 0 two clocks wide, missile 1 eight clocks wide in two close copies
+        tia.set(0x04, 0x10);
+        tia.set(0x05, 0x31);
+        tia.set(0x1D, 0x02);
+        tia.set(0x1E, 0x02);
+        tia.tick(30);
+        tia.set(0x12, 0);
+        tia.sync();
+        tia.resm1 = DRAWING_START_COLUMN + 100;
+        tia.tick(CLOCKS_PER_LINE * (DRAWING_START_ROW + 1) - 30);
+
+        let missile0 = tia.resm0 - DRAWING_START_COLUMN;
+        let background = color(0x0E);
+        assert_eq!(pixel(&tia, 0, missile0 - 1), background);
+        assert_eq!(pixel(&tia, 0, missile0), color(0x44));
+        assert_eq!(pixel(&tia, 0, missile0 + 1), color(0x44));
+        assert_eq!(pixel(&tia, 0, missile0 + 2), background);
+
+        let drawn: Vec<usize> = (0..DRAWING_COLUMNS)
+            .filter(|&column| pixel(&tia, 0, column) == color(0x86))
+            .collect();
+        let expected: Vec<usize> = (100..108).chain(116..124).collect();
+        assert_eq!(drawn, expected);
+    }
+
+    #[test]
+    fn test_collisions() {
+        let mut tia = Tia::default();
+        // Player 0 over the edge of the playfield in columns 0-15, and over player 1
+        tia.set(0x0D, 0xF0);
+        tia.set(0x1B, 0xFF);
+        tia.set(0x1C, 0xFF);
+        tia.resp0 = DRAWING_START_COLUMN + 10;
+        tia.resp1 = DRAWING_START_COLUMN + 16;
+        tia.tick(CLOCKS_PER_LINE);
+        assert_eq!(tia.get(CXP0FB as u16), Some(0b1000_0000));
+        assert_eq!(tia.get(CXP1FB as u16), Some(0));
+        assert_eq!(tia.get(CXPPMM as u16), Some(0b1000_0000));
+        assert_eq!(tia.get(CXBLPF as u16), Some(0));
+
+        // The ball over the playfield, and missile 0 over the ball
+        tia.set(0x1F, 0x02);
+        tia.set(0x1D, 0x02);
+        tia.resbl = DRAWING_START_COLUMN + 2;
+        tia.resm0 = DRAWING_START_COLUMN + 2;
+        tia.tick(CLOCKS_PER_LINE);
+        assert_eq!(tia.get(CXBLPF as u16), Some(0b1000_0000));
+        assert_eq!(tia.get(CXM0FB as u16), Some(0b1100_0000));
+        assert_eq!(tia.get(CXM0P as u16), Some(0));
+
+        // CXCLR resets every latch
+        tia.set(0x2C, 0);
+        for register in 0..8 {
+            assert_eq!(tia.get(register), Some(0));
+        }
+    }
+
+    #[test]
+    fn test_input_latch() {
+        let mut tia = Tia::default();
+        let press = WindowEvent::InputStart(InputType::Joystick1Button);
+        let release = WindowEvent::InputEnd(InputType::Joystick1Button);
+
+        // Without the latch the button reads as it is
+        tia.input_event(&press);
+        tia.input_event(&release);
+        assert_eq!(tia.get(INPT4), Some(0b1000_0000));
+
+        // With the latch a press sticks after the button is released
+        tia.set(0x01, 0x40);
+        assert_eq!(tia.get(INPT4), Some(0b1000_0000));
+        tia.input_event(&press);
+        tia.input_event(&release);
+        assert_eq!(tia.get(INPT4), Some(0));
+
+        // Kernels write VBLANK every frame, which keeps the press as long as D6 stays set
+        tia.set(0x01, 0x40);
+        assert_eq!(tia.get(INPT4), Some(0));
+        tia.set(0x01, 0x42);
+        assert_eq!(tia.get(INPT4), Some(0));
+
+        // Clearing D6 resets the latch
+        tia.set(0x01, 0x00);
+        assert_eq!(tia.get(INPT4), Some(0b1000_0000));
+
+        // Dumping the ports grounds the paddle lines
+        tia.input_event(&WindowEvent::InputStart(InputType::Joystick1ButtonB));
+        assert_eq!(tia.get(INPT1), Some(0b1000_0000));
+        tia.set(0x01, 0x80);
+        assert_eq!(tia.get(INPT1), Some(0));
+    }
+
+    #[test]
+    fn test_audio_samples() {
+        let mut tia = Tia::default();
+        // A pure tone on channel 0 at full volume, toggling every audio clock
+        tia.set(0x15, 0x04);
+        tia.set(0x19, 0x0F);
+        tia.tick(CLOCKS_PER_LINE * 2);
+        assert_eq!(
+            tia.take_audio_samples(),
+            [audio::mix(15, 0), 0, audio::mix(15, 0), 0]
+        );
+        assert!(tia.take_audio_samples().is_empty());
+
+        // Samples stop piling up when nothing takes them
+        tia.tick(CLOCKS_PER_FRAME * 120);
+        assert_eq!(tia.take_audio_samples().len(), MAX_AUDIO_SAMPLES);
+    }
+
+    #[test]
+    fn test_player0_draws_over_player1() {
+        let mut tia = Tia::default();
+        tia.set(0x06, 0x44);
+        tia.set(0x07, 0x86);
+        tia.set(0x1B, 0xFF);
+        tia.set(0x1C, 0xFF);
+        tia.resp0 = DRAWING_START_COLUMN + 10;
+        tia.resp1 = DRAWING_START_COLUMN + 14;
+        tia.tick(CLOCKS_PER_LINE * (DRAWING_START_ROW + 1));
+
+        assert_eq!(pixel(&tia, 0, 17), color(0x44));
+        assert_eq!(pixel(&tia, 0, 18), color(0x86));
+    }
+
+    #[test]
+    fn test_resp1_uses_beam_position() {
+        let mut tia = Tia::default();
+        tia.tick(30);
+        tia.set(0x11, 0);
+        tia.sync();
+        assert_eq!(tia.resp1, 30 * COLOR_CLOCKS_PER_SYSTEM_CLOCK + 6);
+        assert_eq!(tia.resp0, 0);
+    }
+
+    #[test]
+    fn test_hmove_moves_every_object() {
+        let mut tia = Tia::default();
+        for position in [
+            &mut tia.resp0,
+            &mut tia.resp1,
+            &mut tia.resm0,
+            &mut tia.resm1,
+            &mut tia.resbl,
+        ] {
+            *position = DRAWING_START_COLUMN + 80;
+        }
+        // -8 moves player 0 right, 7 moves player 1 left
+        tia.set(0x20, 0x80);
+        tia.set(0x21, 0x70);
+        tia.set(0x22, 0x10);
+        tia.set(0x23, 0xF0);
+        tia.set(0x24, 0x20);
+        tia.set(0x2A, 0);
+        assert_eq!(tia.resp0, DRAWING_START_COLUMN + 88);
+        assert_eq!(tia.resp1, DRAWING_START_COLUMN + 73);
+        assert_eq!(tia.resm0, DRAWING_START_COLUMN + 79);
+        assert_eq!(tia.resm1, DRAWING_START_COLUMN + 81);
+        assert_eq!(tia.resbl, DRAWING_START_COLUMN + 78);
+
+        // HMCLR stops all motion
+        tia.set(0x2B, 0);
+        tia.set(0x2A, 0);
+        assert_eq!(tia.resp0, DRAWING_START_COLUMN + 88);
+        assert_eq!(tia.resbl, DRAWING_START_COLUMN + 78);
+    }
+
+    #[test]
+    fn test_read_modify_write_strobes_twice() {
+        use crate::system::System;
+
+        let run = |program: &[u8]| {
+            let mut rom = [0; 4096];
+            rom[..program.len()].copy_from_slice(program);
+            let mut system = System::new(rom);
+            system.tia.resp0 = DRAWING_START_COLUMN + 80;
+            system.tia.set(0x20, 0x10);
+            system.step().unwrap();
+            system.tia.resp0
+        };
+        // STA HMOVE strobes it once
+        assert_eq!(run(&[0x85, 0x2A]), DRAWING_START_COLUMN + 79);
+        // INC HMOVE writes back what it read before the result, moving the player twice
+        assert_eq!(run(&[0xE6, 0x2A]), DRAWING_START_COLUMN + 78);
+    }
+
+    #[test]
+    fn test_repeated_hmove() {
+        let mut tia = Tia::default();
+        tia.set(0x09, 0x0E);
+        tia.set(0x06, 0x44);
+        tia.set(0x04, 0x07);
+        tia.set(0x1B, 0xFF);
+        tia.resp0 = DRAWING_START_COLUMN + 80;
+        tia.tick(CLOCKS_PER_LINE * DRAWING_START_ROW);
+
+        // Two to the left, then one to the right on the next line
+        for motion in [0x20, 0xF0] {
+            tia.set(0x20, motion);
+            tia.set(0x2A, 0);
+            tia.tick(CLOCKS_PER_LINE);
+        }
+
+        let pixel = |row: usize, column: usize| {
+            let start = (row * WIDTH as usize + column) * STRIDE;
+            &tia.buffer.0[start..start + STRIDE]
+        };
+        let black = color(0);
+        let background = color(0x0E);
+        let player = color(0x44);
+        for row in 0..2 {
+            for column in 0..HMOVE_BLANK_COLUMNS {
+                assert_eq!(pixel(row, column), black);
+            }
+            assert_eq!(pixel(row, HMOVE_BLANK_COLUMNS), background);
+        }
+
+        assert_eq!(pixel(0, 77), background);
+        assert_eq!(pixel(0, 78), player);
+        assert_eq!(pixel(1, 78), background);
+        assert_eq!(pixel(1, 79), player);
+    }
+}
@@ -0,0 +1,180 @@
+use std::str::FromStr;
+
+use super::colors::{NtscColor, NTSC_PALETTE, PAL_PALETTE, SECAM_PALETTE};
+use super::tia::CLOCKS_PER_LINE;
+use super::System;
+
+/// PAL frames are 312 lines and NTSC frames are 262 lines. Anything closer to PAL is treated as
+/// PAL.
+const PAL_THRESHOLD_LINES: usize = 287;
+/// Enough time to see a few PAL frames
+const DETECTION_CLOCKS: usize = 312 * CLOCKS_PER_LINE * 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TvStandard {
+    #[default]
+    Ntsc,
+    Pal,
+    /// SECAM has PAL's timing, but its own palette of eight colors
+    Secam,
+}
+
+impl TvStandard {
+    /// The number of lines in a frame
+    pub const fn scan_lines(&self) -> usize {
+        match self {
+            Self::Ntsc => 262,
+            Self::Pal | Self::Secam => 312,
+        }
+    }
+
+    /// The line the picture starts on, after the 3 lines of VSYNC and the vertical blank
+    pub const fn drawing_start_row(&self) -> usize {
+        match self {
+            Self::Ntsc => 40,
+            Self::Pal | Self::Secam => 48,
+        }
+    }
+
+    /// The colors the console makes, indexed by a color register's value shifted right once
+    pub fn palette(&self) -> &'static [NtscColor; 128] {
+        match self {
+            Self::Ntsc => &NTSC_PALETTE,
+            Self::Pal => &PAL_PALETTE,
+            Self::Secam => &SECAM_PALETTE,
+        }
+    }
+
+    /// The color for a value of a color register. D0 isn't connected, so odd values are the same
+    /// color as the even value below them.
+    pub fn color(&self, value: u8) -> &'static NtscColor {
+        &self.palette()[value as usize >> 1]
+    }
+}
+
+impl FromStr for TvStandard {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.to_lowercase().as_str() {
+            "ntsc" => TvStandard::Ntsc,
+            "pal" => TvStandard::Pal,
+            "secam" => TvStandard::Secam,
+            _ => return Err(format!("Unknown TV standard: {s}")),
+        })
+    }
+}
+
+/// Guesses the TV standard a program was written for by running it for a few frames and
+/// measuring the time between VSYNCs. Falls back to NTSC when the program doesn't produce a
+/// steady frame in that time. SECAM programs time their frames like PAL ones, so they're detected
+/// as PAL.
+pub fn detect_tv_standard(program: &[u8; 4096]) -> TvStandard {
+    let mut system = System::new(*program);
+    let mut vsync_starts = vec![];
+    let mut in_vsync = false;
+
+    while system.clocks < DETECTION_CLOCKS && vsync_starts.len() < 3 {
+        if system.step().is_err() {
+            break;
+        }
+        let vsync = system.tia.is_vsync();
+        if vsync && !in_vsync {
+            vsync_starts.push(system.clocks);
+        }
+        in_vsync = vsync;
+    }
+
+    // The first frame is often irregular while the program is starting up, so use the last one
+    match vsync_starts.windows(2).last() {
+        Some(&[start, end]) if (end - start) / CLOCKS_PER_LINE > PAL_THRESHOLD_LINES => {
+            TvStandard::Pal
+        }
+        _ => TvStandard::Ntsc,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A kernel that does nothing but produce frames of `lines` lines, 3 of them VSYNC
+    fn kernel(lines: usize) -> [u8; 4096] {
+        let remaining = (lines - 3 - 255) as u8;
+        let mut program = [0; 4096];
+        #[rustfmt::skip]
+        let code = [
+            0xA9, 0x02,       // LDA #2
+            0x85, 0x00,       // STA VSYNC
+            0x85, 0x02,       // STA WSYNC
+            0x85, 0x02,       // STA WSYNC
+            0x85, 0x02,       // STA WSYNC
+            0xA9, 0x00,       // LDA #0
+            0x85, 0x00,       // STA VSYNC
+            0xA2, 0xFF,       // LDX #255
+            0x85, 0x02,       // STA WSYNC
+            0xCA,             // DEX
+            0xD0, 0xFB,       // BNE -5
+            0xA2, remaining,  // LDX #remaining
+            0x85, 0x02,       // STA WSYNC
+            0xCA,             // DEX
+            0xD0, 0xFB,       // BNE -5
+            0x4C, 0x00, 0x10, // JMP $1000
+        ];
+        program[..code.len()].copy_from_slice(&code);
+        program
+    }
+
+    #[test]
+    fn test_detect_ntsc() {
+        assert_eq!(detect_tv_standard(&kernel(262)), TvStandard::Ntsc);
+    }
+
+    #[test]
+    fn test_detect_pal() {
+        assert_eq!(detect_tv_standard(&kernel(312)), TvStandard::Pal);
+    }
+
+    #[test]
+    fn test_frame_lengths() {
+        assert_eq!(TvStandard::Ntsc.scan_lines() * CLOCKS_PER_LINE, 19_912);
+        assert_eq!(TvStandard::Pal.scan_lines() * CLOCKS_PER_LINE, 23_712);
+        assert_eq!(TvStandard::Secam.scan_lines(), TvStandard::Pal.scan_lines());
+    }
+
+    #[test]
+    fn test_palettes() {
+        let color = |tv: TvStandard, value: u8| tv.color(value).as_slice();
+        // Hue 2 is gold on PAL and orange on NTSC, and the first and last PAL hues are gray
+        assert_ne!(color(TvStandard::Ntsc, 0x24), color(TvStandard::Pal, 0x24));
+        assert_eq!(color(TvStandard::Pal, 0x16), color(TvStandard::Pal, 0x06));
+        assert_eq!(color(TvStandard::Pal, 0xF6), color(TvStandard::Pal, 0x06));
+        // SECAM only looks at the luminance
+        assert_eq!(
+            color(TvStandard::Secam, 0x44),
+            color(TvStandard::Secam, 0x04)
+        );
+        assert_eq!(color(TvStandard::Secam, 0x0E), [0xFF, 0xFF, 0xFF, 0xFF]);
+        assert_eq!(color(TvStandard::Secam, 0x02), [0xFF, 0x21, 0x21, 0xFF]);
+    }
+
+    #[test]
+    fn test_d0_is_ignored() {
+        for tv in [TvStandard::Ntsc, TvStandard::Pal, TvStandard::Secam] {
+            assert!(std::ptr::eq(tv.color(0x1F), tv.color(0x1E)));
+            assert!(std::ptr::eq(tv.color(0x1E), &tv.palette()[0x0F]));
+        }
+        assert_eq!(
+            TvStandard::Ntsc.color(0xFF).as_slice(),
+            [0x8C, 0xE0, 0xFC, 0xFF]
+        );
+    }
+
+    #[test]
+    fn test_detect_defaults_to_ntsc() {
+        // An infinite loop that never syncs
+        let mut program = [0; 4096];
+        program[0..3].copy_from_slice(&[0x4C, 0x00, 0x10]);
+        assert_eq!(detect_tv_standard(&program), TvStandard::Ntsc);
+    }
+}
@@ -1,5 +1,7 @@
 use std::time::Duration;
 
+use crate::system::tia::CLOCK_NANOS;
+
 #[cfg(test)]
 use fake_clock::FakeClock as Instant;
 #[cfg(not(test))]
@@ -13,18 +15,37 @@ fn sleep(durr: Duration) {
     FakeClock::advance_time(durr.as_millis() as u64);
 }
 
+/// When to show the picture
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum RenderMode {
+    /// Every 16 milliseconds, wherever the beam is. Changes made mid-frame can tear.
+    WallClock,
+    /// Each time the TIA finishes a frame, at most at the TV's refresh rate
+    #[default]
+    FrameComplete,
+}
+
 pub struct Timer {
+    // When the last frame was finished
     instant: Instant,
     render_instant: Instant,
-    pub runover: Duration,
+    // How long a frame takes on the real console
+    frame_time: Duration,
+    // Nanoseconds the emulator is ahead of the real console, negative when it's behind. What
+    // the sleeps miss by carries over to the next frame, so the pace evens out.
+    ahead: i64,
+    // Run as fast as possible instead of at the speed of the real console
+    turbo: bool,
 }
 
 impl Timer {
-    pub fn start() -> Self {
+    pub fn start(clocks_per_frame: usize) -> Self {
         Timer {
             instant: Instant::now(),
             render_instant: Instant::now(),
-            runover: Duration::ZERO,
+            frame_time: Duration::from_nanos((clocks_per_frame * CLOCK_NANOS) as u64),
+            ahead: 0,
+            turbo: false,
         }
     }
 
@@ -36,61 +57,156 @@ impl Timer {
         self.render_instant.elapsed() > Duration::from_millis(16)
     }
 
+    /// Whether to show the picture now, given whether a frame was just finished. The debugger
+    /// moves the beam slowly, so it always shows its progress through the frame.
+    pub fn should_render_frame(&self, mode: RenderMode, frame_ready: bool, debug: bool) -> bool {
+        match mode {
+            RenderMode::WallClock => self.should_render(),
+            RenderMode::FrameComplete => {
+                (frame_ready && self.refresh_due()) || (debug && self.should_render())
+            }
+        }
+    }
+
+    /// Whether a frame's worth of time has passed since the last render, less some slack for
+    /// frames that finish a little early
+    fn refresh_due(&self) -> bool {
+        self.render_instant.elapsed() >= self.frame_time * 9 / 10
+    }
+
     pub fn did_render(&mut self) {
         self.render_instant = Instant::now();
     }
 
-    // TODO: Fix this thing I didn't expect to happen where instructions seem to take longer than
-    // they should
-    pub fn pause_for(&mut self, dur: Duration) {
-        let elapsed = self.elapsed();
-        if dur < elapsed {
-            self.runover += elapsed - dur;
-        }
-        let actual = dur.saturating_sub(elapsed);
-        if self.runover < actual {
-            let should_sleep = actual - self.runover;
-            let now = Instant::now();
-
-            sleep(should_sleep);
-            self.runover = now.elapsed() - should_sleep;
-        } else {
-            self.runover -= actual;
-        }
+    /// Starts timing again after the emulator was stopped, without catching up on the time it
+    /// was stopped for.
+    pub fn resume(&mut self) {
+        self.ahead = 0;
+        self.instant = Instant::now();
+    }
+
+    /// Turns turbo on or off. Time spent in turbo isn't caught up on once it's turned off.
+    pub fn set_turbo(&mut self, turbo: bool) {
+        self.turbo = turbo;
+    }
 
+    /// Called once a frame is finished. Sleeps for however far the emulator got ahead of the real
+    /// console. When it falls behind, the next frames don't sleep until it has caught up, but it
+    /// never tries to catch up on more than a frame, so a stall doesn't turn into a rush.
+    pub fn end_frame(&mut self) {
+        if self.turbo {
+            self.resume();
+            return;
+        }
+        let elapsed = self.elapsed();
         self.instant = Instant::now();
+
+        let frame_nanos = self.frame_time.as_nanos() as i64;
+        self.ahead = (self.ahead + frame_nanos - elapsed.as_nanos() as i64).max(-frame_nanos);
+        // The sleep counts towards the next frame's elapsed time, along with whatever it overshot
+        if self.ahead > 0 {
+            sleep(Duration::from_nanos(self.ahead as u64));
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use fake_clock::FakeClock;
+
+    // NTSC, a 60th of a second
+    const CLOCKS_PER_FRAME: usize = 19_912;
 
     #[test]
     fn test_start_a_timer() {
-        use fake_clock::FakeClock;
-        let timer = Timer::start();
+        let timer = Timer::start(CLOCKS_PER_FRAME);
         let earlier = timer.elapsed();
         FakeClock::advance_time(10);
         assert!(timer.elapsed() > earlier);
     }
 
     #[test]
-    // NOTE: Fake clock does not support times less than 1 millisecond these times are unrealistic
-    // for our use case
-    fn test_pause_for() {
-        use fake_clock::FakeClock;
-        let mut timer = Timer::start();
+    // NOTE: Fake clock only has millisecond precision, so a frame sleeps for 16 or 17
+    // milliseconds instead of 16.67
+    fn test_frames_keep_real_time() {
+        let mut timer = Timer::start(CLOCKS_PER_FRAME);
+        let now = Instant::now();
+        for _ in 0..600 {
+            // Some time spent emulating the frame
+            FakeClock::advance_time(5);
+            timer.end_frame();
+        }
+        // Ten seconds, give or take the last frame
+        let elapsed = now.elapsed().as_millis();
+        assert!((9_980..=10_020).contains(&elapsed), "{elapsed}");
+    }
+
+    #[test]
+    fn test_slow_frames_are_caught_up() {
+        let mut timer = Timer::start(CLOCKS_PER_FRAME);
         let now = Instant::now();
-        timer.pause_for(Duration::from_millis(83_700));
+        // A slow frame, then the next one doesn't sleep to make up for it
+        FakeClock::advance_time(25);
+        timer.end_frame();
         FakeClock::advance_time(10);
-        assert!(now.elapsed() > Duration::from_millis(83_700));
+        timer.end_frame();
+        assert_eq!(now.elapsed(), Duration::from_millis(35));
+        // Back on pace after that
+        for _ in 0..58 {
+            timer.end_frame();
+        }
+        let elapsed = now.elapsed().as_millis();
+        assert!((990..=1_010).contains(&elapsed), "{elapsed}");
+
+        // A stall isn't caught up on past a frame
+        FakeClock::advance_time(1_000);
+        timer.end_frame();
+        let stalled = Instant::now();
+        for _ in 0..60 {
+            timer.end_frame();
+        }
+        assert!(stalled.elapsed() > Duration::from_millis(970));
     }
 
     #[test]
-    fn test_pause_for_too_long() {
-        let mut timer = Timer::start();
-        timer.runover = Duration::from_millis(20);
-        timer.pause_for(Duration::from_millis(10));
+    fn test_should_render_frame() {
+        let mut timer = Timer::start(CLOCKS_PER_FRAME);
+        timer.did_render();
+        FakeClock::advance_time(10);
+        // Halfway through the wall clock, and a frame finished early, like in turbo
+        assert!(!timer.should_render_frame(RenderMode::WallClock, true, false));
+        assert!(!timer.should_render_frame(RenderMode::FrameComplete, true, false));
+
+        FakeClock::advance_time(7);
+        // Wall clock shows the picture whether or not the frame is done
+        assert!(timer.should_render_frame(RenderMode::WallClock, false, false));
+        assert!(timer.should_render_frame(RenderMode::WallClock, true, false));
+        // Frame complete waits for the frame
+        assert!(!timer.should_render_frame(RenderMode::FrameComplete, false, false));
+        assert!(timer.should_render_frame(RenderMode::FrameComplete, true, false));
+        // Unless the debugger is stepping through it
+        assert!(timer.should_render_frame(RenderMode::FrameComplete, false, true));
+
+        timer.did_render();
+        assert!(!timer.should_render_frame(RenderMode::FrameComplete, true, true));
+    }
+
+    #[test]
+    fn test_turbo_skips_sleep() {
+        let mut timer = Timer::start(CLOCKS_PER_FRAME);
+        timer.set_turbo(true);
+        let now = Instant::now();
+        for _ in 0..60 {
+            timer.end_frame();
+        }
+        assert_eq!(now.elapsed(), Duration::ZERO);
+        assert_eq!(timer.ahead, 0);
+
+        timer.set_turbo(false);
+        for _ in 0..60 {
+            timer.end_frame();
+        }
+        assert!(now.elapsed() >= Duration::from_millis(990));
     }
 }
@@ -0,0 +1,234 @@
+use std::fmt;
+use std::iter::Peekable;
+use std::str::{Chars, FromStr};
+
+use crate::system::System;
+
+/// An expression watched in the debugger, like `[$80] + [$81] * 256` for a 16 bit pointer.
+///
+/// Supports memory reads `[addr]`, the registers `a`, `x`, `y`, `pc`, and `sp`, hex literals
+/// written `$FF` or `0xFF`, decimal literals, `+`, `-`, `*`, and parentheses.
+#[derive(Debug, PartialEq)]
+pub struct Watch {
+    source: String,
+    expression: Expression,
+}
+
+impl Watch {
+    pub fn eval(&self, system: &System) -> i64 {
+        self.expression.eval(system)
+    }
+}
+
+impl FromStr for Watch {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            chars: s.chars().peekable(),
+        };
+        let expression = parser.expression()?;
+        parser.skip_whitespace();
+        if let Some(c) = parser.chars.next() {
+            return Err(format!("Unexpected '{c}' in watch expression"));
+        }
+        Ok(Self {
+            source: s.trim().to_owned(),
+            expression,
+        })
+    }
+}
+
+impl fmt::Display for Watch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.source)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Register {
+    A,
+    X,
+    Y,
+    Pc,
+    Sp,
+}
+
+#[derive(Debug, PartialEq)]
+enum Expression {
+    Literal(i64),
+    Register(Register),
+    Memory(Box<Expression>),
+    Add(Box<Expression>, Box<Expression>),
+    Sub(Box<Expression>, Box<Expression>),
+    Mul(Box<Expression>, Box<Expression>),
+}
+
+impl Expression {
+    fn eval(&self, system: &System) -> i64 {
+        match self {
+            Self::Literal(value) => *value,
+            Self::Register(Register::A) => system.chip.a as i64,
+            Self::Register(Register::X) => system.chip.x as i64,
+            Self::Register(Register::Y) => system.chip.y as i64,
+            Self::Register(Register::Pc) => system.chip.pc as i64,
+            Self::Register(Register::Sp) => system.chip.sp as i64,
+            Self::Memory(addr) => system.peek(addr.eval(system) as u16) as i64,
+            Self::Add(lhs, rhs) => lhs.eval(system).wrapping_add(rhs.eval(system)),
+            Self::Sub(lhs, rhs) => lhs.eval(system).wrapping_sub(rhs.eval(system)),
+            Self::Mul(lhs, rhs) => lhs.eval(system).wrapping_mul(rhs.eval(system)),
+        }
+    }
+}
+
+struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+}
+
+impl<'a> Parser<'a> {
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_whitespace();
+        self.chars.peek().copied()
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.chars.next();
+                Ok(())
+            }
+            _ => Err(format!("Expected '{expected}' in watch expression")),
+        }
+    }
+
+    // expression = term (('+' | '-') term)*
+    fn expression(&mut self) -> Result<Expression, String> {
+        let mut lhs = self.term()?;
+        loop {
+            lhs = match self.peek() {
+                Some('+') => {
+                    self.chars.next();
+                    Expression::Add(Box::new(lhs), Box::new(self.term()?))
+                }
+                Some('-') => {
+                    self.chars.next();
+                    Expression::Sub(Box::new(lhs), Box::new(self.term()?))
+                }
+                _ => return Ok(lhs),
+            };
+        }
+    }
+
+    // term = factor ('*' factor)*
+    fn term(&mut self) -> Result<Expression, String> {
+        let mut lhs = self.factor()?;
+        while let Some('*') = self.peek() {
+            self.chars.next();
+            lhs = Expression::Mul(Box::new(lhs), Box::new(self.factor()?));
+        }
+        Ok(lhs)
+    }
+
+    // factor = '[' expression ']' | '(' expression ')' | number | register
+    fn factor(&mut self) -> Result<Expression, String> {
+        match self.peek() {
+            Some('[') => {
+                self.chars.next();
+                let addr = self.expression()?;
+                self.expect(']')?;
+                Ok(Expression::Memory(Box::new(addr)))
+            }
+            Some('(') => {
+                self.chars.next();
+                let expression = self.expression()?;
+                self.expect(')')?;
+                Ok(expression)
+            }
+            Some('$') => {
+                self.chars.next();
+                self.number(16)
+            }
+            Some(c) if c.is_ascii_digit() => self.number(10),
+            Some(c) if c.is_ascii_alphabetic() => self.register(),
+            Some(c) => Err(format!("Unexpected '{c}' in watch expression")),
+            None => Err("Unexpected end of watch expression".to_owned()),
+        }
+    }
+
+    fn word(&mut self) -> String {
+        let mut word = String::new();
+        while let Some(c) = self.chars.next_if(|c| c.is_ascii_alphanumeric()) {
+            word.push(c);
+        }
+        word
+    }
+
+    fn number(&mut self, radix: u32) -> Result<Expression, String> {
+        let word = self.word();
+        let (digits, radix) = match word.strip_prefix("0x") {
+            Some(digits) => (digits, 16),
+            None => (word.as_str(), radix),
+        };
+        i64::from_str_radix(digits, radix)
+            .map(Expression::Literal)
+            .map_err(|_| format!("Invalid number '{word}' in watch expression"))
+    }
+
+    fn register(&mut self) -> Result<Expression, String> {
+        let word = self.word();
+        let register = match word.to_lowercase().as_str() {
+            "a" => Register::A,
+            "x" => Register::X,
+            "y" => Register::Y,
+            "pc" => Register::Pc,
+            "sp" => Register::Sp,
+            _ => return Err(format!("Unknown register '{word}' in watch expression")),
+        };
+        Ok(Expression::Register(register))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(expression: &str, system: &System) -> i64 {
+        expression.parse::<Watch>().unwrap().eval(system)
+    }
+
+    #[test]
+    fn test_pointer() {
+        let mut system = System::new([0; 4096]);
+        system.memory[0] = 0x34;
+        system.memory[1] = 0x12;
+        assert_eq!(eval("[$80] + [$81] * 256", &system), 0x1234);
+        assert_eq!(eval("[0x81]*$100+[0x80]", &system), 0x1234);
+    }
+
+    #[test]
+    fn test_registers() {
+        let mut system = System::new([0; 4096]);
+        system.chip.a = 1;
+        system.chip.x = 2;
+        system.chip.y = 3;
+        system.chip.sp = 0xFD;
+        system.memory[0x7F] = 0x42;
+        assert_eq!(eval("a + x * y", &system), 7);
+        assert_eq!(eval("(a + x) * y", &system), 9);
+        assert_eq!(eval("PC - 1", &system), 0x0FFF);
+        assert_eq!(eval("[sp + 2]", &system), 0x42);
+    }
+
+    #[test]
+    fn test_invalid_expressions() {
+        assert!("[$80".parse::<Watch>().is_err());
+        assert!("q + 1".parse::<Watch>().is_err());
+        assert!("1 +".parse::<Watch>().is_err());
+        assert!("1 2".parse::<Watch>().is_err());
+        assert!("$G".parse::<Watch>().is_err());
+    }
+}
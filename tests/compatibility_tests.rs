@@ -0,0 +1,21 @@
+use stanley::compatibility::{Compatibility, CompatibilityReport};
+
+#[test]
+fn test_compatibility_report() {
+    let report = CompatibilityReport::check_directory("tests/roms", 2).unwrap();
+    let [(clean_name, clean), (unimplemented_name, unimplemented)] = &report.results[..] else {
+        panic!("Expected two fixture ROMs, found {:?}", report.results);
+    };
+
+    assert_eq!(clean_name, "clean.bin");
+    assert_eq!(*clean, Compatibility::Clean);
+    assert_eq!(unimplemented_name, "unimplemented.bin");
+    assert!(
+        matches!(unimplemented, Compatibility::Unimplemented(message) if message.contains("RIOT write")),
+        "{unimplemented:?}"
+    );
+
+    let failures = report.failures();
+    assert_eq!(failures.len(), 1);
+    assert_eq!(failures[0].1, vec!["unimplemented.bin"]);
+}
@@ -4,9 +4,9 @@ use std::path::Path;
 use std::str::FromStr;
 use std::string::String;
 
-use stanley::renderer::WindowEvent;
-use stanley::system::tia::{BUFF_SIZE, HEIGHT, STRIDE, WIDTH};
-use stanley::system::{instructions::Instruction, System};
+use stanley::input::WindowEvent;
+use stanley::system::tia::{Buffer, BUFF_SIZE, HEIGHT, WIDTH};
+use stanley::system::System;
 use stanley::Result;
 
 const OUTPUT_WIDTH: usize = WIDTH as usize * OUTPUT_PIXEL_WIDTH;
@@ -14,6 +14,8 @@ const OUTPUT_HEIGHT: usize = HEIGHT as usize * OUTPUT_PIXEL_HEIGHT;
 const OUTPUT_STRIDE: usize = 3;
 const OUTPUT_PIXEL_HEIGHT: usize = 4;
 const OUTPUT_PIXEL_WIDTH: usize = 6;
+/// How many of the differing pixels to print when a snapshot doesn't match
+const REPORTED_DIFFERENCES: usize = 10;
 
 include!(concat!(env!("OUT_DIR"), "/tests.rs"));
 
@@ -80,21 +82,23 @@ fn test_snapshot(snapshot_path: impl AsRef<Path>) {
                 break;
             }
 
-            let instruction: Instruction = system.next_byte().try_into().unwrap();
-            system.execute(instruction).unwrap();
+            system.step().unwrap();
         }
     }
 
     let any_differences = screen != screen_actual;
+    let mut different_pixels = 0;
     if any_differences {
-        let buffer = screen
-            .chunks(STRIDE)
-            .zip(screen_actual.chunks(STRIDE))
+        let expected = Buffer(screen).as_rgb();
+        let actual = Buffer(screen_actual).as_rgb();
+        different_pixels = report_differences(&expected, &actual);
+        let buffer = expected
+            .chunks(OUTPUT_STRIDE)
+            .zip(actual.chunks(OUTPUT_STRIDE))
             .map(|(expected, actual)| {
-                (
-                    [expected[2], expected[1], expected[0]],
-                    [actual[2], actual[1], actual[0]],
-                )
+                let expected: [u8; OUTPUT_STRIDE] = expected.try_into().unwrap();
+                let actual: [u8; OUTPUT_STRIDE] = actual.try_into().unwrap();
+                (expected, actual)
             })
             .map(|(expected, actual)| {
                 if expected == actual {
@@ -146,11 +150,40 @@ fn test_snapshot(snapshot_path: impl AsRef<Path>) {
 
     assert!(
         !any_differences,
-        "Unexpected image output, view diff at ./artifacts/{}.ppm",
-        test_name
+        "Unexpected image output, {} pixels differ, view diff at ./artifacts/{}.ppm",
+        different_pixels, test_name
     )
 }
 
+/// Prints the first few pixels that differ, with the expected and actual RGB colors, and returns
+/// how many pixels differ in total
+fn report_differences(expected: &[u8], actual: &[u8]) -> usize {
+    let differences: Vec<_> = expected
+        .chunks(OUTPUT_STRIDE)
+        .zip(actual.chunks(OUTPUT_STRIDE))
+        .enumerate()
+        .filter(|(_, (expected, actual))| expected != actual)
+        .collect();
+    eprintln!("{} pixels differ", differences.len());
+    for (index, (expected, actual)) in differences.iter().take(REPORTED_DIFFERENCES) {
+        eprintln!(
+            "  ({}, {}): expected #{:02X}{:02X}{:02X}, got #{:02X}{:02X}{:02X}",
+            index % WIDTH as usize,
+            index / WIDTH as usize,
+            expected[0],
+            expected[1],
+            expected[2],
+            actual[0],
+            actual[1],
+            actual[2],
+        );
+    }
+    if differences.len() > REPORTED_DIFFERENCES {
+        eprintln!("  ...");
+    }
+    differences.len()
+}
+
 fn create_ppm(buffer: &[u8], output_name: &str) -> Result<()> {
     fs::create_dir_all("./artifacts")?;
 
@@ -0,0 +1,24 @@
+// These only use the emulator, so they also run without SDL:
+// cargo test --no-default-features --test library_tests
+
+use stanley::input::{InputType, WindowEvent};
+use stanley::system::System;
+
+#[test]
+fn test_step_without_sdl() {
+    let mut program = [0; 4096];
+    // LDA #$42; STA $80; JMP $F004
+    program[0..7].copy_from_slice(&[0xA9, 0x42, 0x85, 0x80, 0x4C, 0x04, 0xF0]);
+    let mut system = System::new(program);
+    for _ in 0..3 {
+        system.step().unwrap();
+    }
+    assert_eq!(system.chip.a, 0x42);
+    assert_eq!(system.memory_get(0x80), 0x42);
+    assert_eq!(system.chip.pc, 0xF004);
+    assert_eq!(system.clocks, 8);
+
+    // Input still reaches the console
+    system.input_event(&WindowEvent::InputStart(InputType::Joystick1Up));
+    assert_eq!(system.memory_get(0x0280), 0b1110_1111);
+}
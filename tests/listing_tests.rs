@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+use std::fs;
+
+use stanley::system::instructions::Instruction;
+
+/// A line of an assembler listing, `ADDR: BYTES MNEMONIC OPERAND`
+#[derive(Debug)]
+struct ListingLine {
+    addr: u16,
+    bytes: Vec<u8>,
+    text: String,
+}
+
+#[derive(Debug, PartialEq)]
+struct Divergence {
+    addr: u16,
+    expected: String,
+    actual: String,
+}
+
+/// Parses a listing. Blank lines and lines starting with `;` are skipped.
+fn parse_listing(listing: &str) -> Vec<ListingLine> {
+    listing
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with(';'))
+        .map(|line| {
+            let (addr, rest) = line.split_once(':').expect("Listing line missing address");
+            let addr = u16::from_str_radix(addr.trim(), 16).expect("Invalid listing address");
+            let mut words = rest.split_whitespace().peekable();
+            let mut bytes = vec![];
+            while let Some(byte) = words
+                .peek()
+                .filter(|word| word.len() == 2)
+                .and_then(|word| u8::from_str_radix(word, 16).ok())
+            {
+                bytes.push(byte);
+                words.next();
+            }
+            let text = words.collect::<Vec<_>>().join(" ").to_uppercase();
+            ListingLine { addr, bytes, text }
+        })
+        .collect()
+}
+
+/// Disassembles the instruction at `addr`, returning its bytes and text
+fn disassemble_at(program: &[u8; 4096], addr: u16) -> (Vec<u8>, String) {
+    let index = (addr & 0x0FFF) as usize;
+    let inst = match Instruction::try_from(program[index]) {
+        Ok(inst) => inst,
+        Err(byte) => return (vec![byte], format!(".byte ${byte:02X}")),
+    };
+    let length = 1 + inst.address_mode().operand_bytes();
+    let bytes = program[index..(index + length).min(program.len())].to_vec();
+    let mut operands = program.iter().enumerate().skip(index + 1);
+    let arguments = inst.format_arguments(&mut operands, &HashMap::new(), addr);
+    let text = format!("{inst} {arguments}");
+    (bytes, text.trim().to_owned())
+}
+
+/// Compares the disassembly of `program` against an assembler listing, returning the first line
+/// where they differ
+fn compare_listing(program: &[u8; 4096], listing: &str) -> Option<Divergence> {
+    parse_listing(listing).into_iter().find_map(|line| {
+        let (bytes, text) = disassemble_at(program, line.addr);
+        let format = |bytes: &[u8], text: &str| {
+            let bytes: Vec<String> = bytes.iter().map(|byte| format!("{byte:02X}")).collect();
+            format!("{} {text}", bytes.join(" "))
+        };
+        if bytes != line.bytes || text != line.text {
+            Some(Divergence {
+                addr: line.addr,
+                expected: format(&line.bytes, &line.text),
+                actual: format(&bytes, &text),
+            })
+        } else {
+            None
+        }
+    })
+}
+
+fn tiny_program() -> [u8; 4096] {
+    fs::read("./tests/listings/tiny.bin")
+        .unwrap()
+        .try_into()
+        .unwrap()
+}
+
+#[test]
+fn test_listing_matches() {
+    let listing = fs::read_to_string("./tests/listings/tiny.lst").unwrap();
+    let divergence = compare_listing(&tiny_program(), &listing);
+    assert!(divergence.is_none(), "{divergence:?}");
+}
+
+#[test]
+fn test_listing_divergence() {
+    let listing = "
+        1000: A2 05     LDX #$05
+        1002: CA        DEX
+        1003: D0 FD     BNE $1003
+        1005: 85 80     STA $80
+    ";
+    assert_eq!(
+        compare_listing(&tiny_program(), listing),
+        Some(Divergence {
+            addr: 0x1003,
+            expected: "D0 FD BNE $1003".to_owned(),
+            actual: "D0 FD BNE $1002".to_owned(),
+        })
+    );
+}